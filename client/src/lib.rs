@@ -0,0 +1,645 @@
+//! Building, signing and encoding extrinsics for the substrate-demo runtime.
+//!
+//! Extrinsics are built from the runtime's own `Call` and `SignedExtra` types, so they always
+//! match what the node expects. Submit the SCALE-encoded bytes with `author_submitExtrinsic`.
+
+#![warn(missing_docs)]
+
+use codec::{Decode, Encode};
+use sp_core::Pair;
+use sp_runtime::{MultiSignature, MultiSigner, generic::{Era, SignedPayload}, traits::IdentifyAccount};
+use substrate_demo_runtime::{
+	AccountId, Balance, BlockNumber, Event, EventRecord, Hash, Index, SignedExtra, VERSION,
+	fee_quota, pause, storage_keys,
+};
+
+pub use substrate_demo_runtime::{Call, UncheckedExtrinsic};
+
+/// The inputs of the signed extensions, i.e. everything that is signed besides the call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SigningParams {
+	/// Hash of the chain's genesis block, the `CheckGenesis` input.
+	pub genesis_hash: Hash,
+	/// Spec version of the runtime the extrinsic is for, the `CheckVersion` input.
+	pub spec_version: u32,
+	/// The signer's next nonce, the `CheckNonce` input.
+	pub nonce: Index,
+	/// The period the extrinsic is valid for, the `CheckEra` input.
+	pub era: Era,
+	/// Hash of the block `era` starts at, the genesis hash for immortal extrinsics.
+	pub era_hash: Hash,
+	/// Tip paid to the block author on top of the fee; members of `vec_set` that tip nothing use
+	/// their fee-free quota first.
+	pub tip: Balance,
+}
+
+impl SigningParams {
+	/// Parameters of an extrinsic that stays valid forever, for this crate's runtime version.
+	pub fn immortal(genesis_hash: Hash, nonce: Index) -> Self {
+		SigningParams {
+			genesis_hash,
+			spec_version: VERSION.spec_version,
+			nonce,
+			era: Era::Immortal,
+			era_hash: genesis_hash,
+			tip: 0,
+		}
+	}
+
+	/// Parameters of an extrinsic that is valid from block `birth_hash` on, see `mortal_era`.
+	pub fn mortal(genesis_hash: Hash, nonce: Index, era: Era, birth_hash: Hash) -> Self {
+		SigningParams {
+			era,
+			era_hash: birth_hash,
+			..Self::immortal(genesis_hash, nonce)
+		}
+	}
+
+	fn extra(&self) -> SignedExtra {
+		(
+			::system::CheckVersion::new(),
+			::system::CheckGenesis::new(),
+			::system::CheckEra::from(self.era),
+			::system::CheckNonce::from(self.nonce),
+			::system::CheckWeight::new(),
+			pause::CheckPaused::new(),
+			fee_quota::ChargeTransactionPaymentOrQuota::from(self.tip),
+		)
+	}
+
+	fn additional_signed(&self) -> (u32, Hash, Hash, (), (), (), ()) {
+		(self.spec_version, self.genesis_hash, self.era_hash, (), (), (), ())
+	}
+}
+
+/// The era of an extrinsic valid for about `period` blocks from block `current` on.
+///
+/// Also returns the number of the block the era starts at, whose hash goes into
+/// `SigningParams::mortal`.
+pub fn mortal_era(period: u64, current: BlockNumber) -> (Era, BlockNumber) {
+	let era = Era::mortal(period, current.into());
+	(era, era.birth(current.into()) as BlockNumber)
+}
+
+/// The account of a key pair.
+pub fn account_id<P>(pair: &P) -> AccountId where
+	P: Pair,
+	P::Public: Into<MultiSigner>,
+{
+	let signer: MultiSigner = pair.public().into();
+	signer.into_account()
+}
+
+/// Sign `call` with `pair`, an sr25519 or ed25519 key pair.
+pub fn sign<P>(pair: &P, call: Call, params: &SigningParams) -> UncheckedExtrinsic where
+	P: Pair,
+	P::Public: Into<MultiSigner>,
+	P::Signature: Into<MultiSignature>,
+{
+	let payload = SignedPayload::from_raw(call, params.extra(), params.additional_signed());
+	let signature = payload.using_encoded(|payload| pair.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+
+	UncheckedExtrinsic::new_signed(call, account_id(pair).into(), signature.into(), extra)
+}
+
+/// Sign `call` with `pair` and SCALE-encode the extrinsic, ready to be submitted.
+pub fn sign_and_encode<P>(pair: &P, call: Call, params: &SigningParams) -> Vec<u8> where
+	P: Pair,
+	P::Public: Into<MultiSigner>,
+	P::Signature: Into<MultiSignature>,
+{
+	sign(pair, call, params).encode()
+}
+
+/// Storage key of `who`'s next nonce.
+pub fn nonce_key(who: &AccountId) -> Vec<u8> {
+	storage_keys::account_nonce(who)
+}
+
+/// Decode the value at `nonce_key`; an account without a value has never sent an extrinsic.
+pub fn decode_nonce(value: Option<&[u8]>) -> Result<Index, codec::Error> {
+	value.map(|mut value| Index::decode(&mut value)).unwrap_or(Ok(0))
+}
+
+/// Storage key of the events deposited by a block.
+pub fn events_key() -> Vec<u8> {
+	storage_keys::events()
+}
+
+/// Decode the value at `events_key`; a block without a value deposited no events.
+pub fn decode_events(value: Option<&[u8]>) -> Result<Vec<EventRecord<Event, Hash>>, codec::Error> {
+	value.map(|mut value| Decode::decode(&mut value)).unwrap_or_else(|| Ok(Vec::new()))
+}
+
+/// Storage key of the council's members.
+pub fn council_members_key() -> Vec<u8> {
+	storage_keys::council_members()
+}
+
+/// Decode the value at `council_members_key`; no value means no members.
+pub fn decode_council_members(value: Option<&[u8]>) -> Result<Vec<AccountId>, codec::Error> {
+	value.map(|mut value| Decode::decode(&mut value)).unwrap_or_else(|| Ok(Vec::new()))
+}
+
+/// Calls of the `system` module.
+pub mod system {
+	use super::Call;
+
+	/// Replace the runtime with `code`. Needs root, see `governance::dispatch_as_root`.
+	pub fn set_code(code: Vec<u8>) -> Call {
+		Call::System(::system::Call::set_code(code))
+	}
+
+	/// Remove the storage items at `keys`. Needs root.
+	pub fn kill_storage(keys: Vec<Vec<u8>>) -> Call {
+		Call::System(::system::Call::kill_storage(keys))
+	}
+}
+
+/// Calls of the `sudo` module, for chains that have not handed over to the council yet.
+pub mod sudo {
+	use super::Call;
+
+	/// Dispatch `call` as root; only the sudo key may sign this.
+	pub fn sudo(call: Call) -> Call {
+		Call::Sudo(::sudo::Call::sudo(Box::new(call)))
+	}
+}
+
+/// Calls of the council, a `collective` instance.
+pub mod council {
+	use super::{AccountId, Call, Hash};
+	use sp_runtime::traits::{BlakeTwo256, Hash as _};
+
+	/// Propose `call` as a motion that is dispatched once `threshold` members, the proposer
+	/// included, voted for it. A threshold below two dispatches it at once. Only members may sign
+	/// this.
+	pub fn propose(threshold: u32, call: Call) -> Call {
+		Call::Council(collective::Call::propose(threshold, Box::new(call)))
+	}
+
+	/// Vote on the motion `index` whose proposal hashes to `proposal`, see `proposal_hash`.
+	pub fn vote(proposal: Hash, index: u32, approve: bool) -> Call {
+		Call::Council(collective::Call::vote(proposal, index, approve))
+	}
+
+	/// Replace the members. Needs root.
+	pub fn set_members(members: Vec<AccountId>) -> Call {
+		Call::Council(collective::Call::set_members(members))
+	}
+
+	/// The hash motions are identified by.
+	pub fn proposal_hash(call: &Call) -> Hash {
+		BlakeTwo256::hash_of(call)
+	}
+}
+
+/// Calls of the `governance` module.
+pub mod governance {
+	use super::{Call, storage_keys};
+	use substrate_demo_runtime::governance::Call as ModuleCall;
+
+	/// Dispatch `call` as root; only two thirds of the council may, see `council::propose`.
+	pub fn dispatch_as_root(call: Call) -> Call {
+		Call::Governance(ModuleCall::dispatch_as_root(Box::new(call)))
+	}
+
+	/// Remove the sudo key, so nobody can sudo anymore. Needs root.
+	pub fn remove_sudo_key() -> Call {
+		super::system::kill_storage(vec![storage_keys::sudo_key()])
+	}
+}
+
+/// Calls of the `vesting` module.
+pub mod vesting {
+	use super::Call;
+	use substrate_demo_runtime::vesting::Call as ModuleCall;
+
+	/// Unlock what the signer's vesting schedule released so far.
+	pub fn vest() -> Call {
+		Call::Vesting(ModuleCall::vest())
+	}
+}
+
+/// Calls of the `scheduler` module.
+pub mod scheduler {
+	use super::{BlockNumber, Call};
+	use substrate_demo_runtime::scheduler::{Call as ModuleCall, TaskId};
+
+	/// Dispatch `call` at block `when` with the signer's origin, or as root under `sudo`.
+	pub fn schedule(when: BlockNumber, call: Call) -> Call {
+		Call::Scheduler(ModuleCall::schedule(when, None, Box::new(call)))
+	}
+
+	/// Dispatch `call` every `period` blocks from `when` on, `count` times in all.
+	pub fn schedule_periodic(when: BlockNumber, period: BlockNumber, count: u32, call: Call) -> Call {
+		Call::Scheduler(ModuleCall::schedule(when, Some((period, count)), Box::new(call)))
+	}
+
+	/// Cancel the task `id`, which the signer scheduled.
+	pub fn cancel(id: TaskId) -> Call {
+		Call::Scheduler(ModuleCall::cancel(id))
+	}
+}
+
+/// Calls of the `utility` module.
+pub mod utility {
+	use super::{AccountId, Call};
+	use codec::Encode;
+	use substrate_demo_runtime::{Runtime, utility::{Call as ModuleCall, CallHash, Module}};
+
+	/// Dispatch `calls` in order, stopping at the first that fails.
+	pub fn batch(calls: Vec<Call>) -> Call {
+		Call::Utility(ModuleCall::batch(calls))
+	}
+
+	/// Dispatch `calls` in order, failing with the first that fails.
+	pub fn batch_all(calls: Vec<Call>) -> Call {
+		Call::Utility(ModuleCall::batch_all(calls))
+	}
+
+	/// Approve `call` from the multisig of the signer and `other_signatories`, dispatching it with
+	/// the `threshold`-th approval. `other_signatories` are sorted here.
+	pub fn as_multi(threshold: u16, mut other_signatories: Vec<AccountId>, call: Call) -> Call {
+		other_signatories.sort();
+		Call::Utility(ModuleCall::as_multi(threshold, other_signatories, Box::new(call)))
+	}
+
+	/// Cancel the pending multisig call `call_hash` the signer approved first.
+	pub fn cancel_as_multi(threshold: u16, mut other_signatories: Vec<AccountId>, call_hash: CallHash) -> Call {
+		other_signatories.sort();
+		Call::Utility(ModuleCall::cancel_as_multi(threshold, other_signatories, call_hash))
+	}
+
+	/// The account of the multisig of `signatories` that needs `threshold` approvals.
+	pub fn multi_account_id(mut signatories: Vec<AccountId>, threshold: u16) -> AccountId {
+		signatories.sort();
+		Module::<Runtime>::multi_account_id(&signatories, threshold)
+	}
+
+	/// Hash of `call` as multisig calls are identified by.
+	pub fn call_hash(call: &Call) -> CallHash {
+		call.using_encoded(sp_core::blake2_256)
+	}
+}
+
+/// Calls of the `proxy` module.
+pub mod proxy {
+	use super::{AccountId, Call};
+	use substrate_demo_runtime::{ProxyType, proxy::Call as ModuleCall};
+
+	/// Dispatch `call` for `real`, which made the signer a proxy of a type allowing it.
+	pub fn proxy(real: AccountId, call: Call) -> Call {
+		Call::Proxy(ModuleCall::proxy(real, Box::new(call)))
+	}
+
+	/// Let `proxy` dispatch the calls `proxy_type` allows for the signer.
+	pub fn add_proxy(proxy: AccountId, proxy_type: ProxyType) -> Call {
+		Call::Proxy(ModuleCall::add_proxy(proxy, proxy_type))
+	}
+
+	/// Remove the proxy `proxy` of type `proxy_type` of the signer.
+	pub fn remove_proxy(proxy: AccountId, proxy_type: ProxyType) -> Call {
+		Call::Proxy(ModuleCall::remove_proxy(proxy, proxy_type))
+	}
+
+	/// Remove all proxies of the signer.
+	pub fn remove_proxies() -> Call {
+		Call::Proxy(ModuleCall::remove_proxies())
+	}
+}
+
+/// Calls of the `pause` module. All need root.
+pub mod pause {
+	use super::Call;
+	use substrate_demo_runtime::pause::Call as ModuleCall;
+
+	/// Pause all calls of the module `pallet`, named as in `construct_runtime!`.
+	pub fn pause_pallet(pallet: &str) -> Call {
+		Call::Pause(ModuleCall::pause_pallet(pallet.as_bytes().to_vec()))
+	}
+
+	/// Pause the call `call` of the module `pallet`.
+	pub fn pause_call(pallet: &str, call: &str) -> Call {
+		Call::Pause(ModuleCall::pause_call(pallet.as_bytes().to_vec(), call.as_bytes().to_vec()))
+	}
+
+	/// Unpause the module `pallet`, or only its call `call`.
+	pub fn unpause(pallet: &str, call: Option<&str>) -> Call {
+		Call::Pause(ModuleCall::unpause(pallet.as_bytes().to_vec(), call.map(|call| call.as_bytes().to_vec())))
+	}
+}
+
+/// Calls of the `assets` module.
+pub mod assets {
+	use super::{AccountId, Balance, Call};
+	use substrate_demo_runtime::{AssetId, assets::Call as ModuleCall};
+
+	/// Create an asset issued by the signer.
+	pub fn create(name: &str, symbol: &str, decimals: u8) -> Call {
+		Call::Assets(ModuleCall::create(name.as_bytes().to_vec(), symbol.as_bytes().to_vec(), decimals))
+	}
+
+	/// Mint `amount` of asset `id` to `beneficiary`. Only the issuer may sign this.
+	pub fn mint(id: AssetId, beneficiary: AccountId, amount: Balance) -> Call {
+		Call::Assets(ModuleCall::mint(id, beneficiary, amount))
+	}
+
+	/// Transfer `amount` of asset `id` from the signer to `dest`.
+	pub fn transfer(id: AssetId, dest: AccountId, amount: Balance) -> Call {
+		Call::Assets(ModuleCall::transfer(id, dest, amount))
+	}
+
+	/// Burn `amount` of the signer's balance of asset `id`.
+	pub fn burn(id: AssetId, amount: Balance) -> Call {
+		Call::Assets(ModuleCall::burn(id, amount))
+	}
+
+	/// Stop `who` from moving asset `id`. Only the issuer may sign this.
+	pub fn freeze(id: AssetId, who: AccountId) -> Call {
+		Call::Assets(ModuleCall::freeze(id, who))
+	}
+
+	/// Let `who` move asset `id` again. Only the issuer may sign this.
+	pub fn thaw(id: AssetId, who: AccountId) -> Call {
+		Call::Assets(ModuleCall::thaw(id, who))
+	}
+}
+
+/// Calls of the `nft` module.
+pub mod nft {
+	use super::{AccountId, Call};
+	use substrate_demo_runtime::{CollectionId, ItemId, nft::Call as ModuleCall};
+
+	/// Create a collection issued by the signer.
+	pub fn create_collection() -> Call {
+		Call::Nft(ModuleCall::create_collection())
+	}
+
+	/// Mint item `item` of `collection` to `owner`. Only the issuer may sign this.
+	pub fn mint(collection: CollectionId, item: ItemId, owner: AccountId, metadata: &[u8]) -> Call {
+		Call::Nft(ModuleCall::mint(collection, item, owner, metadata.to_vec()))
+	}
+
+	/// Transfer the signer's item `item` of `collection` to `dest`.
+	pub fn transfer(collection: CollectionId, item: ItemId, dest: AccountId) -> Call {
+		Call::Nft(ModuleCall::transfer(collection, item, dest))
+	}
+
+	/// Let `delegate` transfer the signer's item once, or nobody if `None`.
+	pub fn approve(collection: CollectionId, item: ItemId, delegate: Option<AccountId>) -> Call {
+		Call::Nft(ModuleCall::approve(collection, item, delegate))
+	}
+
+	/// Transfer an item the signer was approved for to `dest`.
+	pub fn transfer_from(collection: CollectionId, item: ItemId, dest: AccountId) -> Call {
+		Call::Nft(ModuleCall::transfer_from(collection, item, dest))
+	}
+
+	/// Burn the signer's item `item` of `collection`.
+	pub fn burn(collection: CollectionId, item: ItemId) -> Call {
+		Call::Nft(ModuleCall::burn(collection, item))
+	}
+}
+
+/// Calls of the `contracts` module.
+pub mod contracts {
+	use super::{AccountId, Balance, Call, Hash};
+
+	/// Store the wasm `code` of a contract, paying for up to `gas_limit` gas.
+	pub fn put_code(gas_limit: u64, code: Vec<u8>) -> Call {
+		Call::Contracts(::contracts::Call::put_code(gas_limit, code))
+	}
+
+	/// Instantiate the stored code `code_hash` with `data` as the input of its `deploy`, giving the
+	/// contract `endowment` of the signer's balance.
+	pub fn instantiate(endowment: Balance, gas_limit: u64, code_hash: Hash, data: Vec<u8>) -> Call {
+		Call::Contracts(::contracts::Call::instantiate(endowment, gas_limit, code_hash, data))
+	}
+
+	/// Call the contract `dest` with `data` as its input, transferring `value` to it.
+	pub fn call(dest: AccountId, value: Balance, gas_limit: u64, data: Vec<u8>) -> Call {
+		Call::Contracts(::contracts::Call::call(dest.into(), value, gas_limit, data))
+	}
+}
+
+/// Calls of the `simple_event` module.
+pub mod simple_event {
+	use super::Call;
+	use substrate_demo_runtime::simple_event::Call as ModuleCall;
+
+	/// Deposit an event carrying `value`.
+	pub fn set_value(value: u32) -> Call {
+		Call::SimpleEventModule(ModuleCall::set_value(value))
+	}
+}
+
+/// Calls of the `single_value` module.
+pub mod single_value {
+	use super::{AccountId, Call};
+	use substrate_demo_runtime::single_value::Call as ModuleCall;
+
+	/// Store `value`.
+	pub fn set_value(value: u32) -> Call {
+		Call::SingleValueModule(ModuleCall::set_value(value))
+	}
+
+	/// Deposit an event carrying the stored value.
+	pub fn get_value() -> Call {
+		Call::SingleValueModule(ModuleCall::get_value())
+	}
+
+	/// Store `account`.
+	pub fn set_account(account: AccountId) -> Call {
+		Call::SingleValueModule(ModuleCall::set_account(account))
+	}
+
+	/// Deposit an event carrying the stored account.
+	pub fn get_account() -> Call {
+		Call::SingleValueModule(ModuleCall::get_account())
+	}
+}
+
+/// Calls of the `vec_set` module.
+pub mod vec_set {
+	use super::Call;
+	use substrate_demo_runtime::vec_set::Call as ModuleCall;
+
+	/// Join the members.
+	pub fn add_member() -> Call {
+		Call::VecValueModule(ModuleCall::add_member())
+	}
+
+	/// Leave the members.
+	pub fn remove_member() -> Call {
+		Call::VecValueModule(ModuleCall::remove_member())
+	}
+}
+
+/// Calls of the `simple_map` module.
+pub mod simple_map {
+	use super::{AccountId, BlockNumber, Call};
+	use substrate_demo_runtime::simple_map::{Call as ModuleCall, Permissions};
+
+	/// Set the signer's entry, for good.
+	pub fn set_single_entry(entry: u32) -> Call {
+		Call::SimpleMapModule(ModuleCall::set_single_entry(entry, None))
+	}
+
+	/// Set the signer's entry, to be removed at block `expires_at`.
+	pub fn set_expiring_entry(entry: u32, expires_at: BlockNumber) -> Call {
+		Call::SimpleMapModule(ModuleCall::set_single_entry(entry, Some(expires_at)))
+	}
+
+	/// Deposit an event carrying `account`'s entry.
+	pub fn get_single_entry(account: AccountId) -> Call {
+		Call::SimpleMapModule(ModuleCall::get_single_entry(account))
+	}
+
+	/// Remove the signer's entry.
+	pub fn take_single_entry() -> Call {
+		Call::SimpleMapModule(ModuleCall::take_single_entry())
+	}
+
+	/// Add `value` to the signer's entry.
+	pub fn increase_single_entry(value: u32) -> Call {
+		Call::SimpleMapModule(ModuleCall::increase_single_entry(value))
+	}
+
+	/// Replace the signer's entry with `new_entry` if it is `old_entry`.
+	pub fn compare_and_swap_single_entry(old_entry: u32, new_entry: u32) -> Call {
+		Call::SimpleMapModule(ModuleCall::compare_and_swap_single_entry(old_entry, new_entry))
+	}
+
+	/// Replace the signer's entry with `new_entry` if its version is `expected_version`, zero
+	/// standing for no entry. Read the version with the `DemoApi_simple_map_entry` runtime API.
+	pub fn compare_version_and_swap(expected_version: u64, new_entry: u32) -> Call {
+		Call::SimpleMapModule(ModuleCall::compare_version_and_swap(expected_version, new_entry))
+	}
+
+	/// Make the signer's entry expire at block `expires_at` instead, or never if `None`.
+	pub fn renew_entry(expires_at: Option<BlockNumber>) -> Call {
+		Call::SimpleMapModule(ModuleCall::renew_entry(expires_at))
+	}
+
+	/// Set what `delegate` may do to the signer's entry.
+	pub fn approve(delegate: AccountId, permissions: Permissions) -> Call {
+		Call::SimpleMapModule(ModuleCall::approve(delegate, permissions))
+	}
+
+	/// Take back what `delegate` was allowed to do to the signer's entry.
+	pub fn revoke(delegate: AccountId) -> Call {
+		Call::SimpleMapModule(ModuleCall::revoke(delegate))
+	}
+
+	/// Add `value` to `owner`'s entry, as a delegate allowed to.
+	pub fn increase_single_entry_for(owner: AccountId, value: u32) -> Call {
+		Call::SimpleMapModule(ModuleCall::increase_single_entry_for(owner, value))
+	}
+
+	/// Replace `owner`'s entry with `new_entry` if it is `old_entry`, as a delegate allowed to.
+	pub fn compare_and_swap_single_entry_for(owner: AccountId, old_entry: u32, new_entry: u32) -> Call {
+		Call::SimpleMapModule(ModuleCall::compare_and_swap_single_entry_for(owner, old_entry, new_entry))
+	}
+}
+
+/// Calls of the `linked_map` module.
+pub mod linked_map {
+	use super::{AccountId, Call};
+	use substrate_demo_runtime::linked_map::Call as ModuleCall;
+
+	/// Append `account` to the lists.
+	pub fn add_member(account: AccountId) -> Call {
+		Call::LinkedMapModule(ModuleCall::add_member(account))
+	}
+
+	/// Remove the member at `index` of the bounded list.
+	pub fn remove_member_bunded(index: u32) -> Call {
+		Call::LinkedMapModule(ModuleCall::remove_member_bunded(index))
+	}
+
+	/// Remove the member at `index` of the linked list.
+	pub fn remove_member_linked(index: u32) -> Call {
+		Call::LinkedMapModule(ModuleCall::remove_member_linked(index))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{ed25519, sr25519};
+	use sp_runtime::traits::Verify;
+	use substrate_demo_runtime::{Address, Phase};
+
+	fn params() -> SigningParams {
+		SigningParams::immortal(Hash::repeat_byte(1), 3)
+	}
+
+	fn check_signature<P>(pair: &P) where
+		P: Pair,
+		P::Public: Into<MultiSigner>,
+		P::Signature: Into<MultiSignature>,
+	{
+		let params = params();
+		let encoded = sign_and_encode(pair, simple_map::set_single_entry(5), &params);
+		let xt = UncheckedExtrinsic::decode(&mut &encoded[..]).expect("extrinsics decode");
+		let (address, signature, extra) = xt.signature.clone().expect("the extrinsic is signed");
+
+		assert_eq!(address, Address::from(account_id(pair)));
+		assert_eq!(extra.encode(), params.extra().encode());
+		let payload = SignedPayload::from_raw(xt.function, extra, params.additional_signed());
+		assert!(payload.using_encoded(|payload| signature.verify(payload, &account_id(pair))));
+	}
+
+	#[test]
+	fn sr25519_signatures_verify() {
+		check_signature(&sr25519::Pair::from_string("//Alice", None).unwrap());
+	}
+
+	#[test]
+	fn ed25519_signatures_verify() {
+		check_signature(&ed25519::Pair::from_string("//Alice", None).unwrap());
+	}
+
+	#[test]
+	fn mortal_era_starts_before_current_block() {
+		let (era, birth) = mortal_era(64, 100);
+
+		assert_eq!(era, Era::mortal(64, 100));
+		assert!(birth <= 100 && birth > 100 - 64);
+	}
+
+	#[test]
+	fn missing_values_decode_to_defaults() {
+		assert_eq!(decode_nonce(None).ok(), Some(0));
+		assert_eq!(decode_nonce(Some(&7u32.encode())).ok(), Some(7));
+		assert_eq!(decode_events(None).ok(), Some(Vec::new()));
+		assert_eq!(decode_council_members(None).ok(), Some(Vec::new()));
+	}
+
+	#[test]
+	fn root_calls_go_through_a_council_motion() {
+		let set_code = system::set_code(vec![1, 2, 3]);
+		let motion = council::propose(2, governance::dispatch_as_root(set_code.clone()));
+
+		match motion {
+			Call::Council(collective::Call::propose(2, ref proposal)) => {
+				assert_eq!(**proposal, governance::dispatch_as_root(set_code));
+			},
+			_ => panic!("not a council motion"),
+		}
+	}
+
+	#[test]
+	fn events_decode() {
+		let alice = account_id(&sr25519::Pair::from_string("//Alice", None).unwrap());
+		let records = vec![EventRecord {
+			phase: Phase::ApplyExtrinsic(1),
+			event: Event::simple_event(substrate_demo_runtime::simple_event::RawEvent::EmitInput(42, alice)),
+			topics: vec![],
+		}];
+
+		assert_eq!(decode_events(Some(&records.encode())).ok(), Some(records));
+	}
+}
@@ -0,0 +1,25 @@
+;; Counts the calls made to it. Every call stores the new count, a little-endian u32, emits it in a
+;; `Contract` event and returns it.
+(module
+	(import "env" "ext_get_storage" (func $ext_get_storage (param i32) (result i32)))
+	(import "env" "ext_set_storage" (func $ext_set_storage (param i32 i32 i32 i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_scratch_write" (func $ext_scratch_write (param i32 i32)))
+	(import "env" "ext_deposit_event" (func $ext_deposit_event (param i32 i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; [0, 32) the storage key of the count, all zeros
+	;; [32, 36) the count
+
+	(func (export "deploy"))
+
+	(func (export "call")
+		(if (i32.eqz (call $ext_get_storage (i32.const 0)))
+			(then (call $ext_scratch_read (i32.const 32) (i32.const 0) (i32.const 4)))
+		)
+		(i32.store (i32.const 32) (i32.add (i32.load (i32.const 32)) (i32.const 1)))
+		(call $ext_set_storage (i32.const 0) (i32.const 1) (i32.const 32) (i32.const 4))
+		(call $ext_deposit_event (i32.const 0) (i32.const 0) (i32.const 32) (i32.const 4))
+		(call $ext_scratch_write (i32.const 32) (i32.const 4))
+	)
+)
@@ -0,0 +1,31 @@
+;; Reads the runtime's storage. The input of a call is the key of an item, such as
+;; `storage_keys::members()` or `storage_keys::simple_map(who)`; the call emits the item's encoded
+;; value in a `Contract` event and returns it, and traps if there is no such item.
+;; `ext_get_runtime_storage` is not limited to the demo modules: any item can be read this way.
+(module
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_get_runtime_storage" (func $ext_get_runtime_storage (param i32 i32) (result i32)))
+	(import "env" "ext_deposit_event" (func $ext_deposit_event (param i32 i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "deploy"))
+
+	(func (export "call")
+		(local $key_len i32)
+		(local $value_len i32)
+
+		;; the key, from the input, goes to [0, key_len)
+		(set_local $key_len (call $ext_scratch_size))
+		(call $ext_scratch_read (i32.const 0) (i32.const 0) (get_local $key_len))
+
+		(if (call $ext_get_runtime_storage (i32.const 0) (get_local $key_len))
+			(then unreachable)
+		)
+
+		;; the value is left in the scratch buffer to be returned, and copied after the key to be emitted
+		(set_local $value_len (call $ext_scratch_size))
+		(call $ext_scratch_read (get_local $key_len) (i32.const 0) (get_local $value_len))
+		(call $ext_deposit_event (i32.const 0) (i32.const 0) (get_local $key_len) (get_local $value_len))
+	)
+)
@@ -0,0 +1,401 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Fungible assets created by users.
+//!
+//! The account creating an asset is its issuer: it alone mints the asset, freezes and thaws
+//! holders, and sets the metadata. Holders transfer and burn their balances. The supply of every
+//! asset always equals the sum of its balances.
+
+use codec::{Encode, Decode};
+use frame_support::{decl_module, decl_storage, decl_event, dispatch::DispatchResult, ensure, traits::Get, Parameter};
+use sp_runtime::{RuntimeDebug, traits::{CheckedAdd, Member, One, SimpleArithmetic, Zero}};
+use sp_std::prelude::*;
+use system::ensure_signed;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// The amounts of assets.
+	type Balance: Parameter + Member + SimpleArithmetic + Default + Copy;
+	/// Identifies an asset.
+	type AssetId: Parameter + Member + SimpleArithmetic + Default + Copy;
+	/// Bytes an asset's name or symbol may have.
+	type StringLimit: Get<u32>;
+}
+
+/// The issuer and supply of an asset.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct AssetDetails<AccountId, Balance> {
+	pub issuer: AccountId,
+	pub supply: Balance,
+}
+
+/// How an asset is shown.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct AssetMetadata {
+	pub name: Vec<u8>,
+	pub symbol: Vec<u8>,
+	/// Digits of a balance after the decimal point.
+	pub decimals: u8,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Assets {
+		/// The issuer and supply of each asset.
+		pub Details get(fn details): map T::AssetId => Option<AssetDetails<T::AccountId, T::Balance>>;
+		/// The metadata of each asset.
+		pub Metadata get(fn metadata): map T::AssetId => AssetMetadata;
+		/// The balance of each holder of each asset.
+		pub Balances get(fn balance): map (T::AssetId, T::AccountId) => T::Balance;
+		/// Holders that can not transfer or burn an asset.
+		pub Frozen get(fn frozen): map (T::AssetId, T::AccountId) => bool;
+		/// The id of the next asset created.
+		NextAssetId get(fn next_asset_id): T::AssetId;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		const StringLimit: u32 = T::StringLimit::get();
+
+		/// Create an asset issued by the caller, with no supply yet.
+		pub fn create(origin, name: Vec<u8>, symbol: Vec<u8>, decimals: u8) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let metadata = Self::checked_metadata(name, symbol, decimals)?;
+
+			let id = Self::next_asset_id();
+			<NextAssetId<T>>::put(id + One::one());
+			<Details<T>>::insert(id, AssetDetails { issuer: who.clone(), supply: Zero::zero() });
+			<Metadata<T>>::insert(id, metadata);
+
+			Self::deposit_event(RawEvent::Created(id, who));
+			Ok(())
+		}
+
+		/// Mint `amount` of asset `id` to `beneficiary`. Only the issuer may.
+		pub fn mint(origin, id: T::AssetId, beneficiary: T::AccountId, amount: T::Balance) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut details = Self::issued_by(id, &who)?;
+			details.supply = details.supply.checked_add(&amount).ok_or("the supply would overflow")?;
+
+			// the balance is at most the supply, so it does not overflow either
+			<Balances<T>>::mutate((id, beneficiary.clone()), |balance| *balance += amount);
+			<Details<T>>::insert(id, details);
+
+			Self::deposit_event(RawEvent::Minted(id, beneficiary, amount));
+			Ok(())
+		}
+
+		/// Transfer `amount` of asset `id` from the caller to `dest`.
+		pub fn transfer(origin, id: T::AssetId, dest: T::AccountId, amount: T::Balance) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(<Details<T>>::exists(id), "asset does not exist");
+			ensure!(!Self::frozen((id, who.clone())), "the balance is frozen");
+			let balance = Self::balance((id, who.clone()));
+			ensure!(balance >= amount, "balance too low");
+
+			Self::set_balance(id, &who, balance - amount);
+			<Balances<T>>::mutate((id, dest.clone()), |balance| *balance += amount);
+
+			Self::deposit_event(RawEvent::Transferred(id, who, dest, amount));
+			Ok(())
+		}
+
+		/// Burn `amount` of the caller's balance of asset `id`, taking it out of the supply.
+		pub fn burn(origin, id: T::AssetId, amount: T::Balance) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut details = Self::details(id).ok_or("asset does not exist")?;
+			ensure!(!Self::frozen((id, who.clone())), "the balance is frozen");
+			let balance = Self::balance((id, who.clone()));
+			ensure!(balance >= amount, "balance too low");
+
+			details.supply -= amount;
+			<Details<T>>::insert(id, details);
+			Self::set_balance(id, &who, balance - amount);
+
+			Self::deposit_event(RawEvent::Burned(id, who, amount));
+			Ok(())
+		}
+
+		/// Stop `who` from transferring or burning asset `id`. Only the issuer may.
+		pub fn freeze(origin, id: T::AssetId, who: T::AccountId) -> DispatchResult {
+			let issuer = ensure_signed(origin)?;
+			Self::issued_by(id, &issuer)?;
+			<Frozen<T>>::insert((id, who.clone()), true);
+
+			Self::deposit_event(RawEvent::Frozen(id, who));
+			Ok(())
+		}
+
+		/// Let a frozen `who` transfer and burn asset `id` again. Only the issuer may.
+		pub fn thaw(origin, id: T::AssetId, who: T::AccountId) -> DispatchResult {
+			let issuer = ensure_signed(origin)?;
+			Self::issued_by(id, &issuer)?;
+			ensure!(Self::frozen((id, who.clone())), "the balance must be frozen");
+			<Frozen<T>>::remove((id, who.clone()));
+
+			Self::deposit_event(RawEvent::Thawed(id, who));
+			Ok(())
+		}
+
+		/// Replace the metadata of asset `id`. Only the issuer may.
+		pub fn set_metadata(origin, id: T::AssetId, name: Vec<u8>, symbol: Vec<u8>, decimals: u8) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::issued_by(id, &who)?;
+			<Metadata<T>>::insert(id, Self::checked_metadata(name, symbol, decimals)?);
+
+			Self::deposit_event(RawEvent::MetadataSet(id));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The supply of asset `id`, zero if it does not exist.
+	pub fn total_supply(id: T::AssetId) -> T::Balance {
+		Self::details(id).map(|details| details.supply).unwrap_or_default()
+	}
+
+	/// Set the balance of `who`, removing it once it is zero.
+	fn set_balance(id: T::AssetId, who: &T::AccountId, balance: T::Balance) {
+		if balance.is_zero() {
+			<Balances<T>>::remove((id, who.clone()));
+		} else {
+			<Balances<T>>::insert((id, who.clone()), balance);
+		}
+	}
+
+	/// The details of asset `id` if `who` issued it.
+	fn issued_by(id: T::AssetId, who: &T::AccountId) -> Result<AssetDetails<T::AccountId, T::Balance>, &'static str> {
+		let details = Self::details(id).ok_or("asset does not exist")?;
+		ensure!(&details.issuer == who, "must be the issuer of the asset");
+		Ok(details)
+	}
+
+	fn checked_metadata(name: Vec<u8>, symbol: Vec<u8>, decimals: u8) -> Result<AssetMetadata, &'static str> {
+		let limit = T::StringLimit::get() as usize;
+		ensure!(name.len() <= limit && symbol.len() <= limit, "name and symbol must not exceed the limit");
+		Ok(AssetMetadata { name, symbol, decimals })
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as system::Trait>::AccountId,
+		AssetId = <T as Trait>::AssetId,
+		Balance = <T as Trait>::Balance,
+	{
+		/// An asset was created by its issuer.
+		Created(AssetId, AccountId),
+		/// An amount of an asset was minted to an account.
+		Minted(AssetId, AccountId, Balance),
+		/// An amount of an asset was transferred from one account to another.
+		Transferred(AssetId, AccountId, AccountId, Balance),
+		/// An account burned an amount of an asset.
+		Burned(AssetId, AccountId, Balance),
+		/// An account's balance of an asset was frozen.
+		Frozen(AssetId, AccountId),
+		/// An account's balance of an asset was thawed.
+		Thawed(AssetId, AccountId),
+		/// The metadata of an asset changed.
+		MetadataSet(AssetId),
+	}
+);
+
+#[cfg(test)]
+mod tests {
+	use super::{AssetMetadata, Module, Trait, RawEvent};
+
+	use sp_core::H256;
+	use frame_support::{impl_outer_origin, impl_outer_event, assert_ok, assert_err, parameter_types, weights::Weight};
+	use sp_runtime::{
+		traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	mod assets_event {
+		pub use crate::assets::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for TestRuntime {
+			assets_event<T>,
+		}
+	}
+
+	parameter_types! {
+		pub const StringLimit: u32 = 8;
+	}
+	impl Trait for TestRuntime {
+		type Event = TestEvent;
+		type Balance = u64;
+		type AssetId = u32;
+		type StringLimit = StringLimit;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type Assets = Module<TestRuntime>;
+
+	const ISSUER: u64 = 1;
+	const HOLDER: u64 = 2;
+	const OTHER: u64 = 3;
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			let mut ext = sp_io::TestExternalities::from(storage);
+			ext.execute_with(|| {
+				System::set_block_number(1);
+				assert_ok!(Assets::create(Origin::signed(ISSUER), b"Token".to_vec(), b"TKN".to_vec(), 2));
+			});
+			ext
+		}
+	}
+
+	fn deposited(event: RawEvent<u64, u32, u64>) -> bool {
+		let expected_event = TestEvent::assets_event(event);
+		System::events().iter().any(|a| a.event == expected_event)
+	}
+
+	/// The supply of asset 0 must be the sum of the balances of all accounts the tests use.
+	fn assert_supply_invariant() {
+		let sum: u64 = [ISSUER, HOLDER, OTHER].iter().map(|&who| Assets::balance((0, who))).sum();
+		assert_eq!(Assets::total_supply(0), sum);
+	}
+
+	#[test]
+	fn created_assets_have_metadata_and_no_supply() {
+		ExtBuilder::build().execute_with(|| {
+			assert!(deposited(RawEvent::Created(0, ISSUER)));
+			assert_eq!(Assets::details(0).unwrap().issuer, ISSUER);
+			assert_eq!(Assets::total_supply(0), 0);
+			assert_eq!(
+				Assets::metadata(0),
+				AssetMetadata { name: b"Token".to_vec(), symbol: b"TKN".to_vec(), decimals: 2 },
+			);
+
+			assert_ok!(Assets::create(Origin::signed(HOLDER), b"Other".to_vec(), b"OTH".to_vec(), 0));
+			assert_eq!(Assets::details(1).unwrap().issuer, HOLDER);
+			assert_err!(
+				Assets::create(Origin::signed(HOLDER), b"Much too long".to_vec(), b"LONG".to_vec(), 0),
+				"name and symbol must not exceed the limit",
+			);
+		})
+	}
+
+	#[test]
+	fn only_the_issuer_mints() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Assets::mint(Origin::signed(ISSUER), 0, HOLDER, 100));
+			assert!(deposited(RawEvent::Minted(0, HOLDER, 100)));
+			assert_eq!(Assets::balance((0, HOLDER)), 100);
+			assert_eq!(Assets::total_supply(0), 100);
+
+			assert_err!(Assets::mint(Origin::signed(HOLDER), 0, HOLDER, 100), "must be the issuer of the asset");
+			assert_err!(Assets::mint(Origin::signed(ISSUER), 1, HOLDER, 100), "asset does not exist");
+			assert_err!(Assets::mint(Origin::signed(ISSUER), 0, OTHER, u64::max_value()), "the supply would overflow");
+			assert_supply_invariant();
+		})
+	}
+
+	#[test]
+	fn holders_transfer() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Assets::mint(Origin::signed(ISSUER), 0, HOLDER, 100));
+			assert_ok!(Assets::transfer(Origin::signed(HOLDER), 0, OTHER, 40));
+			assert!(deposited(RawEvent::Transferred(0, HOLDER, OTHER, 40)));
+			assert_eq!(Assets::balance((0, HOLDER)), 60);
+			assert_eq!(Assets::balance((0, OTHER)), 40);
+
+			assert_err!(Assets::transfer(Origin::signed(HOLDER), 0, OTHER, 61), "balance too low");
+			assert_ok!(Assets::transfer(Origin::signed(HOLDER), 0, HOLDER, 60));
+			assert_eq!(Assets::balance((0, HOLDER)), 60);
+			assert_supply_invariant();
+		})
+	}
+
+	#[test]
+	fn holders_burn() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Assets::mint(Origin::signed(ISSUER), 0, HOLDER, 100));
+			assert_ok!(Assets::burn(Origin::signed(HOLDER), 0, 30));
+			assert!(deposited(RawEvent::Burned(0, HOLDER, 30)));
+			assert_eq!(Assets::total_supply(0), 70);
+			assert_err!(Assets::burn(Origin::signed(HOLDER), 0, 71), "balance too low");
+			assert_supply_invariant();
+
+			assert_ok!(Assets::burn(Origin::signed(HOLDER), 0, 70));
+			assert_eq!(Assets::total_supply(0), 0);
+			assert_supply_invariant();
+		})
+	}
+
+	#[test]
+	fn frozen_holders_can_not_move_their_balance() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Assets::mint(Origin::signed(ISSUER), 0, HOLDER, 100));
+			assert_err!(Assets::freeze(Origin::signed(OTHER), 0, HOLDER), "must be the issuer of the asset");
+			assert_ok!(Assets::freeze(Origin::signed(ISSUER), 0, HOLDER));
+			assert!(deposited(RawEvent::Frozen(0, HOLDER)));
+
+			assert_err!(Assets::transfer(Origin::signed(HOLDER), 0, OTHER, 10), "the balance is frozen");
+			assert_err!(Assets::burn(Origin::signed(HOLDER), 0, 10), "the balance is frozen");
+			// frozen holders still receive
+			assert_ok!(Assets::mint(Origin::signed(ISSUER), 0, HOLDER, 10));
+
+			assert_ok!(Assets::thaw(Origin::signed(ISSUER), 0, HOLDER));
+			assert!(deposited(RawEvent::Thawed(0, HOLDER)));
+			assert_ok!(Assets::transfer(Origin::signed(HOLDER), 0, OTHER, 10));
+			assert_err!(Assets::thaw(Origin::signed(ISSUER), 0, HOLDER), "the balance must be frozen");
+			assert_supply_invariant();
+		})
+	}
+
+	#[test]
+	fn only_the_issuer_sets_metadata() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(
+				Assets::set_metadata(Origin::signed(HOLDER), 0, b"Mine".to_vec(), b"MN".to_vec(), 0),
+				"must be the issuer of the asset",
+			);
+			assert_ok!(Assets::set_metadata(Origin::signed(ISSUER), 0, b"Renamed".to_vec(), b"RN".to_vec(), 6));
+			assert!(deposited(RawEvent::MetadataSet(0)));
+			assert_eq!(Assets::metadata(0).decimals, 6);
+		})
+	}
+}
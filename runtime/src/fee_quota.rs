@@ -0,0 +1,388 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! A quota of fee-free transactions for the members of `vec_set`.
+//!
+//! `ChargeTransactionPaymentOrQuota` takes the place of `ChargeTransactionPayment` in `SignedExtra`.
+//! Members that tip nothing send up to `FreeTransactions` transactions every `EraLength` blocks
+//! without paying fees; everyone else, and members whose quota ran out, pay as usual. The quotas
+//! used in an era are removed when the next one starts.
+
+use codec::{Encode, Decode};
+use frame_support::{decl_module, decl_storage, traits::{Currency, Get}, weights::DispatchInfo};
+use sp_runtime::{
+	traits::{One, SaturatedConversion, Saturating, SignedExtension, Zero},
+	transaction_validity::{TransactionValidity, TransactionValidityError, ValidTransaction},
+};
+use sp_std::prelude::*;
+use transaction_payment::ChargeTransactionPayment;
+use crate::vec_set;
+
+type BalanceOf<T> =
+	<<T as transaction_payment::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: transaction_payment::Trait + vec_set::Trait {
+	/// Fee-free transactions a member may send per era.
+	type FreeTransactions: Get<u32>;
+	/// Blocks per era; the quotas reset when an era starts.
+	type EraLength: Get<Self::BlockNumber>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as FeeQuota {
+		/// Fee-free transactions each member sent, by era.
+		pub Used get(fn used): double_map hasher(blake2_256) T::BlockNumber, blake2_256(T::AccountId) => u32;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		const FreeTransactions: u32 = T::FreeTransactions::get();
+		const EraLength: T::BlockNumber = T::EraLength::get();
+
+		fn on_initialize(n: T::BlockNumber) {
+			let era = Self::current_era();
+			if (n % T::EraLength::get()).is_zero() && !era.is_zero() {
+				<Used<T>>::remove_prefix(&(era - One::one()));
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Index of the era the current block belongs to.
+	pub fn current_era() -> T::BlockNumber {
+		<system::Module<T>>::block_number() / T::EraLength::get()
+	}
+
+	/// Fee-free transactions `who` has left in the current era; none if they are not a member.
+	pub fn remaining(who: &T::AccountId) -> u32 {
+		if !<vec_set::Module<T>>::is_member(who) {
+			return 0;
+		}
+
+		T::FreeTransactions::get().saturating_sub(Self::used(Self::current_era(), who))
+	}
+
+	fn use_quota(who: &T::AccountId) {
+		<Used<T>>::mutate(Self::current_era(), who, |used| *used = used.saturating_add(1));
+	}
+}
+
+/// Charges the fee of a transaction like `ChargeTransactionPayment` does, unless the signer is a
+/// member with quota left who tips nothing.
+///
+/// Encodes like `ChargeTransactionPayment`, so transactions are signed the same way. Fee-free
+/// transactions get the lowest priority, so paying ones go first. All fee-free transactions of a
+/// member provide the same tag until one of them is included, so the pool holds at most one per
+/// member, and they leave the pool when the era they were validated in ends. One included after
+/// the quota ran out pays its fee.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeTransactionPaymentOrQuota<T: Trait + Send + Sync>(#[codec(compact)] BalanceOf<T>);
+
+impl<T: Trait + Send + Sync> ChargeTransactionPaymentOrQuota<T> {
+	/// Pay `tip` on top of the fee, or nothing if it is zero and the quota allows.
+	pub fn from(tip: BalanceOf<T>) -> Self {
+		Self(tip)
+	}
+
+	fn is_free(&self, who: &T::AccountId) -> bool {
+		self.0.is_zero() && <Module<T>>::remaining(who) > 0
+	}
+
+	fn charge(&self) -> ChargeTransactionPayment<T> {
+		ChargeTransactionPayment::from(self.0)
+	}
+}
+
+impl<T: Trait + Send + Sync> sp_std::fmt::Debug for ChargeTransactionPaymentOrQuota<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "ChargeTransactionPaymentOrQuota<{:?}>", self.0)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + Send + Sync> SignedExtension for ChargeTransactionPaymentOrQuota<T> where
+	BalanceOf<T>: Send + Sync,
+{
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = ();
+	type DispatchInfo = DispatchInfo;
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: Self::DispatchInfo,
+		len: usize,
+	) -> TransactionValidity {
+		if self.is_free(who) {
+			let era = <Module<T>>::current_era();
+			let era_end = era.saturating_add(One::one()).saturating_mul(T::EraLength::get());
+			let now = <system::Module<T>>::block_number();
+			Ok(ValidTransaction {
+				priority: 0,
+				provides: vec![(b"fee_quota", who, era, <Module<T>>::used(era, who)).encode()],
+				longevity: era_end.saturating_sub(now).saturated_into::<u64>(),
+				..Default::default()
+			})
+		} else {
+			self.charge().validate(who, call, info, len)
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: Self::DispatchInfo,
+		len: usize,
+	) -> Result<(), TransactionValidityError> {
+		if self.is_free(who) {
+			<Module<T>>::use_quota(who);
+			Ok(())
+		} else {
+			self.charge().pre_dispatch(who, call, info, len)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ChargeTransactionPaymentOrQuota, Module, Trait};
+	use crate::vec_set;
+
+	use sp_core::H256;
+	use frame_support::{impl_outer_origin, assert_ok, parameter_types, weights::{DispatchInfo, Weight}};
+	use sp_runtime::{
+		traits::{BlakeTwo256, ConvertInto, IdentityLookup, OnInitialize, SignedExtension}, testing::Header,
+		transaction_validity::ValidTransaction, Perbill,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for TestRuntime {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+
+	parameter_types! {
+		pub const TransactionBaseFee: u64 = 10;
+		pub const TransactionByteFee: u64 = 1;
+	}
+	impl transaction_payment::Trait for TestRuntime {
+		type Currency = balances::Module<TestRuntime>;
+		type OnTransactionPayment = ();
+		type TransactionBaseFee = TransactionBaseFee;
+		type TransactionByteFee = TransactionByteFee;
+		type WeightToFee = ConvertInto;
+		type FeeMultiplierUpdate = ();
+	}
+
+	impl vec_set::Trait for TestRuntime {
+		type Event = ();
+	}
+
+	parameter_types! {
+		pub const FreeTransactions: u32 = 2;
+		pub const EraLength: u64 = 10;
+	}
+	impl Trait for TestRuntime {
+		type FreeTransactions = FreeTransactions;
+		type EraLength = EraLength;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type Balances = balances::Module<TestRuntime>;
+	type VecSet = vec_set::Module<TestRuntime>;
+	type FeeQuota = Module<TestRuntime>;
+
+	const MEMBER: u64 = 1;
+	const NON_MEMBER: u64 = 2;
+	/// Weight 5 and length 10 cost 10 + 10 + 5.
+	const FEE: u64 = 25;
+	const LEN: usize = 10;
+
+	fn info() -> DispatchInfo {
+		DispatchInfo { weight: 5, pays_fee: true, ..Default::default() }
+	}
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let mut storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			balances::GenesisConfig::<TestRuntime> {
+				balances: vec![(MEMBER, 1_000), (NON_MEMBER, 1_000)],
+				vesting: vec![],
+			}.assimilate_storage(&mut storage).unwrap();
+
+			let mut ext = sp_io::TestExternalities::from(storage);
+			ext.execute_with(|| {
+				System::set_block_number(1);
+				assert_ok!(VecSet::add_member(Origin::signed(MEMBER)));
+			});
+			ext
+		}
+	}
+
+	fn send(who: u64, tip: u64) {
+		assert_ok!(ChargeTransactionPaymentOrQuota::<TestRuntime>::from(tip).pre_dispatch(&who, &(), info(), LEN));
+	}
+
+	#[test]
+	fn members_send_free_transactions_up_to_the_quota() {
+		ExtBuilder::build().execute_with(|| {
+			send(MEMBER, 0);
+			send(MEMBER, 0);
+			assert_eq!(Balances::free_balance(&MEMBER), 1_000);
+			assert_eq!(FeeQuota::remaining(&MEMBER), 0);
+
+			send(MEMBER, 0);
+			assert_eq!(Balances::free_balance(&MEMBER), 1_000 - FEE);
+		})
+	}
+
+	#[test]
+	fn non_members_pay() {
+		ExtBuilder::build().execute_with(|| {
+			assert_eq!(FeeQuota::remaining(&NON_MEMBER), 0);
+			send(NON_MEMBER, 0);
+			assert_eq!(Balances::free_balance(&NON_MEMBER), 1_000 - FEE);
+		})
+	}
+
+	#[test]
+	fn tipping_members_pay_and_keep_their_quota() {
+		ExtBuilder::build().execute_with(|| {
+			send(MEMBER, 5);
+			assert_eq!(Balances::free_balance(&MEMBER), 1_000 - FEE - 5);
+			assert_eq!(FeeQuota::remaining(&MEMBER), 2);
+		})
+	}
+
+	#[test]
+	fn quota_resets_every_era() {
+		ExtBuilder::build().execute_with(|| {
+			send(MEMBER, 0);
+			send(MEMBER, 0);
+			System::set_block_number(9);
+			assert_eq!(FeeQuota::remaining(&MEMBER), 0);
+
+			System::set_block_number(10);
+			assert_eq!(FeeQuota::remaining(&MEMBER), 2);
+			send(MEMBER, 0);
+			assert_eq!(Balances::free_balance(&MEMBER), 1_000);
+			assert_eq!(FeeQuota::used(1, &MEMBER), 1);
+		})
+	}
+
+	#[test]
+	fn past_eras_are_pruned() {
+		ExtBuilder::build().execute_with(|| {
+			send(MEMBER, 0);
+			System::set_block_number(9);
+			FeeQuota::on_initialize(9);
+			assert!(<super::Used<TestRuntime>>::exists(0, &MEMBER));
+
+			System::set_block_number(10);
+			FeeQuota::on_initialize(10);
+			assert!(!<super::Used<TestRuntime>>::exists(0, &MEMBER));
+			send(MEMBER, 0);
+
+			System::set_block_number(20);
+			FeeQuota::on_initialize(20);
+			assert!(!<super::Used<TestRuntime>>::exists(1, &MEMBER));
+		})
+	}
+
+	fn validate(who: u64) -> ValidTransaction {
+		ChargeTransactionPaymentOrQuota::<TestRuntime>::from(0).validate(&who, &(), info(), LEN).unwrap()
+	}
+
+	#[test]
+	fn members_have_one_free_transaction_in_the_pool() {
+		ExtBuilder::build().execute_with(|| {
+			// pending free transactions share a tag, so the pool keeps one of them
+			let first = validate(MEMBER);
+			assert_eq!(validate(MEMBER).provides, first.provides);
+			assert_eq!(first.longevity, 9);
+
+			send(MEMBER, 0);
+			assert_ne!(validate(MEMBER).provides, first.provides);
+			assert!(validate(NON_MEMBER).provides.is_empty());
+		})
+	}
+
+	#[test]
+	fn former_members_pay() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(VecSet::remove_member(Origin::signed(MEMBER)));
+			send(MEMBER, 0);
+			assert_eq!(Balances::free_balance(&MEMBER), 1_000 - FEE);
+		})
+	}
+
+	#[test]
+	fn free_transactions_rank_below_paid_ones() {
+		ExtBuilder::build().execute_with(|| {
+			let free = validate(MEMBER);
+			let paid = validate(NON_MEMBER);
+
+			assert!(free.priority < paid.priority);
+			// validation does not use up the quota
+			assert_eq!(FeeQuota::remaining(&MEMBER), 2);
+		})
+	}
+}
@@ -0,0 +1,197 @@
+//! Transaction fees that follow the load of the chain.
+//!
+//! `TargetedFeeAdjustment` moves the fee multiplier after every block towards making blocks
+//! `TargetBlockFullness` full, and `WeightToFee` charges heavy extrinsics more than proportionally.
+//! `ContractGasPrice` scales the price of contract gas by the same multiplier.
+
+use sp_std::marker::PhantomData;
+use sp_runtime::{Fixed64, Perbill, traits::{Convert, Get}};
+use frame_support::weights::Weight;
+use crate::{Balance, MaximumBlockWeight, System, TransactionPayment};
+
+/// The highest value of the multiplier; fees are then eleven times what an empty chain pays.
+pub const MAXIMUM_MULTIPLIER: i64 = 10;
+
+/// Converts a weight to a fee.
+///
+/// One unit per unit of weight, plus a quadratic term that reaches ten times the linear one for an
+/// extrinsic that takes a whole block, so a few heavy extrinsics cost more than many light ones of
+/// the same total weight.
+pub struct WeightToFee;
+
+impl Convert<Weight, Balance> for WeightToFee {
+	fn convert(weight: Weight) -> Balance {
+		let weight = Balance::from(weight);
+		let max = Balance::from(MaximumBlockWeight::get());
+
+		weight.saturating_add(weight.saturating_mul(weight).saturating_mul(10) / max)
+	}
+}
+
+/// The price of a unit of contract gas: the fee of a unit of weight, scaled by the fee multiplier.
+///
+/// Rounded down, that is one until the multiplier reaches one and at most `MAXIMUM_MULTIPLIER + 1`.
+/// It does not drop to zero with the multiplier, as the gas a contract burns is not bounded by the
+/// weight of the extrinsic calling it.
+pub struct ContractGasPrice;
+
+impl Get<Balance> for ContractGasPrice {
+	fn get() -> Balance {
+		TransactionPayment::next_fee_multiplier()
+			.saturated_multiply_accumulate(WeightToFee::convert(1))
+			.max(1)
+	}
+}
+
+/// Updates the fee multiplier after every block by how far the block's weight was from `T` of
+/// `MaximumBlockWeight`.
+///
+/// With `v = 0.0004` and `d` the difference between the block's fullness and the target, the
+/// multiplier grows by `v * d + (v * d)^2 / 2` above the target and shrinks by
+/// `v * d - (v * d)^2 / 2` below it. A chain of full blocks thus raises the multiplier by about
+/// 0.0003 per block, 4.3 a day, until `MAXIMUM_MULTIPLIER`; empty blocks lower it to -1, where
+/// weight and length fees are waived and only the base fee is left.
+pub struct TargetedFeeAdjustment<T>(PhantomData<T>);
+
+impl<T: Get<Perbill>> Convert<Fixed64, Fixed64> for TargetedFeeAdjustment<T> {
+	fn convert(multiplier: Fixed64) -> Fixed64 {
+		let max_weight = MaximumBlockWeight::get();
+		let block_weight = System::all_extrinsics_weight().min(max_weight) as i64;
+		let target_weight = (T::get() * max_weight) as i64;
+
+		// both weights are at most `max_weight`, a u32, so neither the difference nor its absolute
+		// value overflows
+		let positive = block_weight >= target_weight;
+		let diff = Fixed64::from_rational((block_weight - target_weight).abs(), max_weight.into());
+
+		let v = Fixed64::from_rational(4, 10_000);
+		// v^2 / 2 = 0.00000008
+		let v_squared_2 = Fixed64::from_rational(8, 100_000_000);
+		let first_term = v.saturating_mul(diff);
+		let second_term = v_squared_2.saturating_mul(diff.saturating_mul(diff));
+
+		if positive {
+			multiplier.saturating_add(first_term.saturating_add(second_term))
+				.min(Fixed64::from_natural(MAXIMUM_MULTIPLIER))
+		} else {
+			// `diff` is at most 1, so `first_term` is always the larger one
+			multiplier.saturating_sub(first_term.saturating_sub(second_term))
+				.max(Fixed64::from_natural(-1))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Runtime, TargetBlockFullness};
+
+	type Adjustment = TargetedFeeAdjustment<TargetBlockFullness>;
+
+	fn max() -> Weight {
+		MaximumBlockWeight::get()
+	}
+
+	fn target() -> Weight {
+		TargetBlockFullness::get() * max()
+	}
+
+	/// The multiplier after each block of `weights`, starting from `start`.
+	fn simulate(start: Fixed64, weights: impl IntoIterator<Item = Weight>) -> Vec<Fixed64> {
+		let mut ext: sp_io::TestExternalities = system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap()
+			.into();
+
+		ext.execute_with(|| {
+			let mut multiplier = start;
+			weights.into_iter()
+				.map(|weight| {
+					System::set_block_limits(weight, 0);
+					multiplier = Adjustment::convert(multiplier);
+					multiplier
+				})
+				.collect()
+		})
+	}
+
+	fn floor() -> Fixed64 {
+		Fixed64::from_natural(-1)
+	}
+
+	fn cap() -> Fixed64 {
+		Fixed64::from_natural(MAXIMUM_MULTIPLIER)
+	}
+
+	#[test]
+	fn target_fullness_keeps_the_multiplier() {
+		for &start in &[floor(), Fixed64::default(), Fixed64::from_rational(5, 2), cap()] {
+			let multipliers = simulate(start, vec![target(); 5_000]);
+			assert!(multipliers.iter().all(|&m| m == start));
+		}
+	}
+
+	#[test]
+	fn empty_blocks_lower_fees_to_the_floor() {
+		let multipliers = simulate(Fixed64::default(), vec![0; 15_000]);
+
+		assert!(multipliers.windows(2).all(|w| w[1] <= w[0]));
+		assert!(multipliers.iter().all(|&m| m >= floor()));
+		assert_eq!(*multipliers.last().unwrap(), floor());
+	}
+
+	#[test]
+	fn full_blocks_raise_fees_up_to_the_cap() {
+		let multipliers = simulate(Fixed64::default(), vec![max(); 5_000]);
+		assert!(multipliers.windows(2).all(|w| w[1] > w[0]));
+		assert!(*multipliers.last().unwrap() > Fixed64::from_natural(1));
+
+		let start = Fixed64::from_natural(MAXIMUM_MULTIPLIER - 1);
+		let multipliers = simulate(start, vec![max(); 5_000]);
+		assert!(multipliers.iter().all(|&m| m <= cap()));
+		assert_eq!(*multipliers.last().unwrap(), cap());
+	}
+
+	#[test]
+	fn weights_above_the_maximum_count_as_full_blocks() {
+		assert_eq!(
+			simulate(Fixed64::default(), vec![max()]),
+			simulate(Fixed64::default(), vec![Weight::max_value()]),
+		);
+	}
+
+	#[test]
+	fn fees_recover_after_congestion() {
+		let congestion = simulate(Fixed64::default(), vec![max(); 3_000]);
+		let peak = *congestion.last().unwrap();
+		let recovery = simulate(peak, vec![target() / 2; 50_000]);
+
+		assert!(recovery.windows(2).all(|w| w[1] <= w[0]));
+		assert_eq!(*recovery.last().unwrap(), floor());
+	}
+
+	#[test]
+	fn load_fluctuating_around_the_target_stays_near_neutral() {
+		let swing = target() / 2;
+		// a cheap deterministic sequence of loads between `target - swing` and `target + swing`
+		let weights = (0..10_000u64)
+			.map(|i| (i * 7_919) % 1_000)
+			.map(|x| target() - swing + (2 * swing as u64 * x / 999) as Weight);
+		let multipliers = simulate(Fixed64::default(), weights);
+
+		let bound = Fixed64::from_rational(1, 100);
+		assert!(multipliers.iter().all(|&m| m < bound && m > Fixed64::default().saturating_sub(bound)));
+	}
+
+	#[test]
+	fn weight_fee_grows_faster_than_weight() {
+		assert_eq!(WeightToFee::convert(0), 0);
+		assert_eq!(WeightToFee::convert(max()), 11 * Balance::from(max()));
+
+		// ten light extrinsics are cheaper than one ten times as heavy
+		let light = WeightToFee::convert(max() / 100);
+		let heavy = WeightToFee::convert(max() / 10);
+		assert!(10 * light < heavy);
+		assert!(light >= Balance::from(max() / 100));
+	}
+}
@@ -0,0 +1,59 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! The price of contract gas, following the fee multiplier.
+//!
+//! `contracts` charges a gas price fixed at genesis. This module sets it at the start of every
+//! block to `Price`, which the runtime derives from the fee multiplier of `transaction_payment`, so
+//! contracts get dearer along with extrinsics when blocks are full.
+
+use frame_support::{decl_module, storage::unhashed, traits::{Currency, Get}};
+
+type BalanceOf<T> =
+	<<T as contracts::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: contracts::Trait {
+	/// The gas price of the current block.
+	type Price: Get<BalanceOf<Self>>;
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn on_initialize(_n: T::BlockNumber) {
+			let price = T::Price::get();
+			if price != <contracts::Module<T>>::gas_price() {
+				// `GasPrice` is private to `pallet-contracts`, so its key is spelled out here
+				unhashed::put(&sp_io::hashing::twox_128(b"Contract GasPrice"), &price);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{Runtime, Contracts, GasPrice, MaximumBlockWeight, System, TransactionPayment};
+	use frame_support::traits::Get;
+	use sp_runtime::{Fixed64, traits::{OnFinalize, OnInitialize}};
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		system::GenesisConfig::default().build_storage::<Runtime>().unwrap().into()
+	}
+
+	#[test]
+	fn gas_price_follows_the_fee_multiplier() {
+		new_test_ext().execute_with(|| {
+			GasPrice::on_initialize(1);
+			assert_eq!(Contracts::gas_price(), 1);
+
+			for n in 1..5_000 {
+				System::set_block_limits(MaximumBlockWeight::get(), 0);
+				TransactionPayment::on_finalize(n);
+			}
+			let multiplier = TransactionPayment::next_fee_multiplier();
+			assert!(multiplier > Fixed64::from_natural(1));
+
+			GasPrice::on_initialize(5_000);
+			assert_eq!(Contracts::gas_price(), multiplier.saturated_multiply_accumulate(1));
+			assert!(Contracts::gas_price() > 1);
+		})
+	}
+}
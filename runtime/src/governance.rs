@@ -0,0 +1,224 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Root calls dispatched by council motions.
+//!
+//! A motion of the `collective` council that calls `dispatch_as_root` runs its proposal with the
+//! root origin once enough members voted for it, taking the place of the sudo key.
+
+use frame_support::{
+	decl_module, decl_event, dispatch::DispatchResult, traits::EnsureOrigin,
+	weights::{FunctionOf, GetDispatchInfo}, Parameter,
+};
+use sp_runtime::traits::Dispatchable;
+use sp_std::prelude::*;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+	/// A call that can be dispatched as root.
+	type Proposal: Parameter + Dispatchable<Origin = Self::Origin> + GetDispatchInfo;
+	/// The origin that may dispatch calls as root, a council majority in the runtime.
+	type RootOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Dispatch `proposal` as root. Its outcome is reported by `Dispatched`, not as an error.
+		/// Weighs as much as `proposal` and is of its dispatch class.
+		#[weight = FunctionOf(
+			|args: (&Box<T::Proposal>,)| args.0.get_dispatch_info().weight.saturating_add(10_000),
+			|args: (&Box<T::Proposal>,)| args.0.get_dispatch_info().class,
+			true,
+		)]
+		pub fn dispatch_as_root(origin, proposal: Box<T::Proposal>) -> DispatchResult {
+			T::RootOrigin::ensure_origin(origin)?;
+			let ok = proposal.dispatch(system::RawOrigin::Root.into()).is_ok();
+
+			Self::deposit_event(Event::Dispatched(ok));
+			Ok(())
+		}
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// A proposal was dispatched as root; whether it succeeded.
+		Dispatched(bool),
+	}
+);
+
+#[cfg(test)]
+mod tests {
+	use super::{Module, Trait, Event};
+	use crate::governance;
+
+	use sp_core::{H256, u32_trait::{_2, _3}};
+	use frame_support::{
+		impl_outer_origin, impl_outer_event, impl_outer_dispatch, assert_ok, parameter_types,
+		weights::Weight,
+	};
+	use sp_runtime::{
+		traits::{BlakeTwo256, Hash, IdentityLookup}, testing::Header, Perbill,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {
+			collective<T>,
+		}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for TestRuntime where origin: Origin {
+			system::System,
+			governance::Governance,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = Call;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	impl collective::Trait for TestRuntime {
+		type Origin = Origin;
+		type Proposal = Call;
+		type Event = TestEvent;
+	}
+
+	mod governance_event {
+		pub use crate::governance::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for TestRuntime {
+			governance_event,
+			collective<T>,
+		}
+	}
+
+	impl Trait for TestRuntime {
+		type Event = TestEvent;
+		type Proposal = Call;
+		type RootOrigin = collective::EnsureProportionAtLeast<_2, _3, u64>;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type Council = collective::Module<TestRuntime>;
+	type Governance = Module<TestRuntime>;
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let mut storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			collective::GenesisConfig::<TestRuntime> {
+				members: vec![1, 2, 3],
+				phantom: Default::default(),
+			}.assimilate_storage(&mut storage).unwrap();
+
+			let mut ext = sp_io::TestExternalities::from(storage);
+			ext.execute_with(|| System::set_block_number(1));
+			ext
+		}
+	}
+
+	/// A root-only call that leaves a trace.
+	fn set_storage() -> Call {
+		Call::System(system::Call::set_storage(vec![(b"key".to_vec(), b"value".to_vec())]))
+	}
+
+	fn stored() -> Option<Vec<u8>> {
+		sp_io::storage::get(b"key")
+	}
+
+	fn dispatched(ok: bool) -> bool {
+		let expected_event = TestEvent::governance_event(Event::Dispatched(ok));
+		System::events().iter().any(|a| a.event == expected_event)
+	}
+
+	#[test]
+	fn council_majority_dispatches_as_root() {
+		ExtBuilder::build().execute_with(|| {
+			let origin = collective::RawOrigin::Members(2, 3).into();
+			assert_ok!(Governance::dispatch_as_root(origin, Box::new(set_storage())));
+
+			assert_eq!(stored(), Some(b"value".to_vec()));
+			assert!(dispatched(true));
+		})
+	}
+
+	#[test]
+	fn others_can_not_dispatch_as_root() {
+		ExtBuilder::build().execute_with(|| {
+			let minority = collective::RawOrigin::Members(1, 3).into();
+			assert!(Governance::dispatch_as_root(minority, Box::new(set_storage())).is_err());
+			assert!(Governance::dispatch_as_root(Origin::signed(1), Box::new(set_storage())).is_err());
+			assert!(Governance::dispatch_as_root(Origin::ROOT, Box::new(set_storage())).is_err());
+
+			assert_eq!(stored(), None);
+		})
+	}
+
+	#[test]
+	fn failed_proposals_are_reported() {
+		ExtBuilder::build().execute_with(|| {
+			// `remark` needs a signed origin
+			let remark = Call::System(system::Call::remark(vec![]));
+			let origin = collective::RawOrigin::Members(3, 3).into();
+			assert_ok!(Governance::dispatch_as_root(origin, Box::new(remark)));
+
+			assert!(dispatched(false));
+		})
+	}
+
+	#[test]
+	fn motions_dispatch_once_the_threshold_is_reached() {
+		ExtBuilder::build().execute_with(|| {
+			let proposal = Call::Governance(super::Call::dispatch_as_root(Box::new(set_storage())));
+			let hash = BlakeTwo256::hash_of(&proposal);
+
+			assert_ok!(Council::propose(Origin::signed(1), 2, Box::new(proposal)));
+			assert_eq!(stored(), None);
+
+			assert_ok!(Council::vote(Origin::signed(2), hash, 0, true));
+			assert_eq!(stored(), Some(b"value".to_vec()));
+			assert!(dispatched(true));
+		})
+	}
+
+	#[test]
+	fn motions_below_two_thirds_do_not_dispatch() {
+		ExtBuilder::build().execute_with(|| {
+			// a threshold of one executes at once, with one of three members behind it
+			let proposal = Call::Governance(super::Call::dispatch_as_root(Box::new(set_storage())));
+			assert_ok!(Council::propose(Origin::signed(1), 1, Box::new(proposal)));
+
+			assert_eq!(stored(), None);
+			assert!(!dispatched(true));
+		})
+	}
+}
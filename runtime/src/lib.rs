@@ -9,13 +9,15 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use sp_std::prelude::*;
-use sp_core::OpaqueMetadata;
+use sp_core::{OpaqueMetadata, u32_trait::{_2, _3}};
+use codec::{Encode, Decode};
 use sp_runtime::{
 	ApplyExtrinsicResult, transaction_validity::TransactionValidity, generic, create_runtime_str,
-	impl_opaque_keys, MultiSignature
+	impl_opaque_keys, MultiSignature, RuntimeDebug,
 };
 use sp_runtime::traits::{
-	NumberFor, BlakeTwo256, Block as BlockT, StaticLookup, Verify, ConvertInto, IdentifyAccount
+	NumberFor, BlakeTwo256, Block as BlockT, StaticLookup, Verify, ConvertInto, IdentifyAccount,
+	OpaqueKeys, Dispatchable,
 };
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -24,15 +26,20 @@ use grandpa::fg_primitives;
 use sp_version::RuntimeVersion;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
+use contracts_rpc_runtime_api::ContractExecResult;
 
 // A few exports that help ease life for downstream crates.
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
 pub use timestamp::Call as TimestampCall;
 pub use balances::Call as BalancesCall;
+pub use contracts::Schedule as ContractsSchedule;
+pub use system::{EventRecord, Phase};
 pub use sp_runtime::{Permill, Perbill};
+use frame_support::dispatch::{DecodeDifferent, DispatchError, FunctionMetadata, IsSubType};
+use frame_support::weights::{DispatchInfo, GetDispatchInfo};
 pub use frame_support::{
-	StorageValue, construct_runtime, parameter_types,
+	StorageValue, StorageMap, construct_runtime, parameter_types,
 	traits::Randomness,
 	weights::Weight,
 };
@@ -54,6 +61,15 @@ pub type AccountIndex = u32;
 /// Balance of an account.
 pub type Balance = u128;
 
+/// Identifies an asset of `assets`.
+pub type AssetId = u32;
+
+/// Identifies a collection of `nft`.
+pub type CollectionId = u32;
+
+/// Identifies an item within its collection of `nft`.
+pub type ItemId = u32;
+
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
@@ -66,15 +82,82 @@ pub type DigestItem = generic::DigestItem<Hash>;
 /// Used for the module template in `./template.rs`
 mod template;
 
-mod simple_event;
+pub mod simple_event;
+
+pub mod single_value;
+
+pub mod vec_set;
+
+pub mod simple_map;
+
+pub mod linked_map;
+
+pub mod fees;
+
+pub mod fee_quota;
+
+pub mod validator_set;
+
+pub mod governance;
+
+pub mod vesting;
+
+pub mod scheduler;
+
+pub mod utility;
+
+pub mod proxy;
+
+pub mod pause;
+
+pub mod assets;
+
+pub mod nft;
+
+pub mod gas_price;
+
+/// Storage keys of the demo modules' items, used to request and check storage read proofs.
+#[cfg(feature = "std")]
+pub mod storage_keys {
+	use super::*;
+
+	/// Key of `account`'s entry in `simple_map::SimpleMap`.
+	pub fn simple_map(account: &AccountId) -> Vec<u8> {
+		<simple_map::SimpleMap<Runtime>>::hashed_key_for(account)
+	}
+
+	/// Key of `vec_set::Members`.
+	pub fn members() -> Vec<u8> {
+		<vec_set::Members<Runtime>>::hashed_key().to_vec()
+	}
 
-mod single_value;
+	/// Key of `single_value::MyValue`.
+	pub fn my_value() -> Vec<u8> {
+		<single_value::MyValue>::hashed_key().to_vec()
+	}
+
+	/// Key of `who`'s entry in `system::AccountNonce`, the `CheckNonce` input of their next extrinsic.
+	pub fn account_nonce(who: &AccountId) -> Vec<u8> {
+		<system::AccountNonce<Runtime>>::hashed_key_for(who)
+	}
 
-mod vec_set;
+	/// Key of `system::Events`, the events of the block the state belongs to.
+	///
+	/// `Events` is private to `frame-system`, so its key is spelled out here.
+	pub fn events() -> Vec<u8> {
+		sp_io::hashing::twox_128(b"System Events").to_vec()
+	}
 
-mod simple_map;
+	/// Key of `collective::Members` of the council.
+	pub fn council_members() -> Vec<u8> {
+		<collective::Members<Runtime, CouncilCollective>>::hashed_key().to_vec()
+	}
 
-mod linked_map;
+	/// Key of `sudo::Key`, private to `pallet-sudo` like `system::Events`.
+	pub fn sudo_key() -> Vec<u8> {
+		sp_io::hashing::twox_128(b"Sudo Key").to_vec()
+	}
+}
 
 /// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
 /// the specifics of the runtime. They can then be made to be agnostic over specific formats
@@ -105,7 +188,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("substrate-demo"),
 	impl_name: create_runtime_str!("substrate-demo"),
 	authoring_version: 1,
-	spec_version: 1,
+	// 2: fee quotas and pausing in `SignedExtra`, and the modules added since genesis
+	spec_version: 2,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 };
@@ -181,6 +265,31 @@ impl grandpa::Trait for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const SessionPeriod: BlockNumber = 10 * MINUTES;
+	pub const SessionOffset: BlockNumber = 0;
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(17);
+}
+
+impl session::Trait for Runtime {
+	type Event = Event;
+	type ValidatorId = AccountId;
+	/// Validators are identified by their account.
+	type ValidatorIdOf = ConvertInto;
+	type ShouldEndSession = session::PeriodicSessions<SessionPeriod, SessionOffset>;
+	/// The validator set root manages.
+	type OnSessionEnding = ValidatorSet;
+	/// Hands the keys of each new session to Aura and GRANDPA.
+	type SessionHandler = <opaque::SessionKeys as OpaqueKeys>::KeyTypeIdProviders;
+	type Keys = opaque::SessionKeys;
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+	type SelectInitialValidators = ();
+}
+
+impl validator_set::Trait for Runtime {
+	type Event = Event;
+}
+
 impl indices::Trait for Runtime {
 	/// The type for recording indexing into the account enumeration. If this ever overflows, there
 	/// will be problems!
@@ -227,8 +336,10 @@ impl balances::Trait for Runtime {
 }
 
 parameter_types! {
-	pub const TransactionBaseFee: Balance = 0;
+	pub const TransactionBaseFee: Balance = 1_000;
 	pub const TransactionByteFee: Balance = 1;
+	// The share of `MaximumBlockWeight` the fee multiplier steers blocks towards.
+	pub const TargetBlockFullness: Perbill = Perbill::from_percent(25);
 }
 
 impl transaction_payment::Trait for Runtime {
@@ -236,15 +347,35 @@ impl transaction_payment::Trait for Runtime {
 	type OnTransactionPayment = ();
 	type TransactionBaseFee = TransactionBaseFee;
 	type TransactionByteFee = TransactionByteFee;
-	type WeightToFee = ConvertInto;
-	type FeeMultiplierUpdate = ();
+	type WeightToFee = fees::WeightToFee;
+	type FeeMultiplierUpdate = fees::TargetedFeeAdjustment<TargetBlockFullness>;
 }
 
+/// Kept only so chains started with a sudo key can hand over to the council, see the README.
 impl sudo::Trait for Runtime {
 	type Event = Event;
 	type Proposal = Call;
 }
 
+type CouncilCollective = collective::Instance1;
+impl collective::Trait<CouncilCollective> for Runtime {
+	type Origin = Origin;
+	type Proposal = Call;
+	type Event = Event;
+}
+
+impl vesting::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+}
+
+impl governance::Trait for Runtime {
+	type Event = Event;
+	type Proposal = Call;
+	/// Two thirds of the council.
+	type RootOrigin = collective::EnsureProportionAtLeast<_2, _3, AccountId, CouncilCollective>;
+}
+
 /// Used for the module template in `./template.rs`
 impl template::Trait for Runtime {
 	type Event = Event;
@@ -262,14 +393,275 @@ impl vec_set::Trait for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const MaxExpiriesPerBlock: u32 = 50;
+	pub const SimpleMapExpiryDeposit: Balance = 1_000;
+}
+
 impl simple_map::Trait for Runtime {
 	type Event = Event;
+	type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+	type Currency = Balances;
+	type ExpiryDeposit = SimpleMapExpiryDeposit;
 }
 
 impl linked_map::Trait for Runtime {
 	type Event = Event;
 }
 
+parameter_types! {
+	pub const FreeTransactions: u32 = 100;
+	pub const QuotaEraLength: BlockNumber = HOURS;
+}
+
+impl fee_quota::Trait for Runtime {
+	type FreeTransactions = FreeTransactions;
+	type EraLength = QuotaEraLength;
+}
+
+parameter_types! {
+	/// A quarter of `MaximumBlockWeight`, taken from what extrinsics may use in blocks running tasks.
+	pub const MaximumSchedulerWeight: Weight = 250_000;
+	pub const MaxScheduledPerBlock: u32 = 50;
+	/// Leaves ten slots of every block to root.
+	pub const MaxSignedPerBlock: u32 = 40;
+	pub const MaxScheduledPerOrigin: u32 = 10;
+	pub const SchedulerTaskDeposit: Balance = 1_000;
+}
+
+impl scheduler::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type CallFilter = Pause;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type MaxSignedPerBlock = MaxSignedPerBlock;
+	type MaxScheduledPerOrigin = MaxScheduledPerOrigin;
+	type Currency = Balances;
+	type TaskDeposit = SchedulerTaskDeposit;
+}
+
+parameter_types! {
+	pub const MultisigDepositBase: Balance = 1_000;
+	pub const MultisigDepositFactor: Balance = 100;
+	pub const MaxSignatories: u16 = 20;
+}
+
+impl utility::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type CallFilter = Pause;
+	type Currency = Balances;
+	type MultisigDepositBase = MultisigDepositBase;
+	type MultisigDepositFactor = MultisigDepositFactor;
+	type MaxSignatories = MaxSignatories;
+}
+
+/// What a proxy may dispatch for the account it acts for.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+pub enum ProxyType {
+	/// Any call.
+	Any,
+	/// Any call but those of `balances`, `sudo`, and those wrapping other calls, unless every
+	/// call of a batch is allowed.
+	NonTransfer,
+	/// Only the calls of the demo modules, in batches or not.
+	DemoPallets,
+}
+
+impl proxy::ProxyFilter<Call> for ProxyType {
+	fn allows(&self, call: &Call) -> bool {
+		match (self, call) {
+			(ProxyType::Any, _) => true,
+			(_, Call::Utility(utility::Call::batch(calls))) |
+			(_, Call::Utility(utility::Call::batch_all(calls))) => calls.iter().all(|call| self.allows(call)),
+			(ProxyType::NonTransfer, call) => match call {
+				Call::Balances(..) | Call::Assets(assets::Call::transfer(..)) | Call::Nft(nft::Call::transfer(..)) |
+				Call::Nft(nft::Call::approve(..)) | Call::Nft(nft::Call::transfer_from(..)) | Call::Sudo(..) |
+				Call::Contracts(..) | Call::Utility(..) | Call::Scheduler(..) | Call::Proxy(..) => false,
+				_ => true,
+			},
+			(ProxyType::DemoPallets, call) => match call {
+				Call::SimpleMapModule(..) | Call::SingleValueModule(..) | Call::VecValueModule(..) |
+				Call::LinkedMapModule(..) => true,
+				_ => false,
+			},
+		}
+	}
+}
+
+parameter_types! {
+	pub const ProxyDepositBase: Balance = 1_000;
+	pub const ProxyDepositFactor: Balance = 100;
+	pub const MaxProxies: u32 = 32;
+}
+
+impl proxy::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type CallFilter = Pause;
+	type Currency = Balances;
+	type ProxyType = ProxyType;
+	type ProxyDepositBase = ProxyDepositBase;
+	type ProxyDepositFactor = ProxyDepositFactor;
+	type MaxProxies = MaxProxies;
+}
+
+parameter_types! {
+	pub const AssetStringLimit: u32 = 32;
+}
+
+impl assets::Trait for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type StringLimit = AssetStringLimit;
+}
+
+parameter_types! {
+	pub const NftMetadataLimit: u32 = 256;
+}
+
+impl nft::Trait for Runtime {
+	type Event = Event;
+	type CollectionId = CollectionId;
+	type ItemId = ItemId;
+	type MetadataLimit = NftMetadataLimit;
+}
+
+parameter_types! {
+	pub const ContractFee: Balance = 1_000;
+	pub const TombstoneDeposit: Balance = 16_000;
+	pub const RentByteFee: Balance = 4;
+	pub const RentDepositOffset: Balance = 100_000;
+	pub const SurchargeReward: Balance = 15_000;
+}
+
+/// A call dispatched by a contract, which fails if `pause` paused it.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct ContractCall(pub Call);
+
+impl Dispatchable for ContractCall {
+	type Origin = Origin;
+	type Trait = Runtime;
+	type Error = DispatchError;
+
+	fn dispatch(self, origin: Origin) -> Result<(), DispatchError> {
+		if !<Pause as pause::CallFilter<Call>>::allows(&self.0) {
+			return Err("the call is paused".into());
+		}
+		self.0.dispatch(origin).map_err(Into::into)
+	}
+}
+
+impl IsSubType<contracts::Module<Runtime>, Runtime> for ContractCall {
+	fn is_sub_type(&self) -> Option<&contracts::Call<Runtime>> {
+		self.0.is_sub_type()
+	}
+}
+
+impl GetDispatchInfo for ContractCall {
+	fn get_dispatch_info(&self) -> DispatchInfo {
+		self.0.get_dispatch_info()
+	}
+}
+
+/// Contracts pay the fees of `transaction_payment` and `balances` for the calls they dispatch and
+/// the transfers they make; the price of gas follows the fee multiplier through `gas_price`. The calls they dispatch are
+/// checked against `pause` like those of `utility`, `scheduler` and `proxy`.
+impl contracts::Trait for Runtime {
+	type Currency = Balances;
+	type Time = Timestamp;
+	type Randomness = RandomnessCollectiveFlip;
+	type Call = ContractCall;
+	type Event = Event;
+	type DetermineContractAddress = contracts::SimpleAddressDeterminator<Runtime>;
+	type ComputeDispatchFee = contracts::DefaultDispatchFeeComputor<Runtime>;
+	type TrieIdGenerator = contracts::TrieIdFromParentCounter<Runtime>;
+	type GasPayment = ();
+	type RentPayment = ();
+	type SignedClaimHandicap = contracts::DefaultSignedClaimHandicap;
+	type TombstoneDeposit = TombstoneDeposit;
+	type StorageSizeOffset = contracts::DefaultStorageSizeOffset;
+	type RentByteFee = RentByteFee;
+	type RentDepositOffset = RentDepositOffset;
+	type SurchargeReward = SurchargeReward;
+	type TransferFee = TransferFee;
+	type CreationFee = CreationFee;
+	type TransactionBaseFee = TransactionBaseFee;
+	type TransactionByteFee = TransactionByteFee;
+	type ContractFee = ContractFee;
+	type CallBaseFee = contracts::DefaultCallBaseFee;
+	type InstantiateBaseFee = contracts::DefaultInstantiateBaseFee;
+	type MaxDepth = contracts::DefaultMaxDepth;
+	type MaxValueSize = contracts::DefaultMaxValueSize;
+	type BlockGasLimit = contracts::DefaultBlockGasLimit;
+}
+
+impl gas_price::Trait for Runtime {
+	type Price = fees::ContractGasPrice;
+}
+
+parameter_types! {
+	/// The modules keeping the chain and its governance running, and the one unpausing.
+	pub const PauseExempt: &'static [&'static str] = &[
+		"System", "Timestamp", "Sudo", "Council", "Governance", "Pause",
+	];
+}
+
+impl pause::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Exempt = PauseExempt;
+}
+
+/// Names the call at `index` in `functions`, the metadata of its module's calls, which lists them
+/// in the order they are indexed in.
+fn call_name(index: u8, functions: &'static [FunctionMetadata]) -> &'static str {
+	match functions.get(index as usize).map(|function| &function.name) {
+		Some(DecodeDifferent::Encode(name)) => *name,
+		_ => "",
+	}
+}
+
+macro_rules! impl_call_name {
+	($( $module:ident ),* $(,)?) => {
+		impl pause::CallName for Call {
+			fn call_name(&self) -> (&'static str, &'static str) {
+				match self {
+					$(
+						Call::$module(call) => (
+							stringify!($module),
+							call.using_encoded(|encoded| call_name(encoded[0], $module::call_functions())),
+						),
+					)*
+				}
+			}
+
+			fn exists(pallet: &[u8], call: Option<&[u8]>) -> bool {
+				$(
+					if pallet == stringify!($module).as_bytes() {
+						return call.map_or(true, |call| $module::call_functions().iter().any(|function| {
+							match &function.name {
+								DecodeDifferent::Encode(name) => name.as_bytes() == call,
+								_ => false,
+							}
+						}));
+					}
+				)*
+				false
+			}
+		}
+	}
+}
+
+impl_call_name!(
+	System, Timestamp, Grandpa, Indices, Balances, Sudo, TemplateModule, RandomnessCollectiveFlip,
+	SimpleEventModule, SingleValueModule, VecValueModule, SimpleMapModule, LinkedMapModule,
+	ValidatorSet, Session, Council, Governance, Vesting, Scheduler, Utility, Proxy, Pause, Assets,
+	Nft, Contracts,
+);
+
 construct_runtime!(
 	pub enum Runtime where
 		Block = Block,
@@ -292,6 +684,20 @@ construct_runtime!(
 		VecValueModule: vec_set::{Module, Call, Storage, Event<T>},
 		SimpleMapModule: simple_map::{Module, Call, Storage, Event<T>},
 		LinkedMapModule: linked_map::{Module, Call, Storage, Event<T>},
+		FeeQuota: fee_quota::{Module, Storage},
+		ValidatorSet: validator_set::{Module, Call, Storage, Event<T>, Config<T>},
+		Session: session::{Module, Call, Storage, Event, Config<T>},
+		Council: collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+		Governance: governance::{Module, Call, Event},
+		Vesting: vesting::{Module, Call, Storage, Event<T>, Config<T>},
+		Scheduler: scheduler::{Module, Call, Storage, Event<T>},
+		Utility: utility::{Module, Call, Storage, Event<T>},
+		Proxy: proxy::{Module, Call, Storage, Event<T>},
+		Pause: pause::{Module, Call, Storage, Event},
+		Assets: assets::{Module, Call, Storage, Event<T>},
+		Nft: nft::{Module, Call, Storage, Event<T>},
+		Contracts: contracts::{Module, Call, Storage, Event<T>, Config<T>},
+		GasPrice: gas_price::{Module},
 	}
 );
 
@@ -312,7 +718,8 @@ pub type SignedExtra = (
 	system::CheckEra<Runtime>,
 	system::CheckNonce<Runtime>,
 	system::CheckWeight<Runtime>,
-	transaction_payment::ChargeTransactionPayment<Runtime>
+	pause::CheckPaused<Runtime>,
+	fee_quota::ChargeTransactionPaymentOrQuota<Runtime>
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -321,6 +728,43 @@ pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExt
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<Runtime, Block, system::ChainContext<Runtime>, Runtime, AllModules>;
 
+sp_api::decl_runtime_apis! {
+	/// Read-only queries over the demo modules, so the node does not have to decode raw storage.
+	pub trait DemoApi {
+		/// Number of accounts in `vec_set::Members`.
+		fn member_count() -> u32;
+		/// The accounts in `vec_set::Members`.
+		fn members() -> Vec<AccountId>;
+		/// Current value of `linked_map::TheCounter`.
+		fn the_counter() -> u32;
+		/// Number of entries in `simple_map::SimpleMap`, on chains that had none before it was counted.
+		fn simple_map_entries() -> u32;
+		/// The entry of `who` in `simple_map::SimpleMap` and its version, if it has one.
+		fn simple_map_entry(who: AccountId) -> Option<(u32, u64)>;
+		/// Events deposited by the block this is called at.
+		#[skip_initialize_block]
+		fn block_events() -> Vec<EventRecord<Event, Hash>>;
+	}
+
+	/// Balances and metadata of `assets`, so clients do not have to decode raw storage.
+	pub trait AssetsApi {
+		/// The balance of `who` of asset `id`.
+		fn balance(id: AssetId, who: AccountId) -> Balance;
+		/// The supply of asset `id`, zero if it does not exist.
+		fn total_supply(id: AssetId) -> Balance;
+		/// The metadata of asset `id`, if it exists.
+		fn metadata(id: AssetId) -> Option<assets::AssetMetadata>;
+	}
+
+	/// Items of `nft`, so clients do not have to decode raw storage.
+	pub trait NftApi {
+		/// Up to `count` items owned by `who`, skipping the first `start`, reading only those.
+		fn items_of(who: AccountId, start: u32, count: u32) -> Vec<(CollectionId, ItemId)>;
+		/// Item `item` of `collection`, if it exists.
+		fn item(collection: CollectionId, item: ItemId) -> Option<nft::Item<AccountId>>;
+	}
+}
+
 impl_runtime_apis! {
 	impl sp_api::Core<Block> for Runtime {
 		fn version() -> RuntimeVersion {
@@ -400,4 +844,81 @@ impl_runtime_apis! {
 			Grandpa::grandpa_authorities()
 		}
 	}
+
+	impl self::DemoApi<Block> for Runtime {
+		fn member_count() -> u32 {
+			VecValueModule::members().len() as u32
+		}
+
+		fn members() -> Vec<AccountId> {
+			VecValueModule::members()
+		}
+
+		fn the_counter() -> u32 {
+			LinkedMapModule::the_counter()
+		}
+
+		fn simple_map_entries() -> u32 {
+			SimpleMapModule::entry_count()
+		}
+
+		fn simple_map_entry(who: AccountId) -> Option<(u32, u64)> {
+			SimpleMapModule::entry(&who)
+		}
+
+		fn block_events() -> Vec<EventRecord<Event, Hash>> {
+			System::events()
+		}
+	}
+
+	impl self::AssetsApi<Block> for Runtime {
+		fn balance(id: AssetId, who: AccountId) -> Balance {
+			Assets::balance((id, who))
+		}
+
+		fn total_supply(id: AssetId) -> Balance {
+			Assets::total_supply(id)
+		}
+
+		fn metadata(id: AssetId) -> Option<assets::AssetMetadata> {
+			Assets::details(id).map(|_| Assets::metadata(id))
+		}
+	}
+
+	impl self::NftApi<Block> for Runtime {
+		fn items_of(who: AccountId, start: u32, count: u32) -> Vec<(CollectionId, ItemId)> {
+			Nft::items_of(&who, start, count)
+		}
+
+		fn item(collection: CollectionId, item: ItemId) -> Option<nft::Item<AccountId>> {
+			Nft::item((collection, item))
+		}
+	}
+
+	impl contracts_rpc_runtime_api::ContractsApi<Block, AccountId, Balance> for Runtime {
+		fn call(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: u64,
+			input_data: Vec<u8>,
+		) -> ContractExecResult {
+			match Contracts::bare_call(origin, dest.into(), value, gas_limit, input_data) {
+				Ok(v) => ContractExecResult::Success {
+					status: v.status,
+					data: v.data,
+				},
+				Err(_) => ContractExecResult::Error,
+			}
+		}
+
+		fn get_storage(address: AccountId, key: [u8; 32]) -> contracts_rpc_runtime_api::GetStorageResult {
+			use contracts_rpc_runtime_api::GetStorageError as RpcGetStorageError;
+
+			Contracts::get_storage(address, key).map_err(|e| match e {
+				contracts::GetStorageError::ContractDoesntExist => RpcGetStorageError::ContractDoesntExist,
+				contracts::GetStorageError::IsTombstone => RpcGetStorageError::IsTombstone,
+			})
+		}
+	}
 }
@@ -0,0 +1,475 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Non-fungible items grouped in collections.
+//!
+//! The account creating a collection is its issuer: it alone mints items into it and sets their
+//! metadata. An item's owner transfers or burns it, and may approve one other account to transfer
+//! it once. `Owned` numbers the items of every owner from zero, so a page of them is listed by
+//! reading only the items on it.
+
+use codec::{Encode, Decode};
+use frame_support::{decl_module, decl_storage, decl_event, dispatch::DispatchResult, ensure, traits::Get, Parameter};
+use sp_runtime::{RuntimeDebug, traits::{Member, One, SimpleArithmetic}};
+use sp_std::prelude::*;
+use system::ensure_signed;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Identifies a collection.
+	type CollectionId: Parameter + Member + SimpleArithmetic + Default + Copy;
+	/// Identifies an item within its collection.
+	type ItemId: Parameter + Member + SimpleArithmetic + Default + Copy;
+	/// Bytes of metadata an item may have.
+	type MetadataLimit: Get<u32>;
+}
+
+/// A collection of items.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct Collection<AccountId> {
+	/// The account minting the collection's items.
+	pub issuer: AccountId,
+	/// Items of the collection that were not burned.
+	pub items: u32,
+}
+
+/// A unique item.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct Item<AccountId> {
+	pub owner: AccountId,
+	/// The account the owner allowed to transfer the item, until it is transferred.
+	pub approved: Option<AccountId>,
+	pub metadata: Vec<u8>,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Nft {
+		/// Every collection.
+		pub Collections get(fn collection): map T::CollectionId => Option<Collection<T::AccountId>>;
+		/// Every item, by collection and item id.
+		pub Items get(fn item): map (T::CollectionId, T::ItemId) => Option<Item<T::AccountId>>;
+		/// The items of each owner, numbered from zero to `OwnedCount`.
+		pub Owned get(fn owned):
+			double_map hasher(blake2_256) T::AccountId, blake2_256(u32) => Option<(T::CollectionId, T::ItemId)>;
+		/// How many items each owner has.
+		pub OwnedCount get(fn owned_count): map T::AccountId => u32;
+		/// The number of each item in `Owned` of its owner.
+		OwnedIndex: map (T::CollectionId, T::ItemId) => u32;
+		/// The id of the next collection created.
+		NextCollectionId get(fn next_collection_id): T::CollectionId;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		const MetadataLimit: u32 = T::MetadataLimit::get();
+
+		/// Create a collection issued by the caller.
+		pub fn create_collection(origin) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+
+			let id = Self::next_collection_id();
+			<NextCollectionId<T>>::put(id + One::one());
+			<Collections<T>>::insert(id, Collection { issuer: who.clone(), items: 0 });
+
+			Self::deposit_event(RawEvent::CollectionCreated(id, who));
+			Ok(())
+		}
+
+		/// Mint item `item` of `collection` to `owner`. Only the collection's issuer may.
+		pub fn mint(
+			origin,
+			collection: T::CollectionId,
+			item: T::ItemId,
+			owner: T::AccountId,
+			metadata: Vec<u8>
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut details = Self::issued_by(collection, &who)?;
+			ensure!(!<Items<T>>::exists((collection, item)), "item already exists");
+			Self::ensure_metadata_limit(&metadata)?;
+
+			details.items += 1;
+			<Collections<T>>::insert(collection, details);
+			<Items<T>>::insert((collection, item), Item { owner: owner.clone(), approved: None, metadata });
+			Self::add_owned(&owner, (collection, item));
+
+			Self::deposit_event(RawEvent::Minted(collection, item, owner));
+			Ok(())
+		}
+
+		/// Transfer the caller's item `item` of `collection` to `dest`.
+		pub fn transfer(origin, collection: T::CollectionId, item: T::ItemId, dest: T::AccountId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let details = Self::item((collection, item)).ok_or("item does not exist")?;
+			ensure!(details.owner == who, "must own the item");
+
+			Self::do_transfer(collection, item, details, dest);
+			Ok(())
+		}
+
+		/// Let `delegate` transfer the caller's item `item` of `collection` once, or nobody if
+		/// `None`. Replaces an earlier approval.
+		pub fn approve(
+			origin,
+			collection: T::CollectionId,
+			item: T::ItemId,
+			delegate: Option<T::AccountId>
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut details = Self::item((collection, item)).ok_or("item does not exist")?;
+			ensure!(details.owner == who, "must own the item");
+
+			details.approved = delegate.clone();
+			<Items<T>>::insert((collection, item), details);
+
+			Self::deposit_event(RawEvent::Approved(collection, item, who, delegate));
+			Ok(())
+		}
+
+		/// Transfer item `item` of `collection`, which its owner approved the caller for, to `dest`.
+		pub fn transfer_from(
+			origin,
+			collection: T::CollectionId,
+			item: T::ItemId,
+			dest: T::AccountId
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let details = Self::item((collection, item)).ok_or("item does not exist")?;
+			ensure!(details.approved.as_ref() == Some(&who), "must be approved for the item");
+
+			Self::do_transfer(collection, item, details, dest);
+			Ok(())
+		}
+
+		/// Burn the caller's item `item` of `collection`.
+		pub fn burn(origin, collection: T::CollectionId, item: T::ItemId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let details = Self::item((collection, item)).ok_or("item does not exist")?;
+			ensure!(details.owner == who, "must own the item");
+
+			<Collections<T>>::mutate(collection, |c| if let Some(c) = c {
+				c.items -= 1;
+			});
+			<Items<T>>::remove((collection, item));
+			Self::remove_owned(&who, (collection, item));
+
+			Self::deposit_event(RawEvent::Burned(collection, item, who));
+			Ok(())
+		}
+
+		/// Replace the metadata of item `item` of `collection`. Only the collection's issuer may.
+		pub fn set_metadata(origin, collection: T::CollectionId, item: T::ItemId, metadata: Vec<u8>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::issued_by(collection, &who)?;
+			let mut details = Self::item((collection, item)).ok_or("item does not exist")?;
+			Self::ensure_metadata_limit(&metadata)?;
+
+			details.metadata = metadata;
+			<Items<T>>::insert((collection, item), details);
+
+			Self::deposit_event(RawEvent::MetadataSet(collection, item));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Up to `count` of the items of `who`, skipping the first `start`.
+	///
+	/// Items are listed in the order `who` got them, except that an item leaving takes the place
+	/// of the last one, so pages shift when `who`'s items change between them.
+	pub fn items_of(who: &T::AccountId, start: u32, count: u32) -> Vec<(T::CollectionId, T::ItemId)> {
+		let end = start.saturating_add(count).min(Self::owned_count(who));
+		(start..end).filter_map(|index| Self::owned(who, index)).collect()
+	}
+
+	/// Number `item` as the last of `who`'s items.
+	fn add_owned(who: &T::AccountId, item: (T::CollectionId, T::ItemId)) {
+		let index = Self::owned_count(who);
+		<Owned<T>>::insert(who, index, item);
+		<OwnedIndex<T>>::insert(item, index);
+		<OwnedCount<T>>::insert(who, index + 1);
+	}
+
+	/// Remove `item` from `who`'s items, moving the last one to its number.
+	fn remove_owned(who: &T::AccountId, item: (T::CollectionId, T::ItemId)) {
+		let index = <OwnedIndex<T>>::take(item);
+		let last = Self::owned_count(who).saturating_sub(1);
+		if index != last {
+			if let Some(moved) = Self::owned(who, last) {
+				<Owned<T>>::insert(who, index, moved);
+				<OwnedIndex<T>>::insert(moved, index);
+			}
+		}
+		<Owned<T>>::remove(who, last);
+		if last == 0 {
+			<OwnedCount<T>>::remove(who);
+		} else {
+			<OwnedCount<T>>::insert(who, last);
+		}
+	}
+
+	fn do_transfer(
+		collection: T::CollectionId,
+		item: T::ItemId,
+		mut details: Item<T::AccountId>,
+		dest: T::AccountId,
+	) {
+		let from = details.owner.clone();
+		Self::remove_owned(&from, (collection, item));
+		Self::add_owned(&dest, (collection, item));
+		details.owner = dest.clone();
+		details.approved = None;
+		<Items<T>>::insert((collection, item), details);
+
+		Self::deposit_event(RawEvent::Transferred(collection, item, from, dest));
+	}
+
+	/// The details of `collection` if `who` issued it.
+	fn issued_by(collection: T::CollectionId, who: &T::AccountId) -> Result<Collection<T::AccountId>, &'static str> {
+		let details = Self::collection(collection).ok_or("collection does not exist")?;
+		ensure!(&details.issuer == who, "must be the issuer of the collection");
+		Ok(details)
+	}
+
+	fn ensure_metadata_limit(metadata: &[u8]) -> DispatchResult {
+		ensure!(metadata.len() <= T::MetadataLimit::get() as usize, "metadata must not exceed the limit");
+		Ok(())
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as system::Trait>::AccountId,
+		CollectionId = <T as Trait>::CollectionId,
+		ItemId = <T as Trait>::ItemId,
+	{
+		/// A collection was created by its issuer.
+		CollectionCreated(CollectionId, AccountId),
+		/// An item was minted to its owner.
+		Minted(CollectionId, ItemId, AccountId),
+		/// An item was transferred from one owner to another.
+		Transferred(CollectionId, ItemId, AccountId, AccountId),
+		/// An item's owner approved an account to transfer it, or nobody.
+		Approved(CollectionId, ItemId, AccountId, Option<AccountId>),
+		/// An item was burned by its owner.
+		Burned(CollectionId, ItemId, AccountId),
+		/// The metadata of an item changed.
+		MetadataSet(CollectionId, ItemId),
+	}
+);
+
+#[cfg(test)]
+mod tests {
+	use super::{Module, Trait, RawEvent};
+
+	use sp_core::H256;
+	use frame_support::{impl_outer_origin, impl_outer_event, assert_ok, assert_err, parameter_types, weights::Weight};
+	use sp_runtime::{
+		traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	mod nft_event {
+		pub use crate::nft::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for TestRuntime {
+			nft_event<T>,
+		}
+	}
+
+	parameter_types! {
+		pub const MetadataLimit: u32 = 4;
+	}
+	impl Trait for TestRuntime {
+		type Event = TestEvent;
+		type CollectionId = u32;
+		type ItemId = u32;
+		type MetadataLimit = MetadataLimit;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type Nft = Module<TestRuntime>;
+
+	const ISSUER: u64 = 1;
+	const OWNER: u64 = 2;
+	const OTHER: u64 = 3;
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			let mut ext = sp_io::TestExternalities::from(storage);
+			ext.execute_with(|| {
+				System::set_block_number(1);
+				assert_ok!(Nft::create_collection(Origin::signed(ISSUER)));
+				assert_ok!(Nft::mint(Origin::signed(ISSUER), 0, 7, OWNER, b"meta".to_vec()));
+			});
+			ext
+		}
+	}
+
+	fn deposited(event: RawEvent<u64, u32, u32>) -> bool {
+		let expected_event = TestEvent::nft_event(event);
+		System::events().iter().any(|a| a.event == expected_event)
+	}
+
+	fn owner(item: u32) -> Option<u64> {
+		Nft::item((0, item)).map(|details| details.owner)
+	}
+
+	#[test]
+	fn issuers_mint_unique_items() {
+		ExtBuilder::build().execute_with(|| {
+			assert!(deposited(RawEvent::CollectionCreated(0, ISSUER)));
+			assert!(deposited(RawEvent::Minted(0, 7, OWNER)));
+			assert_eq!(owner(7), Some(OWNER));
+			assert_eq!(Nft::collection(0).unwrap().items, 1);
+
+			assert_err!(Nft::mint(Origin::signed(ISSUER), 0, 7, OTHER, vec![]), "item already exists");
+			assert_err!(Nft::mint(Origin::signed(OWNER), 0, 8, OWNER, vec![]), "must be the issuer of the collection");
+			assert_err!(Nft::mint(Origin::signed(ISSUER), 1, 8, OWNER, vec![]), "collection does not exist");
+			assert_err!(
+				Nft::mint(Origin::signed(ISSUER), 0, 8, OWNER, b"too long".to_vec()),
+				"metadata must not exceed the limit",
+			);
+		})
+	}
+
+	#[test]
+	fn owners_transfer() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(Nft::transfer(Origin::signed(OTHER), 0, 7, OTHER), "must own the item");
+			assert_ok!(Nft::transfer(Origin::signed(OWNER), 0, 7, OTHER));
+			assert!(deposited(RawEvent::Transferred(0, 7, OWNER, OTHER)));
+			assert_eq!(owner(7), Some(OTHER));
+			assert_eq!(Nft::items_of(&OWNER, 0, 10), vec![]);
+			assert_eq!(Nft::items_of(&OTHER, 0, 10), vec![(0, 7)]);
+		})
+	}
+
+	#[test]
+	fn approved_accounts_transfer_once() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(Nft::transfer_from(Origin::signed(OTHER), 0, 7, OTHER), "must be approved for the item");
+			assert_ok!(Nft::approve(Origin::signed(OWNER), 0, 7, Some(OTHER)));
+			assert!(deposited(RawEvent::Approved(0, 7, OWNER, Some(OTHER))));
+
+			assert_ok!(Nft::transfer_from(Origin::signed(OTHER), 0, 7, ISSUER));
+			assert_eq!(owner(7), Some(ISSUER));
+			assert_eq!(Nft::item((0, 7)).unwrap().approved, None);
+			assert_err!(Nft::transfer_from(Origin::signed(OTHER), 0, 7, OTHER), "must be approved for the item");
+		})
+	}
+
+	#[test]
+	fn approvals_can_be_revoked() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Nft::approve(Origin::signed(OWNER), 0, 7, Some(OTHER)));
+			assert_ok!(Nft::approve(Origin::signed(OWNER), 0, 7, None));
+			assert_err!(Nft::transfer_from(Origin::signed(OTHER), 0, 7, OTHER), "must be approved for the item");
+			assert_err!(Nft::approve(Origin::signed(OTHER), 0, 7, Some(OTHER)), "must own the item");
+		})
+	}
+
+	#[test]
+	fn owners_burn() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(Nft::burn(Origin::signed(ISSUER), 0, 7), "must own the item");
+			assert_ok!(Nft::burn(Origin::signed(OWNER), 0, 7));
+			assert!(deposited(RawEvent::Burned(0, 7, OWNER)));
+			assert_eq!(owner(7), None);
+			assert_eq!(Nft::collection(0).unwrap().items, 0);
+			assert_eq!(Nft::items_of(&OWNER, 0, 10), vec![]);
+			assert_err!(Nft::burn(Origin::signed(OWNER), 0, 7), "item does not exist");
+		})
+	}
+
+	#[test]
+	fn issuers_set_metadata() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(
+				Nft::set_metadata(Origin::signed(OWNER), 0, 7, b"mine".to_vec()),
+				"must be the issuer of the collection",
+			);
+			assert_ok!(Nft::set_metadata(Origin::signed(ISSUER), 0, 7, b"new".to_vec()));
+			assert!(deposited(RawEvent::MetadataSet(0, 7)));
+			assert_eq!(Nft::item((0, 7)).unwrap().metadata, b"new".to_vec());
+		})
+	}
+
+	#[test]
+	fn items_are_listed_by_page() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Nft::create_collection(Origin::signed(ISSUER)));
+			for item in &[3, 9, 1] {
+				assert_ok!(Nft::mint(Origin::signed(ISSUER), 1, *item, OWNER, vec![]));
+			}
+			assert_ok!(Nft::mint(Origin::signed(ISSUER), 0, 2, OTHER, vec![]));
+
+			assert_eq!(Nft::items_of(&OWNER, 0, 2), vec![(0, 7), (1, 3)]);
+			assert_eq!(Nft::items_of(&OWNER, 2, 2), vec![(1, 9), (1, 1)]);
+			assert_eq!(Nft::items_of(&OWNER, 4, 2), vec![]);
+			assert_eq!(Nft::items_of(&OTHER, 0, 10), vec![(0, 2)]);
+		})
+	}
+
+	#[test]
+	fn leaving_items_make_room_for_the_last_one() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Nft::create_collection(Origin::signed(ISSUER)));
+			for item in &[3, 9, 1] {
+				assert_ok!(Nft::mint(Origin::signed(ISSUER), 1, *item, OWNER, vec![]));
+			}
+
+			assert_ok!(Nft::burn(Origin::signed(OWNER), 1, 3));
+			assert_eq!(Nft::items_of(&OWNER, 0, 10), vec![(0, 7), (1, 1), (1, 9)]);
+			assert_ok!(Nft::transfer(Origin::signed(OWNER), 1, 9, OTHER));
+			assert_eq!(Nft::items_of(&OWNER, 0, 10), vec![(0, 7), (1, 1)]);
+			assert_eq!(Nft::owned_count(&OWNER), 2);
+			assert_eq!(Nft::items_of(&OTHER, 0, 10), vec![(1, 9)]);
+
+			assert_ok!(Nft::transfer(Origin::signed(OWNER), 0, 7, OTHER));
+			assert_ok!(Nft::burn(Origin::signed(OWNER), 1, 1));
+			assert_eq!(Nft::owned_count(&OWNER), 0);
+			assert_eq!(Nft::owned(&OWNER, 0), None);
+		})
+	}
+}
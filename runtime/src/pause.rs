@@ -0,0 +1,423 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Pausing the calls of a module, or single calls, without a runtime upgrade.
+//!
+//! Root pauses a module or call by the names `construct_runtime!` and `decl_module!` give them.
+//! `CheckPaused` in `SignedExtra` rejects transactions calling them, and `utility`, `scheduler`,
+//! `proxy` and the calls contracts dispatch check `CallFilter` before they dispatch calls on behalf
+//! of others. Root may still call paused calls directly. Modules listed in `Exempt`, this one among them, can not be paused.
+
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, dispatch::DispatchResult, ensure, traits::Get,
+	weights::DispatchInfo,
+};
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction},
+};
+use sp_std::{marker::PhantomData, prelude::*};
+use system::ensure_root;
+
+/// Names a call by its module, as in `construct_runtime!`, and its name within the module.
+pub trait CallName {
+	fn call_name(&self) -> (&'static str, &'static str);
+	/// Whether `pallet` names a module with calls, and `call` one of them if given.
+	fn exists(pallet: &[u8], call: Option<&[u8]>) -> bool;
+}
+
+/// Decides whether a call may be dispatched.
+pub trait CallFilter<Call> {
+	fn allows(call: &Call) -> bool;
+}
+
+impl<Call> CallFilter<Call> for () {
+	fn allows(_: &Call) -> bool {
+		true
+	}
+}
+
+pub trait Trait: system::Trait {
+	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+	/// A call that can be paused.
+	type Call: CallName;
+	/// Modules that can not be paused, including this one.
+	type Exempt: Get<&'static [&'static str]>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Pause {
+		/// Modules all of whose calls are paused.
+		pub PausedPallets get(fn paused_pallet): map Vec<u8> => bool;
+		/// Single paused calls, by module and call name.
+		pub PausedCalls get(fn paused_call): map (Vec<u8>, Vec<u8>) => bool;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Pause all calls of the module `pallet`, which must have calls.
+		pub fn pause_pallet(origin, pallet: Vec<u8>) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(<T as Trait>::Call::exists(&pallet, None), "the pallet does not exist");
+			Self::ensure_pausable(&pallet)?;
+			PausedPallets::insert(&pallet, true);
+
+			Self::deposit_event(Event::PalletPaused(pallet));
+			Ok(())
+		}
+
+		/// Pause the call `call` of the module `pallet`, which must exist.
+		pub fn pause_call(origin, pallet: Vec<u8>, call: Vec<u8>) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(<T as Trait>::Call::exists(&pallet, Some(&call)), "the call does not exist");
+			Self::ensure_pausable(&pallet)?;
+			PausedCalls::insert(&(pallet.clone(), call.clone()), true);
+
+			Self::deposit_event(Event::CallPaused(pallet, call));
+			Ok(())
+		}
+
+		/// Unpause the module `pallet`, or only its call `call`. Calls paused on their own stay
+		/// paused when their module is unpaused.
+		pub fn unpause(origin, pallet: Vec<u8>, call: Option<Vec<u8>>) -> DispatchResult {
+			ensure_root(origin)?;
+			match call {
+				None => {
+					ensure!(Self::paused_pallet(&pallet), "the pallet must be paused");
+					PausedPallets::remove(&pallet);
+					Self::deposit_event(Event::PalletUnpaused(pallet));
+				}
+				Some(call) => {
+					let key = (pallet, call);
+					ensure!(Self::paused_call(&key), "the call must be paused");
+					PausedCalls::remove(&key);
+					Self::deposit_event(Event::CallUnpaused(key.0, key.1));
+				}
+			}
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Whether `call` is paused, by itself or with its module.
+	pub fn is_paused(call: &<T as Trait>::Call) -> bool {
+		let (pallet, name) = call.call_name();
+		if T::Exempt::get().contains(&pallet) {
+			return false;
+		}
+
+		let pallet = pallet.as_bytes().to_vec();
+		Self::paused_pallet(&pallet) || Self::paused_call(&(pallet, name.as_bytes().to_vec()))
+	}
+
+	fn ensure_pausable(pallet: &[u8]) -> DispatchResult {
+		ensure!(
+			!T::Exempt::get().iter().any(|exempt| exempt.as_bytes() == pallet),
+			"the pallet can not be paused",
+		);
+		Ok(())
+	}
+}
+
+impl<T: Trait> CallFilter<<T as Trait>::Call> for Module<T> {
+	fn allows(call: &<T as Trait>::Call) -> bool {
+		!Self::is_paused(call)
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// All calls of a module were paused.
+		PalletPaused(Vec<u8>),
+		/// A call of a module was paused.
+		CallPaused(Vec<u8>, Vec<u8>),
+		/// A module was unpaused.
+		PalletUnpaused(Vec<u8>),
+		/// A call of a module was unpaused.
+		CallUnpaused(Vec<u8>, Vec<u8>),
+	}
+);
+
+/// Rejects transactions whose call is paused, both in the pool and in blocks.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckPaused<T: Trait + Send + Sync>(PhantomData<T>);
+
+impl<T: Trait + Send + Sync> CheckPaused<T> {
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Trait + Send + Sync> sp_std::fmt::Debug for CheckPaused<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckPaused")
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + Send + Sync> SignedExtension for CheckPaused<T> {
+	type AccountId = T::AccountId;
+	type Call = <T as Trait>::Call;
+	type AdditionalSigned = ();
+	type DispatchInfo = DispatchInfo;
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: Self::DispatchInfo,
+		_len: usize,
+	) -> TransactionValidity {
+		if <Module<T>>::is_paused(call) {
+			Err(InvalidTransaction::Call.into())
+		} else {
+			Ok(ValidTransaction::default())
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: Self::DispatchInfo,
+		len: usize,
+	) -> Result<(), TransactionValidityError> {
+		self.validate(who, call, info, len).map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{CallName, CheckPaused, Module, Trait, Event};
+	use crate::{pause, linked_map, single_value};
+
+	use sp_core::H256;
+	use frame_support::{
+		impl_outer_origin, impl_outer_event, impl_outer_dispatch, assert_ok, assert_err,
+		parameter_types, weights::{DispatchInfo, Weight},
+	};
+	use sp_runtime::{
+		traits::{BlakeTwo256, IdentityLookup, SignedExtension}, testing::Header, Perbill,
+		transaction_validity::InvalidTransaction,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for TestRuntime where origin: Origin {
+			system::System,
+			single_value::SingleValue,
+			linked_map::LinkedMap,
+			pause::Pause,
+		}
+	}
+
+	impl CallName for Call {
+		fn call_name(&self) -> (&'static str, &'static str) {
+			match self {
+				Call::System(..) => ("System", "any"),
+				Call::SingleValue(single_value::Call::set_value(..)) => ("SingleValue", "set_value"),
+				Call::SingleValue(..) => ("SingleValue", "other"),
+				Call::LinkedMap(..) => ("LinkedMap", "any"),
+				Call::Pause(..) => ("Pause", "any"),
+			}
+		}
+
+		fn exists(pallet: &[u8], call: Option<&[u8]>) -> bool {
+			match (pallet, call) {
+				(b"SingleValue", Some(call)) => call == b"set_value" || call == b"other",
+				(b"SingleValue", None) | (b"System", _) | (b"LinkedMap", _) | (b"Pause", _) => true,
+				_ => false,
+			}
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = Call;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	impl single_value::Trait for TestRuntime {
+		type Event = ();
+	}
+
+	impl linked_map::Trait for TestRuntime {
+		type Event = ();
+	}
+
+	mod pause_event {
+		pub use crate::pause::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for TestRuntime {
+			pause_event,
+		}
+	}
+
+	parameter_types! {
+		pub const Exempt: &'static [&'static str] = &["System", "Pause"];
+	}
+	impl Trait for TestRuntime {
+		type Event = TestEvent;
+		type Call = Call;
+		type Exempt = Exempt;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type SingleValue = single_value::Module<TestRuntime>;
+	type LinkedMap = linked_map::Module<TestRuntime>;
+	type Pause = Module<TestRuntime>;
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			let mut ext = sp_io::TestExternalities::from(storage);
+			ext.execute_with(|| System::set_block_number(1));
+			ext
+		}
+	}
+
+	fn set_value() -> Call {
+		Call::SingleValue(single_value::Call::set_value(7))
+	}
+
+	fn get_value() -> Call {
+		Call::SingleValue(single_value::Call::get_value())
+	}
+
+	fn remark() -> Call {
+		Call::System(system::Call::remark(vec![]))
+	}
+
+	/// Whether `CheckPaused` lets a transaction calling `call` into the pool and into blocks.
+	fn passes(call: Call) -> bool {
+		let validate = CheckPaused::<TestRuntime>::new().validate(&1, &call, DispatchInfo::default(), 0);
+		let pre_dispatch = CheckPaused::<TestRuntime>::new().pre_dispatch(&1, &call, DispatchInfo::default(), 0);
+		assert_eq!(validate.is_ok(), pre_dispatch.is_ok());
+		if let Err(e) = pre_dispatch {
+			assert_eq!(e, InvalidTransaction::Call.into());
+		}
+		validate.is_ok()
+	}
+
+	fn deposited(event: Event) -> bool {
+		let expected_event = TestEvent::pause_event(event);
+		System::events().iter().any(|a| a.event == expected_event)
+	}
+
+	#[test]
+	fn paused_pallets_reject_all_their_calls() {
+		ExtBuilder::build().execute_with(|| {
+			assert!(passes(set_value()));
+			assert_ok!(Pause::pause_pallet(Origin::ROOT, b"SingleValue".to_vec()));
+			assert!(deposited(Event::PalletPaused(b"SingleValue".to_vec())));
+
+			assert!(!passes(set_value()));
+			assert!(!passes(get_value()));
+			assert!(passes(Call::LinkedMap(linked_map::Call::remove_member_linked(0))));
+
+			assert_ok!(Pause::unpause(Origin::ROOT, b"SingleValue".to_vec(), None));
+			assert!(deposited(Event::PalletUnpaused(b"SingleValue".to_vec())));
+			assert!(passes(set_value()));
+		})
+	}
+
+	#[test]
+	fn paused_calls_reject_only_themselves() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Pause::pause_call(Origin::ROOT, b"SingleValue".to_vec(), b"set_value".to_vec()));
+			assert!(deposited(Event::CallPaused(b"SingleValue".to_vec(), b"set_value".to_vec())));
+			assert!(!passes(set_value()));
+			assert!(passes(get_value()));
+
+			assert_err!(Pause::unpause(Origin::ROOT, b"SingleValue".to_vec(), None), "the pallet must be paused");
+			assert_ok!(Pause::unpause(Origin::ROOT, b"SingleValue".to_vec(), Some(b"set_value".to_vec())));
+			assert!(passes(set_value()));
+			assert_err!(
+				Pause::unpause(Origin::ROOT, b"SingleValue".to_vec(), Some(b"set_value".to_vec())),
+				"the call must be paused",
+			);
+		})
+	}
+
+	#[test]
+	fn exempt_pallets_can_not_be_paused() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(Pause::pause_pallet(Origin::ROOT, b"System".to_vec()), "the pallet can not be paused");
+			assert_err!(
+				Pause::pause_call(Origin::ROOT, b"Pause".to_vec(), b"unpause".to_vec()),
+				"the pallet can not be paused",
+			);
+			assert!(passes(remark()));
+		})
+	}
+
+	#[test]
+	fn unknown_pallets_and_calls_are_not_paused() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(Pause::pause_pallet(Origin::ROOT, b"SingleValues".to_vec()), "the pallet does not exist");
+			assert_err!(
+				Pause::pause_call(Origin::ROOT, b"SingleValue".to_vec(), b"set_values".to_vec()),
+				"the call does not exist",
+			);
+			assert_err!(
+				Pause::pause_call(Origin::ROOT, b"SingleValues".to_vec(), b"set_value".to_vec()),
+				"the call does not exist",
+			);
+			assert!(!Pause::paused_pallet(&b"SingleValues".to_vec()));
+			assert!(passes(set_value()));
+		})
+	}
+
+	#[test]
+	fn only_root_pauses() {
+		ExtBuilder::build().execute_with(|| {
+			assert!(Pause::pause_pallet(Origin::signed(1), b"SingleValue".to_vec()).is_err());
+			assert!(Pause::pause_call(Origin::signed(1), b"SingleValue".to_vec(), b"set_value".to_vec()).is_err());
+			assert_ok!(Pause::pause_pallet(Origin::ROOT, b"SingleValue".to_vec()));
+			assert!(Pause::unpause(Origin::signed(1), b"SingleValue".to_vec(), None).is_err());
+			assert!(Pause::paused_pallet(&b"SingleValue".to_vec()));
+		})
+	}
+}
@@ -0,0 +1,402 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Accounts dispatching calls on behalf of others.
+//!
+//! An account adds proxies, each with a proxy type that filters the calls it may dispatch for the
+//! account, e.g. none of those moving balances. Keeping proxies reserves a deposit that grows with
+//! their number.
+
+use frame_support::{
+	decl_module, decl_storage, decl_event, dispatch::DispatchResult, ensure,
+	traits::{Currency, Get, ReservableCurrency}, weights::{DispatchClass, FunctionOf, GetDispatchInfo},
+	Parameter,
+};
+use sp_runtime::traits::{Dispatchable, Member, Saturating, Zero};
+use sp_std::prelude::*;
+use system::ensure_signed;
+use crate::pause::{self, CallFilter};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Decides which calls a proxy of some type may dispatch.
+pub trait ProxyFilter<Call> {
+	/// Whether a proxy of this type may dispatch `call`.
+	fn allows(&self, call: &Call) -> bool;
+}
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// A call that can be dispatched through a proxy.
+	type Call: Parameter + Dispatchable<Origin = Self::Origin> + GetDispatchInfo;
+	/// Calls that may be dispatched, i.e. are not paused.
+	type CallFilter: pause::CallFilter<<Self as Trait>::Call>;
+	/// The currency proxy deposits are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+	/// The kinds of proxies, each allowing some calls.
+	type ProxyType: Parameter + Member + Ord + Copy + ProxyFilter<<Self as Trait>::Call>;
+	/// Deposit for having proxies...
+	type ProxyDepositBase: Get<BalanceOf<Self>>;
+	/// ...plus this much for each of them.
+	type ProxyDepositFactor: Get<BalanceOf<Self>>;
+	/// Proxies an account may have.
+	type MaxProxies: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Proxy {
+		/// The proxies of each account with their types, and the deposit reserved for them.
+		pub Proxies get(fn proxies): map T::AccountId => (Vec<(T::AccountId, T::ProxyType)>, BalanceOf<T>);
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		const ProxyDepositBase: BalanceOf<T> = T::ProxyDepositBase::get();
+		const ProxyDepositFactor: BalanceOf<T> = T::ProxyDepositFactor::get();
+		const MaxProxies: u32 = T::MaxProxies::get();
+
+		/// Dispatch `call` for `real`, which must have made the caller a proxy of a type that
+		/// allows the call. Weighs as much as `call` on top of the proxy check.
+		#[weight = FunctionOf(
+			|args: (&T::AccountId, &Box<<T as Trait>::Call>)| args.1.get_dispatch_info().weight.saturating_add(10_000),
+			|_: (&T::AccountId, &Box<<T as Trait>::Call>)| DispatchClass::Normal,
+			true,
+		)]
+		pub fn proxy(origin, real: T::AccountId, call: Box<<T as Trait>::Call>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (proxies, _) = Self::proxies(&real);
+			ensure!(proxies.iter().any(|(proxy, _)| proxy == &who), "must be a proxy of the account");
+			ensure!(
+				proxies.iter().any(|(proxy, proxy_type)| proxy == &who && proxy_type.allows(&call)),
+				"the call is not allowed for the proxy",
+			);
+			ensure!(T::CallFilter::allows(&call), "the call is paused");
+
+			let ok = call.dispatch(system::RawOrigin::Signed(real.clone()).into()).is_ok();
+
+			Self::deposit_event(RawEvent::ProxyExecuted(who, real, ok));
+			Ok(())
+		}
+
+		/// Let `proxy` dispatch the calls `proxy_type` allows for the caller.
+		pub fn add_proxy(origin, proxy: T::AccountId, proxy_type: T::ProxyType) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (mut proxies, deposit) = Self::proxies(&who);
+			ensure!(
+				!proxies.iter().any(|p| p == &(proxy.clone(), proxy_type)),
+				"must not be a proxy of this type already",
+			);
+			ensure!((proxies.len() as u32) < T::MaxProxies::get(), "must have fewer than the maximum of proxies");
+
+			proxies.push((proxy.clone(), proxy_type));
+			proxies.sort();
+			let new_deposit = Self::deposit(proxies.len() as u32);
+			T::Currency::reserve(&who, new_deposit.saturating_sub(deposit))?;
+			<Proxies<T>>::insert(&who, (proxies, new_deposit));
+
+			Self::deposit_event(RawEvent::ProxyAdded(who, proxy, proxy_type));
+			Ok(())
+		}
+
+		/// Remove the proxy `proxy` of type `proxy_type` of the caller.
+		pub fn remove_proxy(origin, proxy: T::AccountId, proxy_type: T::ProxyType) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (mut proxies, deposit) = Self::proxies(&who);
+			let index = proxies.iter()
+				.position(|p| p == &(proxy.clone(), proxy_type))
+				.ok_or("must be a proxy of this type to be removed")?;
+
+			proxies.remove(index);
+			let new_deposit = Self::deposit(proxies.len() as u32);
+			T::Currency::unreserve(&who, deposit.saturating_sub(new_deposit));
+			if proxies.is_empty() {
+				<Proxies<T>>::remove(&who);
+			} else {
+				<Proxies<T>>::insert(&who, (proxies, new_deposit));
+			}
+
+			Self::deposit_event(RawEvent::ProxyRemoved(who, proxy, proxy_type));
+			Ok(())
+		}
+
+		/// Remove all proxies of the caller, returning the deposit.
+		pub fn remove_proxies(origin) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (_, deposit) = <Proxies<T>>::take(&who);
+			T::Currency::unreserve(&who, deposit);
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The deposit for having `count` proxies.
+	pub fn deposit(count: u32) -> BalanceOf<T> {
+		if count == 0 {
+			Zero::zero()
+		} else {
+			T::ProxyDepositBase::get().saturating_add(T::ProxyDepositFactor::get().saturating_mul(count.into()))
+		}
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as system::Trait>::AccountId,
+		ProxyType = <T as Trait>::ProxyType,
+	{
+		/// An account added a proxy of a type.
+		ProxyAdded(AccountId, AccountId, ProxyType),
+		/// An account removed a proxy of a type.
+		ProxyRemoved(AccountId, AccountId, ProxyType),
+		/// A proxy dispatched a call for an account; whether it succeeded.
+		ProxyExecuted(AccountId, AccountId, bool),
+	}
+);
+
+#[cfg(test)]
+mod tests {
+	use super::{Module, Trait, RawEvent, ProxyFilter};
+	use crate::{proxy, single_value};
+
+	use codec::{Encode, Decode};
+	use sp_core::H256;
+	use frame_support::{
+		impl_outer_origin, impl_outer_event, impl_outer_dispatch, assert_ok, assert_err,
+		parameter_types, weights::Weight, StorageValue,
+	};
+	use sp_runtime::{
+		traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill, RuntimeDebug,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for TestRuntime where origin: Origin {
+			balances::Balances,
+			single_value::SingleValue,
+			proxy::Proxy,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = Call;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for TestRuntime {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+
+	impl single_value::Trait for TestRuntime {
+		type Event = ();
+	}
+
+	mod proxy_event {
+		pub use crate::proxy::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for TestRuntime {
+			proxy_event<T>,
+		}
+	}
+
+	#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+	pub enum ProxyType {
+		Any,
+		NonTransfer,
+	}
+
+	impl ProxyFilter<Call> for ProxyType {
+		fn allows(&self, call: &Call) -> bool {
+			match self {
+				ProxyType::Any => true,
+				ProxyType::NonTransfer => match call {
+					Call::Balances(..) | Call::Proxy(..) => false,
+					_ => true,
+				},
+			}
+		}
+	}
+
+	parameter_types! {
+		pub const ProxyDepositBase: u64 = 10;
+		pub const ProxyDepositFactor: u64 = 2;
+		pub const MaxProxies: u32 = 2;
+	}
+	impl Trait for TestRuntime {
+		type Event = TestEvent;
+		type Call = Call;
+		type CallFilter = ();
+		type Currency = Balances;
+		type ProxyType = ProxyType;
+		type ProxyDepositBase = ProxyDepositBase;
+		type ProxyDepositFactor = ProxyDepositFactor;
+		type MaxProxies = MaxProxies;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type Balances = balances::Module<TestRuntime>;
+	type SingleValue = single_value::Module<TestRuntime>;
+	type Proxy = Module<TestRuntime>;
+
+	const REAL: u64 = 1;
+	const HOT: u64 = 2;
+	const OTHER: u64 = 3;
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let mut storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			balances::GenesisConfig::<TestRuntime> {
+				balances: vec![(REAL, 100), (HOT, 100), (OTHER, 100)],
+				vesting: vec![],
+			}.assimilate_storage(&mut storage).unwrap();
+
+			let mut ext = sp_io::TestExternalities::from(storage);
+			ext.execute_with(|| System::set_block_number(1));
+			ext
+		}
+	}
+
+	fn set_value(value: u32) -> Box<Call> {
+		Box::new(Call::SingleValue(single_value::Call::set_value(value)))
+	}
+
+	fn transfer(value: u64) -> Box<Call> {
+		Box::new(Call::Balances(balances::Call::transfer(OTHER, value)))
+	}
+
+	fn deposited(event: RawEvent<u64, ProxyType>) -> bool {
+		let expected_event = TestEvent::proxy_event(event);
+		System::events().iter().any(|a| a.event == expected_event)
+	}
+
+	#[test]
+	fn proxies_dispatch_for_the_real_account() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(REAL), HOT, ProxyType::Any));
+			assert!(deposited(RawEvent::ProxyAdded(REAL, HOT, ProxyType::Any)));
+
+			assert_ok!(Proxy::proxy(Origin::signed(HOT), REAL, transfer(10)));
+			assert!(deposited(RawEvent::ProxyExecuted(HOT, REAL, true)));
+			assert_eq!(Balances::free_balance(&OTHER), 110);
+		})
+	}
+
+	#[test]
+	fn non_proxies_can_not_dispatch() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(REAL), HOT, ProxyType::Any));
+			assert_err!(Proxy::proxy(Origin::signed(OTHER), REAL, set_value(7)), "must be a proxy of the account");
+			assert_err!(Proxy::proxy(Origin::signed(REAL), HOT, set_value(7)), "must be a proxy of the account");
+		})
+	}
+
+	#[test]
+	fn proxy_types_filter_calls() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(REAL), HOT, ProxyType::NonTransfer));
+
+			assert_ok!(Proxy::proxy(Origin::signed(HOT), REAL, set_value(7)));
+			assert_eq!(single_value::MyValue::get(), 7);
+			assert_err!(Proxy::proxy(Origin::signed(HOT), REAL, transfer(10)), "the call is not allowed for the proxy");
+			assert_eq!(Balances::free_balance(&OTHER), 100);
+
+			// a proxy can not nest calls to get around its filter
+			let nested = Box::new(Call::Proxy(super::Call::proxy(REAL, transfer(10))));
+			assert_err!(Proxy::proxy(Origin::signed(HOT), REAL, nested), "the call is not allowed for the proxy");
+		})
+	}
+
+	#[test]
+	fn proxies_reserve_a_deposit() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(REAL), HOT, ProxyType::NonTransfer));
+			assert_eq!(Balances::reserved_balance(&REAL), 12);
+			assert_ok!(Proxy::add_proxy(Origin::signed(REAL), OTHER, ProxyType::Any));
+			assert_eq!(Balances::reserved_balance(&REAL), 14);
+			assert_err!(
+				Proxy::add_proxy(Origin::signed(REAL), HOT, ProxyType::Any),
+				"must have fewer than the maximum of proxies",
+			);
+
+			assert_ok!(Proxy::remove_proxy(Origin::signed(REAL), OTHER, ProxyType::Any));
+			assert!(deposited(RawEvent::ProxyRemoved(REAL, OTHER, ProxyType::Any)));
+			assert_eq!(Balances::reserved_balance(&REAL), 12);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(REAL), HOT, ProxyType::NonTransfer));
+			assert_eq!(Balances::reserved_balance(&REAL), 0);
+			assert_eq!(Proxy::proxies(&REAL), (vec![], 0));
+		})
+	}
+
+	#[test]
+	fn proxies_are_added_once_per_type() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(REAL), HOT, ProxyType::NonTransfer));
+			assert_err!(
+				Proxy::add_proxy(Origin::signed(REAL), HOT, ProxyType::NonTransfer),
+				"must not be a proxy of this type already",
+			);
+			assert_err!(
+				Proxy::remove_proxy(Origin::signed(REAL), HOT, ProxyType::Any),
+				"must be a proxy of this type to be removed",
+			);
+		})
+	}
+
+	#[test]
+	fn remove_proxies_returns_the_deposit() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(REAL), HOT, ProxyType::NonTransfer));
+			assert_ok!(Proxy::add_proxy(Origin::signed(REAL), HOT, ProxyType::Any));
+			assert_ok!(Proxy::remove_proxies(Origin::signed(REAL)));
+
+			assert_eq!(Balances::reserved_balance(&REAL), 0);
+			assert_err!(Proxy::proxy(Origin::signed(HOT), REAL, set_value(7)), "must be a proxy of the account");
+		})
+	}
+}
@@ -0,0 +1,692 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Calls dispatched at a later block, once or periodically.
+//!
+//! A signed account schedules calls it dispatches itself, root schedules calls dispatched as root.
+//! Signed accounts reserve `TaskDeposit` for each task until its last run, may have at most
+//! `MaxScheduledPerOrigin` tasks, and only take `MaxSignedPerBlock` of a block's slots, leaving the
+//! rest to root. Scheduling weighs as much as all runs of the task.
+//!
+//! Each block's agenda runs in `on_initialize` in the order it was scheduled. Tasks beyond
+//! `MaximumWeight` move to the front of the next block's agenda; calls heavier than `MaximumWeight`
+//! can not be scheduled, so a postponed task is not postponed forever. Postponed tasks and the next
+//! runs of periodic ones go to the first block from there on with room for them. The weight an
+//! agenda used counts against the block, so extrinsics have that much less room.
+
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, dispatch::DispatchResult, ensure, storage::unhashed,
+	traits::{Currency, Get, ReservableCurrency},
+	weights::{DispatchClass, FunctionOf, GetDispatchInfo, Weight}, Parameter,
+};
+use sp_runtime::{RuntimeDebug, traits::{Dispatchable, One, Saturating, Zero}};
+use sp_std::prelude::*;
+use crate::pause::{self, CallFilter};
+
+/// Identifies a task from when it is scheduled until its last run.
+pub type TaskId = u64;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// A call that can be scheduled.
+	type Call: Parameter + Dispatchable<Origin = Self::Origin> + GetDispatchInfo;
+	/// Calls that may be dispatched, i.e. are not paused. Paused tasks fail.
+	type CallFilter: pause::CallFilter<<Self as Trait>::Call>;
+	/// Weight of the calls a block's agenda may dispatch, and of the heaviest call that may be
+	/// scheduled.
+	type MaximumWeight: Get<Weight>;
+	/// Tasks that may be scheduled for a single block.
+	type MaxScheduledPerBlock: Get<u32>;
+	/// Tasks of signed accounts that may be scheduled for a single block, fewer than
+	/// `MaxScheduledPerBlock` so root can always schedule.
+	type MaxSignedPerBlock: Get<u32>;
+	/// Tasks a signed account may have scheduled at once.
+	type MaxScheduledPerOrigin: Get<u32>;
+	/// The currency task deposits are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+	/// Deposit a signed account reserves for each of its tasks.
+	type TaskDeposit: Get<BalanceOf<Self>>;
+}
+
+/// The origin a task is dispatched with.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum TaskOrigin<AccountId> {
+	Root,
+	Signed(AccountId),
+}
+
+/// A call waiting for its block.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Task<Call, AccountId, BlockNumber, Balance> {
+	pub origin: TaskOrigin<AccountId>,
+	/// Reserved from a signed origin until the task's last run.
+	pub deposit: Balance,
+	pub call: Call,
+	/// The block whose agenda has the task.
+	pub when: BlockNumber,
+	/// Blocks between runs and how many runs are left after this one, for periodic tasks.
+	pub periodic: Option<(BlockNumber, u32)>,
+}
+
+type TaskOf<T> = Task<
+	<T as Trait>::Call,
+	<T as system::Trait>::AccountId,
+	<T as system::Trait>::BlockNumber,
+	BalanceOf<T>,
+>;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Scheduler {
+		/// The tasks due at each block, in the order they run.
+		pub Agenda get(fn agenda): map T::BlockNumber => Vec<TaskId>;
+		/// How many tasks of each block's agenda were scheduled by signed accounts.
+		pub SignedScheduled get(fn signed_scheduled): map T::BlockNumber => u32;
+		/// How many tasks each signed account has scheduled.
+		pub TaskCount get(fn task_count): map T::AccountId => u32;
+		/// Every task that has not run for the last time or been canceled.
+		pub Tasks get(fn task): map TaskId => Option<TaskOf<T>>;
+		/// The id of the next task scheduled.
+		NextTaskId get(fn next_task_id): TaskId;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		const MaximumWeight: Weight = T::MaximumWeight::get();
+		const MaxScheduledPerBlock: u32 = T::MaxScheduledPerBlock::get();
+		const MaxSignedPerBlock: u32 = T::MaxSignedPerBlock::get();
+		const MaxScheduledPerOrigin: u32 = T::MaxScheduledPerOrigin::get();
+		const TaskDeposit: BalanceOf<T> = T::TaskDeposit::get();
+
+		/// Dispatch `call` at block `when` with the caller's origin. A `periodic` task of
+		/// `(period, count)` then runs every `period` blocks, `count` times in all.
+		///
+		/// Weighs as much as `call` does for each run, as the runs are not charged when they
+		/// happen. A signed caller reserves `TaskDeposit` until the task's last run.
+		#[weight = FunctionOf(
+			|args: (&T::BlockNumber, &Option<(T::BlockNumber, u32)>, &Box<<T as Trait>::Call>)| {
+				let runs = args.1.as_ref().map_or(1, |(_, count)| *count);
+				args.2.get_dispatch_info().weight.saturating_mul(runs).saturating_add(10_000)
+			},
+			|_: (&T::BlockNumber, &Option<(T::BlockNumber, u32)>, &Box<<T as Trait>::Call>)|
+				DispatchClass::Normal,
+			true,
+		)]
+		pub fn schedule(
+			origin,
+			when: T::BlockNumber,
+			periodic: Option<(T::BlockNumber, u32)>,
+			call: Box<<T as Trait>::Call>
+		) -> DispatchResult {
+			let origin: Result<system::RawOrigin<T::AccountId>, T::Origin> = origin.into();
+			let origin = match origin {
+				Ok(system::RawOrigin::Root) => TaskOrigin::Root,
+				Ok(system::RawOrigin::Signed(who)) => TaskOrigin::Signed(who),
+				_ => return Err("must be signed or root".into()),
+			};
+			ensure!(when > <system::Module<T>>::block_number(), "must be scheduled for a future block");
+			if let Some((period, count)) = periodic {
+				ensure!(!period.is_zero() && count > 0, "periodic tasks need a period and a count");
+			}
+			let periodic = periodic.map(|(period, count)| (period, count - 1));
+			ensure!(
+				call.get_dispatch_info().weight <= T::MaximumWeight::get(),
+				"the call is too heavy to be scheduled",
+			);
+			ensure!(
+				(Self::agenda(when).len() as u32) < T::MaxScheduledPerBlock::get(),
+				"the agenda of the block is full",
+			);
+
+			let deposit = match &origin {
+				TaskOrigin::Root => Zero::zero(),
+				TaskOrigin::Signed(who) => {
+					ensure!(
+						Self::signed_scheduled(when) < T::MaxSignedPerBlock::get(),
+						"the agenda of the block has no room for signed tasks",
+					);
+					ensure!(
+						Self::task_count(who) < T::MaxScheduledPerOrigin::get(),
+						"too many tasks scheduled",
+					);
+					let deposit = T::TaskDeposit::get();
+					T::Currency::reserve(who, deposit)?;
+					<TaskCount<T>>::mutate(who, |count| *count = count.saturating_add(1));
+					deposit
+				},
+			};
+
+			let id = Self::next_task_id();
+			NextTaskId::put(id + 1);
+			Self::add_to_agenda(when, id, &origin, None);
+			<Tasks<T>>::insert(id, Task { origin, deposit, call: *call, when, periodic });
+
+			Self::deposit_event(RawEvent::Scheduled(id, when));
+			Ok(())
+		}
+
+		/// Cancel the task `id`, scheduled by the caller unless it is root.
+		pub fn cancel(origin, id: TaskId) -> DispatchResult {
+			let task = Self::task(id).ok_or("task does not exist")?;
+			let origin: Result<system::RawOrigin<T::AccountId>, T::Origin> = origin.into();
+			match origin {
+				Ok(system::RawOrigin::Root) => (),
+				Ok(system::RawOrigin::Signed(who)) if task.origin == TaskOrigin::Signed(who.clone()) => (),
+				_ => return Err("must have scheduled the task or be root".into()),
+			}
+
+			<Agenda<T>>::mutate(task.when, |agenda| agenda.retain(|&i| i != id));
+			if let TaskOrigin::Signed(_) = task.origin {
+				<SignedScheduled<T>>::mutate(task.when, |count| *count = count.saturating_sub(1));
+			}
+			Self::remove_task(id, task);
+
+			Self::deposit_event(RawEvent::Canceled(id));
+			Ok(())
+		}
+
+		fn on_initialize(n: T::BlockNumber) {
+			Self::run_agenda(n);
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	fn run_agenda(n: T::BlockNumber) {
+		let mut weight: Weight = 0;
+		let mut postponed = Vec::new();
+
+		<SignedScheduled<T>>::remove(n);
+		for id in <Agenda<T>>::take(n) {
+			let task = match Self::task(id) {
+				Some(task) => task,
+				None => continue,
+			};
+			let task_weight = task.call.get_dispatch_info().weight;
+			if weight.saturating_add(task_weight) > T::MaximumWeight::get() {
+				postponed.push(id);
+				continue;
+			}
+			weight = weight.saturating_add(task_weight);
+
+			let origin = match task.origin.clone() {
+				TaskOrigin::Root => system::RawOrigin::Root,
+				TaskOrigin::Signed(who) => system::RawOrigin::Signed(who),
+			};
+			if T::CallFilter::allows(&task.call) && task.call.clone().dispatch(origin.into()).is_ok() {
+				Self::deposit_event(RawEvent::Executed(id));
+			} else {
+				Self::deposit_event(RawEvent::Failed(id));
+			}
+
+			match task.periodic {
+				Some((period, count)) if count > 0 => {
+					let when = Self::block_with_room(n.saturating_add(period), &task.origin);
+					Self::add_to_agenda(when, id, &task.origin, None);
+					<Tasks<T>>::insert(id, Task { when, periodic: Some((period, count - 1)), ..task });
+				}
+				_ => Self::remove_task(id, task),
+			}
+		}
+
+		// the postponed tasks go to the front of the next agenda with room, in their order
+		let mut moved: Vec<(T::BlockNumber, usize)> = Vec::new();
+		for id in postponed {
+			<Tasks<T>>::mutate(id, |task| if let Some(task) = task {
+				let when = Self::block_with_room(n + One::one(), &task.origin);
+				let position = match moved.iter_mut().find(|(block, _)| *block == when) {
+					Some((_, count)) => {
+						*count += 1;
+						*count - 1
+					},
+					None => {
+						moved.push((when, 1));
+						0
+					},
+				};
+				task.when = when;
+				Self::add_to_agenda(when, id, &task.origin, Some(position));
+			});
+		}
+
+		// `AllExtrinsicsWeight` is private to `frame-system`, so its key is spelled out here
+		if weight > 0 {
+			let used = <system::Module<T>>::all_extrinsics_weight().saturating_add(weight);
+			unhashed::put(&sp_io::hashing::twox_128(b"System AllExtrinsicsWeight"), &used);
+		}
+	}
+
+	/// The first block from `from` on whose agenda has room for a task of `origin`.
+	fn block_with_room(mut from: T::BlockNumber, origin: &TaskOrigin<T::AccountId>) -> T::BlockNumber {
+		let has_room = |when: T::BlockNumber| {
+			(Self::agenda(when).len() as u32) < T::MaxScheduledPerBlock::get() && match origin {
+				TaskOrigin::Root => true,
+				TaskOrigin::Signed(_) => Self::signed_scheduled(when) < T::MaxSignedPerBlock::get(),
+			}
+		};
+		while !has_room(from) {
+			from = from.saturating_add(One::one());
+		}
+		from
+	}
+
+	/// Add the task `id` of `origin` to the agenda of `when`, at `position` or at its end.
+	fn add_to_agenda(
+		when: T::BlockNumber,
+		id: TaskId,
+		origin: &TaskOrigin<T::AccountId>,
+		position: Option<usize>,
+	) {
+		<Agenda<T>>::mutate(when, |agenda| match position {
+			Some(position) => agenda.insert(position, id),
+			None => agenda.push(id),
+		});
+		if let TaskOrigin::Signed(_) = origin {
+			<SignedScheduled<T>>::mutate(when, |count| *count = count.saturating_add(1));
+		}
+	}
+
+	/// Forget the task `id`, which is on no agenda, returning the deposit of a signed one.
+	fn remove_task(id: TaskId, task: TaskOf<T>) {
+		<Tasks<T>>::remove(id);
+		if let TaskOrigin::Signed(who) = task.origin {
+			T::Currency::unreserve(&who, task.deposit);
+			<TaskCount<T>>::mutate(&who, |count| *count = count.saturating_sub(1));
+		}
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		BlockNumber = <T as system::Trait>::BlockNumber,
+	{
+		/// A task was scheduled for a block.
+		Scheduled(TaskId, BlockNumber),
+		/// A task was canceled before its last run.
+		Canceled(TaskId),
+		/// A task's call was dispatched and succeeded.
+		Executed(TaskId),
+		/// A task's call was dispatched and failed.
+		Failed(TaskId),
+	}
+);
+
+#[cfg(test)]
+mod tests {
+	use super::{Module, Trait, RawEvent};
+	use crate::{scheduler, single_value};
+
+	use sp_core::H256;
+	use frame_support::{
+		impl_outer_origin, impl_outer_event, impl_outer_dispatch, assert_ok, assert_err,
+		parameter_types, weights::{GetDispatchInfo, Weight}, StorageValue,
+	};
+	use sp_runtime::{
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize}, testing::Header, Perbill,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for TestRuntime where origin: Origin {
+			system::System,
+			single_value::SingleValue,
+			scheduler::Scheduler,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1_000_000;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = Call;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for TestRuntime {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+
+	impl single_value::Trait for TestRuntime {
+		type Event = ();
+	}
+
+	mod scheduler_event {
+		pub use crate::scheduler::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for TestRuntime {
+			scheduler_event<T>,
+		}
+	}
+
+	parameter_types! {
+		// calls without a weight of their own weigh 10_000, so two fit
+		pub const MaximumSchedulerWeight: Weight = 25_000;
+		pub const MaxScheduledPerBlock: u32 = 5;
+		pub const MaxSignedPerBlock: u32 = 4;
+		pub const MaxScheduledPerOrigin: u32 = 4;
+		pub const TaskDeposit: u64 = 10;
+	}
+	impl Trait for TestRuntime {
+		type Event = TestEvent;
+		type Call = Call;
+		type CallFilter = ();
+		type MaximumWeight = MaximumSchedulerWeight;
+		type MaxScheduledPerBlock = MaxScheduledPerBlock;
+		type MaxSignedPerBlock = MaxSignedPerBlock;
+		type MaxScheduledPerOrigin = MaxScheduledPerOrigin;
+		type Currency = Balances;
+		type TaskDeposit = TaskDeposit;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type Balances = balances::Module<TestRuntime>;
+	type SingleValue = single_value::Module<TestRuntime>;
+	type Scheduler = Module<TestRuntime>;
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let mut storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			balances::GenesisConfig::<TestRuntime> {
+				balances: vec![(1, 100), (2, 100), (3, 100)],
+				vesting: vec![],
+			}.assimilate_storage(&mut storage).unwrap();
+
+			let mut ext = sp_io::TestExternalities::from(storage);
+			ext.execute_with(|| System::set_block_number(1));
+			ext
+		}
+	}
+
+	fn set_value(value: u32) -> Box<Call> {
+		Box::new(Call::SingleValue(single_value::Call::set_value(value)))
+	}
+
+	/// Start block `n`, running its agenda.
+	fn run_to(n: u64) {
+		while System::block_number() < n {
+			let block = System::block_number() + 1;
+			System::set_block_number(block);
+			Scheduler::on_initialize(block);
+		}
+	}
+
+	fn value() -> u32 {
+		single_value::MyValue::get()
+	}
+
+	fn deposited(event: RawEvent<u64>) -> bool {
+		let expected_event = TestEvent::scheduler_event(event);
+		System::events().iter().any(|a| a.event == expected_event)
+	}
+
+	#[test]
+	fn tasks_run_at_their_block() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 3, None, set_value(7)));
+			assert!(deposited(RawEvent::Scheduled(0, 3)));
+
+			run_to(2);
+			assert_eq!(value(), 0);
+			run_to(3);
+			assert_eq!(value(), 7);
+			assert!(deposited(RawEvent::Executed(0)));
+			assert_eq!(Scheduler::task(0), None);
+		})
+	}
+
+	#[test]
+	fn tasks_must_be_for_a_future_block() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(
+				Scheduler::schedule(Origin::signed(1), 1, None, set_value(7)),
+				"must be scheduled for a future block",
+			);
+			assert_err!(
+				Scheduler::schedule(Origin::signed(1), 2, Some((0, 3)), set_value(7)),
+				"periodic tasks need a period and a count",
+			);
+		})
+	}
+
+	#[test]
+	fn periodic_tasks_run_count_times() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 2, Some((3, 3)), set_value(7)));
+
+			run_to(11);
+			let runs = System::events().iter()
+				.filter(|a| a.event == TestEvent::scheduler_event(RawEvent::Executed(0)))
+				.count();
+			assert_eq!(runs, 3);
+			assert_eq!(Scheduler::task(0), None);
+			assert!(Scheduler::agenda(11).is_empty());
+		})
+	}
+
+	#[test]
+	fn tasks_dispatch_with_their_origin() {
+		ExtBuilder::build().execute_with(|| {
+			let root_only = Box::new(Call::System(system::Call::set_storage(vec![(b"key".to_vec(), b"value".to_vec())])));
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 2, None, root_only.clone()));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 3, None, root_only));
+
+			run_to(2);
+			assert!(deposited(RawEvent::Failed(0)));
+			assert_eq!(sp_io::storage::get(b"key"), None);
+			run_to(3);
+			assert!(deposited(RawEvent::Executed(1)));
+			assert_eq!(sp_io::storage::get(b"key"), Some(b"value".to_vec()));
+		})
+	}
+
+	#[test]
+	fn tasks_can_be_canceled_by_their_owner_or_root() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 2, None, set_value(7)));
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 2, Some((1, 5)), set_value(8)));
+
+			assert_err!(Scheduler::cancel(Origin::signed(2), 0), "must have scheduled the task or be root");
+			assert_ok!(Scheduler::cancel(Origin::signed(1), 0));
+			assert!(deposited(RawEvent::Canceled(0)));
+			assert_err!(Scheduler::cancel(Origin::signed(1), 0), "task does not exist");
+
+			// a periodic task stops once canceled
+			run_to(3);
+			assert_eq!(value(), 8);
+			assert_ok!(Scheduler::cancel(Origin::ROOT, 1));
+			assert!(Scheduler::agenda(4).is_empty());
+		})
+	}
+
+	#[test]
+	fn tasks_beyond_the_weight_budget_are_postponed() {
+		ExtBuilder::build().execute_with(|| {
+			for value in 1..=3 {
+				assert_ok!(Scheduler::schedule(Origin::signed(1), 2, None, set_value(value)));
+			}
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 3, None, set_value(4)));
+
+			run_to(2);
+			assert_eq!(value(), 2);
+			assert_eq!(Scheduler::agenda(3), vec![2, 3]);
+			assert_eq!(Scheduler::task(2).unwrap().when, 3);
+
+			// the postponed task runs first
+			run_to(3);
+			assert_eq!(value(), 4);
+			assert!(deposited(RawEvent::Executed(2)));
+		})
+	}
+
+	#[test]
+	fn calls_heavier_than_the_budget_are_not_scheduled() {
+		ExtBuilder::build().execute_with(|| {
+			// weighs 40_000, more than an agenda may use
+			let heavy = Box::new(Call::Scheduler(super::Call::schedule(5, Some((1, 3)), set_value(7))));
+			assert_err!(
+				Scheduler::schedule(Origin::signed(1), 2, None, heavy),
+				"the call is too heavy to be scheduled",
+			);
+			assert_eq!(Balances::reserved_balance(&1), 0);
+		})
+	}
+
+	#[test]
+	fn agendas_count_against_the_block_weight() {
+		ExtBuilder::build().execute_with(|| {
+			for value in 1..=3 {
+				assert_ok!(Scheduler::schedule(Origin::signed(1), 2, None, set_value(value)));
+			}
+
+			run_to(2);
+			assert_eq!(System::all_extrinsics_weight(), 20_000);
+		})
+	}
+
+	#[test]
+	fn periodic_tasks_skip_full_agendas() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 2, Some((1, 2)), set_value(7)));
+			for value in 0..4 {
+				assert_ok!(Scheduler::schedule(Origin::signed(2), 3, None, set_value(value)));
+			}
+
+			run_to(2);
+			assert_eq!(Scheduler::agenda(3).len(), 4);
+			assert_eq!(Scheduler::agenda(4), vec![0]);
+			assert_eq!(Scheduler::task(0).unwrap().when, 4);
+		})
+	}
+
+	#[test]
+	fn postponed_tasks_skip_full_agendas() {
+		ExtBuilder::build().execute_with(|| {
+			for &who in &[2, 2, 3, 3] {
+				assert_ok!(Scheduler::schedule(Origin::signed(who), 2, None, set_value(7)));
+			}
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 2, None, set_value(8)));
+			for _ in 0..4 {
+				assert_ok!(Scheduler::schedule(Origin::signed(1), 3, None, set_value(9)));
+			}
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 3, None, set_value(10)));
+
+			// two tasks fit the weight budget; the rest find block 3 full
+			run_to(2);
+			assert_eq!(Scheduler::agenda(3).len(), 5);
+			assert_eq!(Scheduler::agenda(4), vec![2, 3, 4]);
+			assert_eq!(Scheduler::task(2).unwrap().when, 4);
+		})
+	}
+
+	#[test]
+	fn agendas_are_bounded() {
+		ExtBuilder::build().execute_with(|| {
+			for value in 0..4 {
+				assert_ok!(Scheduler::schedule(Origin::signed(value as u64 % 2 + 1), 2, None, set_value(value)));
+			}
+			assert_err!(
+				Scheduler::schedule(Origin::signed(3), 2, None, set_value(4)),
+				"the agenda of the block has no room for signed tasks",
+			);
+
+			// the last slot is left to root
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 2, None, set_value(4)));
+			assert_err!(
+				Scheduler::schedule(Origin::ROOT, 2, None, set_value(5)),
+				"the agenda of the block is full",
+			);
+		})
+	}
+
+	#[test]
+	fn deposits_are_reserved_until_the_last_run() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 2, None, set_value(7)));
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 2, Some((1, 2)), set_value(8)));
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 5, None, set_value(9)));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 2, None, set_value(10)));
+			assert_eq!(Balances::reserved_balance(&1), 30);
+			assert_eq!(Scheduler::task_count(&1), 3);
+
+			run_to(2);
+			assert_eq!(Balances::reserved_balance(&1), 20);
+			run_to(3);
+			assert_eq!(Balances::reserved_balance(&1), 10);
+
+			assert_ok!(Scheduler::cancel(Origin::ROOT, 2));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+			assert_eq!(Scheduler::task_count(&1), 0);
+		})
+	}
+
+	#[test]
+	fn signed_accounts_have_a_bounded_number_of_tasks() {
+		ExtBuilder::build().execute_with(|| {
+			for when in 2..6 {
+				assert_ok!(Scheduler::schedule(Origin::signed(1), when, None, set_value(7)));
+			}
+			assert_err!(
+				Scheduler::schedule(Origin::signed(1), 6, None, set_value(7)),
+				"too many tasks scheduled",
+			);
+			assert_ok!(Scheduler::schedule(Origin::signed(2), 6, None, set_value(7)));
+
+			run_to(2);
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 6, None, set_value(7)));
+		})
+	}
+
+	#[test]
+	fn scheduling_weighs_every_run() {
+		let once = Call::Scheduler(super::Call::schedule(2, None, set_value(7)));
+		assert_eq!(once.get_dispatch_info().weight, 20_000);
+
+		let periodic = Call::Scheduler(super::Call::schedule(2, Some((1, 3)), set_value(7)));
+		assert_eq!(periodic.get_dispatch_info().weight, 40_000);
+	}
+}
@@ -1,16 +1,70 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::{decl_module, decl_storage, decl_event, dispatch::DispatchResult, ensure};
+//! One `u32` entry per account.
+//!
+//! An entry may be given a block it expires at, e.g. to use it as a lease or a lock. `Expiring`
+//! indexes entries by that block, and `on_initialize` removes the ones due; at most
+//! `MaxExpiriesPerBlock` expire at any block, which bounds that work. The owner reserves
+//! `ExpiryDeposit` while its entry has an expiry, so filling the expiries of a block costs.
+//!
+//! An account may also allow others to increase or compare-and-swap its entry, through the
+//! `*_for` calls.
+//!
+//! Every write stamps the entry with a new version, higher than any before, so a client that read
+//! an entry can update it with `compare_version_and_swap` only if nobody wrote it since, even if
+//! its value is the same again.
+
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, dispatch::DispatchResult, ensure,
+	traits::{Currency, Get, ReservableCurrency},
+};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
 use system::ensure_signed;
 
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
 pub trait Trait: system::Trait {
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Entries that may expire at the same block.
+	type MaxExpiriesPerBlock: Get<u32>;
+	/// The currency expiry deposits are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+	/// Deposit an owner reserves while its entry has an expiry.
+	type ExpiryDeposit: Get<BalanceOf<Self>>;
+}
+
+/// What a delegate may do to an account's entry.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Default, RuntimeDebug)]
+pub struct Permissions {
+	/// Call `increase_single_entry_for`.
+	pub increase: bool,
+	/// Call `compare_and_swap_single_entry_for`.
+	pub compare_and_swap: bool,
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait> as SimpleMapStorage {
-		SimpleMap get(fn simple_map): map T::AccountId => u32;
+		pub SimpleMap get(fn simple_map): map T::AccountId => u32;
+		/// Number of accounts that currently hold an entry in `SimpleMap`.
+		///
+		/// Counted since this item was added: `SimpleMap` can not be iterated to count the entries
+		/// of chains that already had some, so on those it is off, and may stay at zero as they go.
+		EntryCount get(fn entry_count): u32;
+		/// The block each expiring entry is removed at.
+		pub Expiry get(fn expiry): map T::AccountId => Option<T::BlockNumber>;
+		/// The accounts whose entries expire at each block.
+		pub Expiring get(fn expiring): map T::BlockNumber => Vec<T::AccountId>;
+		/// The deposit reserved for each expiring entry.
+		ExpiryDeposits get(fn expiry_deposit): map T::AccountId => BalanceOf<T>;
+		/// What each owner of an entry allowed each of its delegates.
+		pub Allowances get(fn allowance):
+			double_map hasher(blake2_256) T::AccountId, blake2_256(T::AccountId) => Permissions;
+		/// The version of each entry; zero for no entry.
+		pub Versions get(fn version): map T::AccountId => u64;
+		/// The version of the latest write of any entry.
+		LastVersion get(fn last_version): u64;
 	}
 }
 
@@ -18,27 +72,43 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
 
-		fn set_single_entry(origin, entry: u32) -> DispatchResult {
+		const MaxExpiriesPerBlock: u32 = T::MaxExpiriesPerBlock::get();
+
+		const ExpiryDeposit: BalanceOf<T> = T::ExpiryDeposit::get();
+
+		fn on_initialize(now: T::BlockNumber) {
+			for who in <Expiring<T>>::take(now) {
+				<Expiry<T>>::remove(&who);
+				let entry = Self::remove(&who);
+
+				Self::deposit_event(RawEvent::EntryExpired(who, entry));
+			}
+		}
+
+		/// Set the caller's entry, to be removed at block `expires_at` if given. The caller reserves
+		/// `ExpiryDeposit` until the entry no longer has an expiry.
+		fn set_single_entry(origin, entry: u32, expires_at: Option<T::BlockNumber>) -> DispatchResult {
 			let user = ensure_signed(origin)?;
-			<SimpleMap<T>>::insert(user.clone(), entry);
+			Self::set_expiry(&user, expires_at)?;
+			let version = Self::write(&user, entry);
 
-			Self::deposit_event(RawEvent::EntrySet(user, entry));
+			Self::deposit_event(RawEvent::EntrySet(user, entry, version));
 			Ok(())
 		}
 
 		fn get_single_entry(origin, account: T::AccountId) -> DispatchResult {
 			let getter = ensure_signed(origin)?;
 			ensure!(<SimpleMap<T>>::exists(account.clone()), "an entry does not exist for this user");
-			let entry = <SimpleMap<T>>::get(account);
+			let entry = <SimpleMap<T>>::get(&account);
 
-			Self::deposit_event(RawEvent::EntryGot(getter, entry));
+			Self::deposit_event(RawEvent::EntryGot(getter, entry, Self::version(account)));
 			Ok(())
 		}
 
 		fn take_single_entry(origin) -> DispatchResult {
 			let taker = ensure_signed(origin)?;
 			ensure!(<SimpleMap<T>>::exists(taker.clone()), "an entry does not exist for this user");
-			let entry = <SimpleMap<T>>::take(taker.clone());
+			let entry = Self::remove(&taker);
 
 			Self::deposit_event(RawEvent::EntryTook(taker, entry));
 
@@ -47,48 +117,217 @@ decl_module! {
 
 		fn increase_single_entry(origin, add_this_val: u32) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
+			Self::increase(sender, add_this_val)
+		}
 
-			let old_entry = <SimpleMap<T>>::get(&sender);
-			let new_entry = old_entry.checked_add(add_this_val).ok_or("value overflowed")?;
-			<SimpleMap<T>>::insert(sender, new_entry);
+		fn compare_and_swap_single_entry(origin, old_entry: u32, new_entry: u32) -> DispatchResult{
+			let user = ensure_signed(origin)?;
+			Self::compare_and_swap(user, old_entry, new_entry)
+		}
 
-			Self::deposit_event(RawEvent::IncreaseEntry(old_entry, new_entry));
+		/// Replace the caller's entry with `new_entry` if its version is `expected_version`, where
+		/// zero stands for no entry.
+		fn compare_version_and_swap(origin, expected_version: u64, new_entry: u32) -> DispatchResult {
+			let user = ensure_signed(origin)?;
+			// entries written before versions were kept have none, but are not missing either
+			let exists = <SimpleMap<T>>::exists(&user);
+			ensure!(
+				exists == (expected_version != 0) && Self::version(&user) == expected_version,
+				"the entry is at another version",
+			);
+			let old_entry = <SimpleMap<T>>::get(&user);
+			let version = Self::write(&user, new_entry);
+
+			Self::deposit_event(RawEvent::CAS(old_entry, new_entry, version));
 			Ok(())
 		}
 
-		fn compare_and_swap_single_entry(origin, old_entry: u32, new_entry: u32) -> DispatchResult{
+		/// Set what `delegate` may do to the caller's entry, replacing what it was allowed before.
+		fn approve(origin, delegate: T::AccountId, permissions: Permissions) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			ensure!(owner != delegate, "can not approve oneself");
+			ensure!(permissions != Permissions::default(), "must grant a permission");
+			<Allowances<T>>::insert(&owner, &delegate, permissions);
+
+			Self::deposit_event(RawEvent::Approved(owner, delegate, permissions));
+			Ok(())
+		}
+
+		/// Take back what `delegate` was allowed to do to the caller's entry.
+		fn revoke(origin, delegate: T::AccountId) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			ensure!(<Allowances<T>>::exists(&owner, &delegate), "the delegate was not approved");
+			<Allowances<T>>::remove(&owner, &delegate);
+
+			Self::deposit_event(RawEvent::Revoked(owner, delegate));
+			Ok(())
+		}
+
+		/// Increase `owner`'s entry, which must exist, as a delegate allowed to.
+		fn increase_single_entry_for(origin, owner: T::AccountId, add_this_val: u32) -> DispatchResult {
+			let delegate = ensure_signed(origin)?;
+			ensure!(Self::allowance(&owner, &delegate).increase, "must be allowed to increase the entry");
+			ensure!(<SimpleMap<T>>::exists(&owner), "an entry does not exist for this user");
+			Self::increase(owner, add_this_val)
+		}
+
+		/// Compare and swap `owner`'s entry, which must exist, as a delegate allowed to.
+		fn compare_and_swap_single_entry_for(
+			origin,
+			owner: T::AccountId,
+			old_entry: u32,
+			new_entry: u32
+		) -> DispatchResult {
+			let delegate = ensure_signed(origin)?;
+			ensure!(
+				Self::allowance(&owner, &delegate).compare_and_swap,
+				"must be allowed to compare and swap the entry",
+			);
+			ensure!(<SimpleMap<T>>::exists(&owner), "an entry does not exist for this user");
+			Self::compare_and_swap(owner, old_entry, new_entry)
+		}
+
+		/// Move the expiry of the caller's entry to block `expires_at`, or keep the entry for good if
+		/// `None`.
+		fn renew_entry(origin, expires_at: Option<T::BlockNumber>) -> DispatchResult {
 			let user = ensure_signed(origin)?;
-			ensure!(old_entry == <SimpleMap<T>>::get(user.clone()), "cas failed bc old_entry inputted by user != existing_entry");
-			<SimpleMap<T>>::insert(user, new_entry);
+			ensure!(<SimpleMap<T>>::exists(&user), "an entry does not exist for this user");
+			Self::set_expiry(&user, expires_at)?;
 
-			Self::deposit_event(RawEvent::CAS(old_entry, new_entry));
+			Self::deposit_event(RawEvent::EntryRenewed(user, expires_at));
 			Ok(())
 		}
 
 	}
 }
 
+impl<T: Trait> Module<T> {
+	fn increase(who: T::AccountId, add_this_val: u32) -> DispatchResult {
+		let old_entry = <SimpleMap<T>>::get(&who);
+		let new_entry = old_entry.checked_add(add_this_val).ok_or("value overflowed")?;
+		let version = Self::write(&who, new_entry);
+
+		Self::deposit_event(RawEvent::IncreaseEntry(old_entry, new_entry, version));
+		Ok(())
+	}
+
+	fn compare_and_swap(who: T::AccountId, old_entry: u32, new_entry: u32) -> DispatchResult {
+		ensure!(old_entry == <SimpleMap<T>>::get(&who), "cas failed bc old_entry inputted by user != existing_entry");
+		let version = Self::write(&who, new_entry);
+
+		Self::deposit_event(RawEvent::CAS(old_entry, new_entry, version));
+		Ok(())
+	}
+
+	/// `who`'s entry and its version, if it has one.
+	pub fn entry(who: &T::AccountId) -> Option<(u32, u64)> {
+		if <SimpleMap<T>>::exists(who) {
+			Some((<SimpleMap<T>>::get(who), Self::version(who)))
+		} else {
+			None
+		}
+	}
+
+	/// Set `who`'s entry to `entry`, returning its new version.
+	fn write(who: &T::AccountId, entry: u32) -> u64 {
+		if !<SimpleMap<T>>::exists(who) {
+			<EntryCount>::mutate(|c| *c = c.saturating_add(1));
+		}
+		<SimpleMap<T>>::insert(who, entry);
+
+		let version = <LastVersion>::get().saturating_add(1);
+		<LastVersion>::put(version);
+		<Versions<T>>::insert(who, version);
+		version
+	}
+
+	/// Remove `who`'s entry, with its version and expiry, returning it.
+	fn remove(who: &T::AccountId) -> u32 {
+		Self::remove_expiry(who);
+		<Versions<T>>::remove(who);
+		<EntryCount>::mutate(|c| *c = c.saturating_sub(1));
+		<SimpleMap<T>>::take(who)
+	}
+
+	/// Make `who`'s entry expire at block `expires_at`, or never if `None`.
+	fn set_expiry(who: &T::AccountId, expires_at: Option<T::BlockNumber>) -> DispatchResult {
+		if Self::expiry(who) == expires_at {
+			return Ok(());
+		}
+		let had_expiry = Self::expiry(who).is_some();
+		if let Some(at) = expires_at {
+			ensure!(at > <system::Module<T>>::block_number(), "must expire at a future block");
+			ensure!(
+				(Self::expiring(at).len() as u32) < T::MaxExpiriesPerBlock::get(),
+				"too many entries expire at this block",
+			);
+			if !had_expiry {
+				let deposit = T::ExpiryDeposit::get();
+				T::Currency::reserve(who, deposit)?;
+				<ExpiryDeposits<T>>::insert(who, deposit);
+			}
+		}
+
+		match expires_at {
+			Some(at) => {
+				if had_expiry {
+					Self::unindex_expiry(who);
+				}
+				<Expiring<T>>::mutate(at, |accounts| accounts.push(who.clone()));
+				<Expiry<T>>::insert(who, at);
+			}
+			None => Self::remove_expiry(who),
+		}
+		Ok(())
+	}
+
+	/// Drop `who`'s expiry, returning its deposit.
+	fn remove_expiry(who: &T::AccountId) {
+		Self::unindex_expiry(who);
+		T::Currency::unreserve(who, <ExpiryDeposits<T>>::take(who));
+	}
+
+	fn unindex_expiry(who: &T::AccountId) {
+		if let Some(at) = <Expiry<T>>::take(who) {
+			<Expiring<T>>::mutate(at, |accounts| accounts.retain(|account| account != who));
+		}
+	}
+}
+
 decl_event!(
 	pub enum Event<T> 
 	where 
 		AccountId = <T as system::Trait>::AccountId,
+		BlockNumber = <T as system::Trait>::BlockNumber,
 	{
-		EntrySet(AccountId, u32),
-		EntryGot(AccountId, u32),
+		// the last field of the events setting or getting an entry is its version
+		EntrySet(AccountId, u32, u64),
+		EntryGot(AccountId, u32, u64),
 		EntryTook(AccountId, u32),
-		IncreaseEntry(u32, u32),
-		CAS(u32, u32),
+		IncreaseEntry(u32, u32, u64),
+		CAS(u32, u32, u64),
+		/// An entry was removed at the block it expired at.
+		EntryExpired(AccountId, u32),
+		/// An entry now expires at another block, or never.
+		EntryRenewed(AccountId, Option<BlockNumber>),
+		/// An owner set what a delegate may do to its entry.
+		Approved(AccountId, AccountId, Permissions),
+		/// An owner took back what a delegate was allowed to do to its entry.
+		Revoked(AccountId, AccountId),
 	}
 );
 
 #[cfg(test)]
 mod tests {
-	use super::{Module, Trait, RawEvent};
+	use super::{Module, Trait, RawEvent, Permissions};
 
 	use sp_core::H256;
-	use frame_support::{impl_outer_origin, impl_outer_event, assert_ok, assert_err, parameter_types, weights::Weight};
+	use frame_support::{
+		impl_outer_origin, impl_outer_event, assert_ok, assert_err, parameter_types,
+		traits::ReservableCurrency, weights::Weight,
+	};
 	use sp_runtime::{
-		traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize}, testing::Header, Perbill,
 	};
 
 	impl_outer_origin! {
@@ -132,19 +371,48 @@ mod tests {
         }
     }
 
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for TestRuntime {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+
+	parameter_types! {
+		pub const MaxExpiriesPerBlock: u32 = 2;
+		pub const ExpiryDeposit: u64 = 10;
+	}
 	impl Trait for TestRuntime {
 		type Event = TestEvent;
+		type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+		type Currency = Balances;
+		type ExpiryDeposit = ExpiryDeposit;
 	}
 
 	type System = system::Module<TestRuntime>;
+	type Balances = balances::Module<TestRuntime>;
 	type SimpleMapModule = Module<TestRuntime>;
 
 	pub struct ExtBuilder;
 	impl ExtBuilder {
         pub fn build() -> sp_io::TestExternalities {
-            let storage = system::GenesisConfig::default()
+            let mut storage = system::GenesisConfig::default()
                 .build_storage::<TestRuntime>()
                 .unwrap();
+            balances::GenesisConfig::<TestRuntime> {
+                balances: vec![(1, 100), (2, 100), (3, 100)],
+                vesting: vec![],
+            }.assimilate_storage(&mut storage).unwrap();
             sp_io::TestExternalities::from(storage)
         }
     }
@@ -152,8 +420,8 @@ mod tests {
 	#[test]
 	fn set_entry_works() {
 		ExtBuilder::build().execute_with(||{
-			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 1));
-			let expected_event = TestEvent::simple_map_event(RawEvent::EntrySet(1, 1));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 1, None));
+			let expected_event = TestEvent::simple_map_event(RawEvent::EntrySet(1, 1, 1));
 			assert!(System::events().iter().any(|a| a.event == expected_event));
 		})
 	}
@@ -168,10 +436,10 @@ mod tests {
 	#[test]
 	fn get_entry_works() {
 		ExtBuilder::build().execute_with(||{
-			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
 			assert_ok!(SimpleMapModule::get_single_entry(Origin::signed(2), 1));
 
-			let expected_event = TestEvent::simple_map_event(RawEvent::EntryGot(2, 10));
+			let expected_event = TestEvent::simple_map_event(RawEvent::EntryGot(2, 10, 1));
 			assert!(System::events().iter().any(|a| a.event == expected_event));
 		})
 	}
@@ -186,7 +454,7 @@ mod tests {
 	#[test]
 	fn take_entry_works() {
 		ExtBuilder::build().execute_with(||{
-			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
 			assert_ok!(SimpleMapModule::take_single_entry(Origin::signed(1)));
 
 			let expected_event = TestEvent::simple_map_event(RawEvent::EntryTook(1, 10));
@@ -199,10 +467,10 @@ mod tests {
 	#[test]
 	fn increase_works() {
 		ExtBuilder::build().execute_with(||{
-			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
 			assert_ok!(SimpleMapModule::increase_single_entry(Origin::signed(1), 15));
 
-			let expected_event = TestEvent::simple_map_event(RawEvent::IncreaseEntry(10, 25));
+			let expected_event = TestEvent::simple_map_event(RawEvent::IncreaseEntry(10, 25, 2));
 			assert!(System::events().iter().any(|a| a.event == expected_event));
 		})
 	}
@@ -212,21 +480,299 @@ mod tests {
 		ExtBuilder::build().execute_with(||{
 			assert_ok!(SimpleMapModule::increase_single_entry(Origin::signed(1), 5));
 
-			let expected_event = TestEvent::simple_map_event(RawEvent::IncreaseEntry(0, 5));
+			let expected_event = TestEvent::simple_map_event(RawEvent::IncreaseEntry(0, 5, 1));
 			assert!(System::events().iter().any(|a| a.event == expected_event));
 		})
 	}
 
+	#[test]
+	fn entry_count_works() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 11, None));
+			assert_ok!(SimpleMapModule::increase_single_entry(Origin::signed(2), 5));
+			assert_eq!(SimpleMapModule::entry_count(), 2);
+
+			assert_ok!(SimpleMapModule::take_single_entry(Origin::signed(1)));
+			assert_eq!(SimpleMapModule::entry_count(), 1);
+		})
+	}
+
 	#[test]
 	fn cas_works(){
 		ExtBuilder::build().execute_with(||{
-			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
 			assert_err!(SimpleMapModule::compare_and_swap_single_entry(Origin::signed(1), 5, 20), "cas failed bc old_entry inputted by user != existing_entry");
 
 			assert_ok!(SimpleMapModule::compare_and_swap_single_entry(Origin::signed(1), 10, 20));
-			let expected_event = TestEvent::simple_map_event(RawEvent::CAS(10, 20));
+			let expected_event = TestEvent::simple_map_event(RawEvent::CAS(10, 20, 2));
+			assert!(System::events().iter().any(|a| a.event == expected_event));
+		})
+	}
+
+	/// Start block `n`, removing the entries due.
+	fn run_to(n: u64) {
+		while System::block_number() < n {
+			let block = System::block_number() + 1;
+			System::set_block_number(block);
+			SimpleMapModule::on_initialize(block);
+		}
+	}
+
+	#[test]
+	fn entries_expire() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, Some(3)));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(2), 20, None));
+			assert_ok!(SimpleMapModule::increase_single_entry(Origin::signed(1), 5));
+
+			run_to(2);
+			assert_eq!(SimpleMapModule::simple_map(1), 15);
+			run_to(3);
+			assert!(!<super::SimpleMap<TestRuntime>>::exists(1));
+			assert_eq!(SimpleMapModule::expiry(1), None);
+			assert_eq!(SimpleMapModule::entry_count(), 1);
+			let expected_event = TestEvent::simple_map_event(RawEvent::EntryExpired(1, 15));
+			assert!(System::events().iter().any(|a| a.event == expected_event));
+		})
+	}
+
+	#[test]
+	fn expiry_must_be_in_the_future() {
+		ExtBuilder::build().execute_with(||{
+			run_to(2);
+			assert_err!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, Some(2)), "must expire at a future block");
+			assert_eq!(SimpleMapModule::entry_count(), 0);
+		})
+	}
+
+	#[test]
+	fn expiries_per_block_are_bounded() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, Some(3)));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(2), 10, Some(3)));
+			assert_err!(SimpleMapModule::set_single_entry(Origin::signed(3), 10, Some(3)), "too many entries expire at this block");
+			// setting an entry again keeps its place
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(2), 11, Some(3)));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(3), 10, Some(4)));
+		})
+	}
+
+	#[test]
+	fn renewed_entries_expire_later() {
+		ExtBuilder::build().execute_with(||{
+			assert_err!(SimpleMapModule::renew_entry(Origin::signed(1), Some(5)), "an entry does not exist for this user");
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, Some(3)));
+			assert_ok!(SimpleMapModule::renew_entry(Origin::signed(1), Some(5)));
+			let expected_event = TestEvent::simple_map_event(RawEvent::EntryRenewed(1, Some(5)));
+			assert!(System::events().iter().any(|a| a.event == expected_event));
+			assert_eq!(SimpleMapModule::expiring(3), vec![]);
+
+			run_to(4);
+			assert_eq!(SimpleMapModule::simple_map(1), 10);
+			run_to(5);
+			assert_eq!(SimpleMapModule::entry_count(), 0);
+		})
+	}
+
+	#[test]
+	fn renewing_without_expiry_keeps_entries() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, Some(3)));
+			assert_ok!(SimpleMapModule::renew_entry(Origin::signed(1), None));
+
+			run_to(5);
+			assert_eq!(SimpleMapModule::simple_map(1), 10);
+			assert_eq!(SimpleMapModule::expiry(1), None);
+		})
+	}
+
+	#[test]
+	fn taken_entries_do_not_expire() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, Some(3)));
+			assert_ok!(SimpleMapModule::take_single_entry(Origin::signed(1)));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 20, None));
+
+			run_to(3);
+			assert_eq!(SimpleMapModule::simple_map(1), 20);
+			assert_eq!(SimpleMapModule::entry_count(), 1);
+		})
+	}
+
+	#[test]
+	fn expiries_reserve_a_deposit() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, Some(3)));
+			assert_eq!(Balances::reserved_balance(&1), 10);
+			// moving the expiry keeps the one deposit
+			assert_ok!(SimpleMapModule::renew_entry(Origin::signed(1), Some(4)));
+			assert_eq!(Balances::reserved_balance(&1), 10);
+			assert_ok!(SimpleMapModule::renew_entry(Origin::signed(1), None));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, Some(3)));
+			assert_ok!(SimpleMapModule::take_single_entry(Origin::signed(1)));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(2), 10, Some(3)));
+			run_to(3);
+			assert_eq!(Balances::reserved_balance(&2), 0);
+			assert_eq!(Balances::free_balance(&2), 100);
+		})
+	}
+
+	#[test]
+	fn expiries_need_the_deposit() {
+		ExtBuilder::build().execute_with(||{
+			// account 4 has no funds
+			let no_funds = Balances::reserve(&4, 10).unwrap_err();
+			assert_err!(SimpleMapModule::set_single_entry(Origin::signed(4), 10, Some(3)), no_funds);
+			assert_eq!(SimpleMapModule::expiring(3), vec![]);
+			assert_eq!(SimpleMapModule::entry_count(), 0);
+
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(4), 10, None));
+		})
+	}
+
+	const INCREASE: Permissions = Permissions { increase: true, compare_and_swap: false };
+	const SWAP: Permissions = Permissions { increase: false, compare_and_swap: true };
+
+	#[test]
+	fn delegates_increase_when_allowed() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
+			assert_err!(SimpleMapModule::increase_single_entry_for(Origin::signed(2), 1, 5), "must be allowed to increase the entry");
+
+			assert_ok!(SimpleMapModule::approve(Origin::signed(1), 2, INCREASE));
+			let expected_event = TestEvent::simple_map_event(RawEvent::Approved(1, 2, INCREASE));
+			assert!(System::events().iter().any(|a| a.event == expected_event));
+
+			assert_ok!(SimpleMapModule::increase_single_entry_for(Origin::signed(2), 1, 5));
+			assert_eq!(SimpleMapModule::simple_map(1), 15);
+			assert_eq!(SimpleMapModule::simple_map(2), 0);
+			let expected_event = TestEvent::simple_map_event(RawEvent::IncreaseEntry(10, 15, 2));
+			assert!(System::events().iter().any(|a| a.event == expected_event));
+
+			assert_err!(
+				SimpleMapModule::compare_and_swap_single_entry_for(Origin::signed(2), 1, 15, 20),
+				"must be allowed to compare and swap the entry",
+			);
+		})
+	}
+
+	#[test]
+	fn delegates_compare_and_swap_when_allowed() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
+			assert_ok!(SimpleMapModule::approve(Origin::signed(1), 2, SWAP));
+
+			assert_err!(
+				SimpleMapModule::compare_and_swap_single_entry_for(Origin::signed(2), 1, 5, 20),
+				"cas failed bc old_entry inputted by user != existing_entry",
+			);
+			assert_ok!(SimpleMapModule::compare_and_swap_single_entry_for(Origin::signed(2), 1, 10, 20));
+			assert_eq!(SimpleMapModule::simple_map(1), 20);
+			assert_err!(SimpleMapModule::increase_single_entry_for(Origin::signed(2), 1, 5), "must be allowed to increase the entry");
+		})
+	}
+
+	#[test]
+	fn delegates_do_not_create_entries() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::approve(Origin::signed(1), 2, INCREASE));
+			assert_err!(SimpleMapModule::increase_single_entry_for(Origin::signed(2), 1, 5), "an entry does not exist for this user");
+			assert_eq!(SimpleMapModule::entry_count(), 0);
+		})
+	}
+
+	#[test]
+	fn revoked_delegates_are_refused() {
+		ExtBuilder::build().execute_with(||{
+			assert_err!(SimpleMapModule::revoke(Origin::signed(1), 2), "the delegate was not approved");
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
+			assert_ok!(SimpleMapModule::approve(Origin::signed(1), 2, INCREASE));
+			assert_ok!(SimpleMapModule::revoke(Origin::signed(1), 2));
+			let expected_event = TestEvent::simple_map_event(RawEvent::Revoked(1, 2));
+			assert!(System::events().iter().any(|a| a.event == expected_event));
+
+			assert_err!(SimpleMapModule::increase_single_entry_for(Origin::signed(2), 1, 5), "must be allowed to increase the entry");
+		})
+	}
+
+	#[test]
+	fn approvals_need_a_permission_and_another_account() {
+		ExtBuilder::build().execute_with(||{
+			assert_err!(SimpleMapModule::approve(Origin::signed(1), 2, Permissions::default()), "must grant a permission");
+			assert_err!(SimpleMapModule::approve(Origin::signed(1), 1, INCREASE), "can not approve oneself");
+		})
+	}
+
+	#[test]
+	fn writes_bump_versions() {
+		ExtBuilder::build().execute_with(||{
+			assert_eq!(SimpleMapModule::entry(&1), None);
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(2), 10, None));
+			assert_eq!(SimpleMapModule::entry(&1), Some((10, 1)));
+			assert_eq!(SimpleMapModule::entry(&2), Some((10, 2)));
+
+			assert_ok!(SimpleMapModule::increase_single_entry(Origin::signed(1), 0));
+			assert_eq!(SimpleMapModule::entry(&1), Some((10, 3)));
+		})
+	}
+
+	#[test]
+	fn versions_are_not_reused() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
+			assert_ok!(SimpleMapModule::take_single_entry(Origin::signed(1)));
+			assert_eq!(SimpleMapModule::version(1), 0);
+
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, None));
+			assert_eq!(SimpleMapModule::entry(&1), Some((10, 2)));
+		})
+	}
+
+	#[test]
+	fn version_cas_works() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::compare_version_and_swap(Origin::signed(1), 0, 10));
+			assert_eq!(SimpleMapModule::entry(&1), Some((10, 1)));
+
+			// the value goes back to 10, but the version moves on
+			assert_ok!(SimpleMapModule::compare_and_swap_single_entry(Origin::signed(1), 10, 20));
+			assert_ok!(SimpleMapModule::compare_and_swap_single_entry(Origin::signed(1), 20, 10));
+			assert_err!(SimpleMapModule::compare_version_and_swap(Origin::signed(1), 1, 30), "the entry is at another version");
+
+			assert_ok!(SimpleMapModule::compare_version_and_swap(Origin::signed(1), 3, 30));
+			let expected_event = TestEvent::simple_map_event(RawEvent::CAS(10, 30, 4));
 			assert!(System::events().iter().any(|a| a.event == expected_event));
 		})
 	}
 
+	#[test]
+	fn version_cas_does_not_overwrite_unversioned_entries() {
+		ExtBuilder::build().execute_with(||{
+			// as written before versions were kept
+			<super::SimpleMap<TestRuntime>>::insert(1, 10);
+			assert_err!(SimpleMapModule::compare_version_and_swap(Origin::signed(1), 0, 20), "the entry is at another version");
+			assert_eq!(SimpleMapModule::simple_map(1), 10);
+
+			assert_ok!(SimpleMapModule::increase_single_entry(Origin::signed(1), 0));
+			assert_ok!(SimpleMapModule::compare_version_and_swap(Origin::signed(1), 1, 20));
+			assert_eq!(SimpleMapModule::entry(&1), Some((20, 2)));
+		})
+	}
+
+	#[test]
+	fn expired_entries_lose_their_version() {
+		ExtBuilder::build().execute_with(||{
+			assert_ok!(SimpleMapModule::set_single_entry(Origin::signed(1), 10, Some(2)));
+			run_to(2);
+			assert_eq!(SimpleMapModule::entry(&1), None);
+			assert_eq!(SimpleMapModule::version(1), 0);
+		})
+	}
+
 }
@@ -9,7 +9,7 @@ pub trait Trait: system::Trait {
 
 decl_storage! {
 	trait Store for Module<T: Trait> as SingleValueStorage {
-		MyValue : u32;
+		pub MyValue : u32;
 		MyAccount: T::AccountId;
 	}
 }
@@ -0,0 +1,563 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Batches of calls and calls approved by several signatories.
+//!
+//! `batch` and `batch_all` dispatch several calls with the caller's origin in one transaction.
+//! `as_multi` dispatches a call from the account of an M-of-N multisig once M of its signatories
+//! submitted it; the account is derived from the sorted signatories and the threshold, so anyone
+//! can compute it with `multi_account_id` before it holds funds.
+
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, dispatch::DispatchResult, ensure,
+	traits::{Currency, Get, ReservableCurrency}, weights::{DispatchClass, FunctionOf, GetDispatchInfo},
+	Parameter,
+};
+use sp_runtime::{RuntimeDebug, traits::{Dispatchable, Saturating}};
+use sp_std::prelude::*;
+use system::ensure_signed;
+use crate::pause::{self, CallFilter};
+
+/// Hash of a call a multisig approves.
+pub type CallHash = [u8; 32];
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// A call that can be batched or approved by a multisig.
+	type Call: Parameter + Dispatchable<Origin = Self::Origin> + GetDispatchInfo;
+	/// Calls that may be dispatched, i.e. are not paused.
+	type CallFilter: pause::CallFilter<<Self as Trait>::Call>;
+	/// The currency deposits for pending multisig calls are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+	/// Deposit for a pending multisig call...
+	type MultisigDepositBase: Get<BalanceOf<Self>>;
+	/// ...plus this much for each approval it needs.
+	type MultisigDepositFactor: Get<BalanceOf<Self>>;
+	/// Signatories a multisig may have.
+	type MaxSignatories: Get<u16>;
+}
+
+/// A multisig call waiting for approvals.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Multisig<AccountId, Balance> {
+	/// The signatory that submitted the call first and reserved the deposit.
+	pub depositor: AccountId,
+	pub deposit: Balance,
+	/// The signatories that submitted the call, in the order they did.
+	pub approvals: Vec<AccountId>,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Utility {
+		/// Pending calls by multisig account and call hash.
+		pub Multisigs get(fn multisig): map (T::AccountId, CallHash) => Option<Multisig<T::AccountId, BalanceOf<T>>>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		const MultisigDepositBase: BalanceOf<T> = T::MultisigDepositBase::get();
+		const MultisigDepositFactor: BalanceOf<T> = T::MultisigDepositFactor::get();
+		const MaxSignatories: u16 = T::MaxSignatories::get();
+
+		/// Dispatch `calls` in order with the caller's origin, stopping at the first that fails or
+		/// is paused. `BatchInterrupted` reports its index; the calls before it stay dispatched.
+		///
+		/// Weighs as much as all `calls` together, on top of the batch itself.
+		#[weight = FunctionOf(
+			|args: (&Vec<<T as Trait>::Call>,)| args.0.iter()
+				.map(|call| call.get_dispatch_info().weight)
+				.fold(10_000, |total, weight| total.saturating_add(weight)),
+			|_: (&Vec<<T as Trait>::Call>,)| DispatchClass::Normal,
+			true,
+		)]
+		pub fn batch(origin, calls: Vec<<T as Trait>::Call>) -> DispatchResult {
+			for (index, call) in calls.into_iter().enumerate() {
+				if !T::CallFilter::allows(&call) || call.dispatch(origin.clone()).is_err() {
+					Self::deposit_event(RawEvent::BatchInterrupted(index as u32));
+					return Ok(());
+				}
+			}
+
+			Self::deposit_event(RawEvent::BatchCompleted);
+			Ok(())
+		}
+
+		/// Dispatch `calls` in order with the caller's origin, failing with the first that fails.
+		///
+		/// All `calls` are checked against `CallFilter` before the first is dispatched, so a batch
+		/// with a paused call fails without changing anything. This Substrate version has no
+		/// storage transactions to roll back a call that fails once dispatched, though: the calls
+		/// before it stay dispatched, and so does whatever it wrote before returning its error. The
+		/// calls of the demo modules check everything before they write, so batches of them are
+		/// all-or-nothing.
+		///
+		/// Weighs as much as all `calls` together, on top of the batch itself.
+		#[weight = FunctionOf(
+			|args: (&Vec<<T as Trait>::Call>,)| args.0.iter()
+				.map(|call| call.get_dispatch_info().weight)
+				.fold(10_000, |total, weight| total.saturating_add(weight)),
+			|_: (&Vec<<T as Trait>::Call>,)| DispatchClass::Normal,
+			true,
+		)]
+		pub fn batch_all(origin, calls: Vec<<T as Trait>::Call>) -> DispatchResult {
+			ensure!(calls.iter().all(T::CallFilter::allows), "the call is paused");
+			for call in calls {
+				call.dispatch(origin.clone())?;
+			}
+
+			Self::deposit_event(RawEvent::BatchCompleted);
+			Ok(())
+		}
+
+		/// Approve `call` from the multisig of the caller and `other_signatories` that needs
+		/// `threshold` approvals, and dispatch it from the multisig account with the last one.
+		///
+		/// The first approval reserves a deposit from the caller, which is returned when the call
+		/// is dispatched or canceled. Every approval weighs as much as `call`, which the last one
+		/// dispatches.
+		#[weight = FunctionOf(
+			|args: (&u16, &Vec<T::AccountId>, &Box<<T as Trait>::Call>)|
+				args.2.get_dispatch_info().weight.saturating_add(10_000),
+			|_: (&u16, &Vec<T::AccountId>, &Box<<T as Trait>::Call>)| DispatchClass::Normal,
+			true,
+		)]
+		pub fn as_multi(
+			origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			call: Box<<T as Trait>::Call>
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(T::CallFilter::allows(&call), "the call is paused");
+			let signatories = Self::signatories(&who, other_signatories)?;
+			ensure!(
+				threshold >= 1 && threshold as usize <= signatories.len(),
+				"threshold must be between one and the number of signatories",
+			);
+			let id = Self::multi_account_id(&signatories, threshold);
+			let call_hash = call.using_encoded(sp_io::hashing::blake2_256);
+
+			let key = (id.clone(), call_hash);
+			let mut multisig = match Self::multisig(&key) {
+				Some(multisig) => {
+					ensure!(!multisig.approvals.contains(&who), "must not have approved the call already");
+					multisig
+				}
+				None => {
+					let deposit = T::MultisigDepositBase::get()
+						.saturating_add(T::MultisigDepositFactor::get().saturating_mul((threshold as u32).into()));
+					if threshold > 1 {
+						T::Currency::reserve(&who, deposit)?;
+						Self::deposit_event(RawEvent::NewMultisig(who.clone(), id.clone(), call_hash));
+					}
+					Multisig { depositor: who.clone(), deposit, approvals: vec![] }
+				}
+			};
+			multisig.approvals.push(who.clone());
+
+			if (multisig.approvals.len() as u16) < threshold {
+				<Multisigs<T>>::insert(&key, multisig);
+				Self::deposit_event(RawEvent::MultisigApproval(who, id, call_hash));
+				return Ok(());
+			}
+
+			<Multisigs<T>>::remove(&key);
+			if threshold > 1 {
+				T::Currency::unreserve(&multisig.depositor, multisig.deposit);
+			}
+			let ok = call.dispatch(system::RawOrigin::Signed(id.clone()).into()).is_ok();
+
+			Self::deposit_event(RawEvent::MultisigExecuted(who, id, call_hash, ok));
+			Ok(())
+		}
+
+		/// Cancel the pending multisig call with `call_hash`, returning the deposit. Only the
+		/// signatory that approved it first may.
+		pub fn cancel_as_multi(
+			origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			call_hash: CallHash
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let signatories = Self::signatories(&who, other_signatories)?;
+			let id = Self::multi_account_id(&signatories, threshold);
+
+			let key = (id.clone(), call_hash);
+			let multisig = Self::multisig(&key).ok_or("multisig call does not exist")?;
+			ensure!(multisig.depositor == who, "must have approved the call first");
+
+			<Multisigs<T>>::remove(&key);
+			T::Currency::unreserve(&who, multisig.deposit);
+
+			Self::deposit_event(RawEvent::MultisigCancelled(who, id, call_hash));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account of the multisig of the sorted `signatories` that needs `threshold` approvals.
+	pub fn multi_account_id(signatories: &[T::AccountId], threshold: u16) -> T::AccountId {
+		let entropy = (b"modlpy/utilisuba", signatories, threshold).using_encoded(sp_io::hashing::blake2_256);
+		T::AccountId::decode(&mut &entropy[..]).unwrap_or_default()
+	}
+
+	/// `who` and `other_signatories`, which must be sorted and not contain `who` or duplicates.
+	fn signatories(who: &T::AccountId, other_signatories: Vec<T::AccountId>) -> Result<Vec<T::AccountId>, &'static str> {
+		ensure!(!other_signatories.is_empty(), "must have other signatories");
+		ensure!(
+			other_signatories.len() < T::MaxSignatories::get() as usize,
+			"must have no more than the maximum of signatories",
+		);
+		ensure!(
+			other_signatories.windows(2).all(|w| w[0] < w[1]),
+			"other signatories must be sorted and unique",
+		);
+
+		let mut signatories = other_signatories;
+		match signatories.binary_search(who) {
+			Ok(_) => return Err("other signatories must not include the caller"),
+			Err(index) => signatories.insert(index, who.clone()),
+		}
+		Ok(signatories)
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as system::Trait>::AccountId,
+	{
+		/// All calls of a batch succeeded.
+		BatchCompleted,
+		/// The call at this index of a batch failed; the later ones were not dispatched.
+		BatchInterrupted(u32),
+		/// A signatory submitted a multisig call first.
+		NewMultisig(AccountId, AccountId, CallHash),
+		/// A signatory approved a multisig call that needs more approvals.
+		MultisigApproval(AccountId, AccountId, CallHash),
+		/// A multisig call was dispatched with the approval of this signatory; whether it succeeded.
+		MultisigExecuted(AccountId, AccountId, CallHash, bool),
+		/// A pending multisig call was canceled.
+		MultisigCancelled(AccountId, AccountId, CallHash),
+	}
+);
+
+#[cfg(test)]
+mod tests {
+	use super::{Module, Trait, RawEvent};
+	use crate::{pause, utility, single_value, vec_set};
+
+	use codec::Encode;
+	use sp_core::H256;
+	use frame_support::{
+		impl_outer_origin, impl_outer_event, impl_outer_dispatch, assert_ok, assert_err,
+		parameter_types, weights::{GetDispatchInfo, Weight}, StorageValue,
+	};
+	use sp_runtime::{
+		traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for TestRuntime where origin: Origin {
+			balances::Balances,
+			single_value::SingleValue,
+			vec_set::VecSet,
+			utility::Utility,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = Call;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for TestRuntime {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+
+	impl single_value::Trait for TestRuntime {
+		type Event = ();
+	}
+
+	impl vec_set::Trait for TestRuntime {
+		type Event = ();
+	}
+
+	mod utility_event {
+		pub use crate::utility::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for TestRuntime {
+			utility_event<T>,
+		}
+	}
+
+	parameter_types! {
+		pub const MultisigDepositBase: u64 = 10;
+		pub const MultisigDepositFactor: u64 = 1;
+		pub const MaxSignatories: u16 = 3;
+	}
+	/// Pauses the calls of `balances`.
+	pub struct NoTransfers;
+	impl pause::CallFilter<Call> for NoTransfers {
+		fn allows(call: &Call) -> bool {
+			match call {
+				Call::Balances(..) => false,
+				_ => true,
+			}
+		}
+	}
+
+	impl Trait for TestRuntime {
+		type Event = TestEvent;
+		type Call = Call;
+		type CallFilter = NoTransfers;
+		type Currency = Balances;
+		type MultisigDepositBase = MultisigDepositBase;
+		type MultisigDepositFactor = MultisigDepositFactor;
+		type MaxSignatories = MaxSignatories;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type Balances = balances::Module<TestRuntime>;
+	type SingleValue = single_value::Module<TestRuntime>;
+	type VecSet = vec_set::Module<TestRuntime>;
+	type Utility = Module<TestRuntime>;
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let mut storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			balances::GenesisConfig::<TestRuntime> {
+				balances: vec![(1, 100), (2, 100), (3, 100)],
+				vesting: vec![],
+			}.assimilate_storage(&mut storage).unwrap();
+
+			let mut ext = sp_io::TestExternalities::from(storage);
+			ext.execute_with(|| System::set_block_number(1));
+			ext
+		}
+	}
+
+	fn set_value(value: u32) -> Call {
+		Call::SingleValue(single_value::Call::set_value(value))
+	}
+
+	fn get_value() -> Call {
+		Call::SingleValue(single_value::Call::get_value())
+	}
+
+	fn add_member() -> Call {
+		Call::VecSet(vec_set::Call::add_member())
+	}
+
+	fn transfer() -> Call {
+		Call::Balances(balances::Call::transfer(2, 10))
+	}
+
+	fn deposited(event: RawEvent<u64>) -> bool {
+		let expected_event = TestEvent::utility_event(event);
+		System::events().iter().any(|a| a.event == expected_event)
+	}
+
+	#[test]
+	fn batch_dispatches_all_calls() {
+		ExtBuilder::build().execute_with(|| {
+			assert_ok!(Utility::batch(Origin::signed(1), vec![add_member(), set_value(7)]));
+
+			assert!(VecSet::is_member(&1));
+			assert_eq!(single_value::MyValue::get(), 7);
+			assert!(deposited(RawEvent::BatchCompleted));
+		})
+	}
+
+	#[test]
+	fn batch_stops_at_the_first_failure() {
+		ExtBuilder::build().execute_with(|| {
+			// `get_value` fails while no value is set
+			assert_ok!(Utility::batch(Origin::signed(1), vec![add_member(), get_value(), set_value(7)]));
+
+			assert!(VecSet::is_member(&1));
+			assert_eq!(single_value::MyValue::exists(), false);
+			assert!(deposited(RawEvent::BatchInterrupted(1)));
+			assert!(!deposited(RawEvent::BatchCompleted));
+		})
+	}
+
+	#[test]
+	fn batch_all_fails_with_the_failing_call() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(
+				Utility::batch_all(Origin::signed(1), vec![set_value(7), add_member(), add_member()]),
+				"must not be a member to be added",
+			);
+			assert!(!deposited(RawEvent::BatchCompleted));
+
+			assert_ok!(Utility::batch_all(Origin::signed(2), vec![add_member(), set_value(8)]));
+			assert!(deposited(RawEvent::BatchCompleted));
+		})
+	}
+
+	#[test]
+	fn batch_all_checks_every_call_before_dispatching() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(
+				Utility::batch_all(Origin::signed(1), vec![add_member(), set_value(7), transfer()]),
+				"the call is paused",
+			);
+			assert!(!VecSet::is_member(&1));
+			assert!(!single_value::MyValue::exists());
+			assert_eq!(Balances::free_balance(&2), 100);
+		})
+	}
+
+	#[test]
+	fn batches_weigh_their_calls() {
+		let batch = Call::Utility(super::Call::batch(vec![add_member(), set_value(7)]));
+		assert_eq!(batch.get_dispatch_info().weight, 30_000);
+
+		let multi = Call::Utility(super::Call::as_multi(1, vec![2], Box::new(batch)));
+		assert_eq!(multi.get_dispatch_info().weight, 40_000);
+
+		let batch_all = Call::Utility(super::Call::batch_all(vec![add_member(), set_value(7)]));
+		assert_eq!(batch_all.get_dispatch_info().weight, 30_000);
+	}
+
+	#[test]
+	fn multi_account_ids_are_deterministic() {
+		let id = Utility::multi_account_id(&[1, 2, 3], 2);
+		assert_eq!(id, Utility::multi_account_id(&[1, 2, 3], 2));
+		assert!(id != Utility::multi_account_id(&[1, 2, 3], 3));
+		assert!(id != Utility::multi_account_id(&[1, 2], 2));
+	}
+
+	#[test]
+	fn multisig_dispatches_at_the_threshold() {
+		ExtBuilder::build().execute_with(|| {
+			let multi = Utility::multi_account_id(&[1, 2, 3], 2);
+			let call = Box::new(add_member());
+			let hash = call.using_encoded(sp_io::hashing::blake2_256);
+
+			assert_ok!(Utility::as_multi(Origin::signed(1), 2, vec![2, 3], call.clone()));
+			assert!(deposited(RawEvent::NewMultisig(1, multi, hash)));
+			assert_eq!(Balances::reserved_balance(&1), 12);
+			assert!(!VecSet::is_member(&multi));
+			assert_err!(
+				Utility::as_multi(Origin::signed(1), 2, vec![2, 3], call.clone()),
+				"must not have approved the call already",
+			);
+
+			assert_ok!(Utility::as_multi(Origin::signed(3), 2, vec![1, 2], call));
+			assert!(VecSet::is_member(&multi));
+			assert!(deposited(RawEvent::MultisigExecuted(3, multi, hash, true)));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+			assert_eq!(Utility::multisig((multi, hash)), None);
+		})
+	}
+
+	#[test]
+	fn multisig_can_batch() {
+		ExtBuilder::build().execute_with(|| {
+			let multi = Utility::multi_account_id(&[1, 2], 1);
+			let call = Box::new(Call::Utility(super::Call::batch_all(vec![add_member(), set_value(7)])));
+
+			assert_ok!(Utility::as_multi(Origin::signed(2), 1, vec![1], call));
+			assert!(VecSet::is_member(&multi));
+			assert_eq!(single_value::MyValue::get(), 7);
+			assert_eq!(Balances::reserved_balance(&2), 0);
+		})
+	}
+
+	#[test]
+	fn multisig_signatories_are_checked() {
+		ExtBuilder::build().execute_with(|| {
+			let call = || Box::new(add_member());
+			assert_err!(Utility::as_multi(Origin::signed(1), 1, vec![], call()), "must have other signatories");
+			assert_err!(
+				Utility::as_multi(Origin::signed(1), 2, vec![3, 2], call()),
+				"other signatories must be sorted and unique",
+			);
+			assert_err!(
+				Utility::as_multi(Origin::signed(1), 2, vec![1, 2], call()),
+				"other signatories must not include the caller",
+			);
+			assert_err!(
+				Utility::as_multi(Origin::signed(1), 2, vec![2, 3, 4], call()),
+				"must have no more than the maximum of signatories",
+			);
+			assert_err!(
+				Utility::as_multi(Origin::signed(1), 3, vec![2], call()),
+				"threshold must be between one and the number of signatories",
+			);
+		})
+	}
+
+	#[test]
+	fn first_approver_can_cancel() {
+		ExtBuilder::build().execute_with(|| {
+			let multi = Utility::multi_account_id(&[1, 2, 3], 3);
+			let call = Box::new(add_member());
+			let hash = call.using_encoded(sp_io::hashing::blake2_256);
+			assert_ok!(Utility::as_multi(Origin::signed(1), 3, vec![2, 3], call.clone()));
+			assert_ok!(Utility::as_multi(Origin::signed(2), 3, vec![1, 3], call));
+
+			assert_err!(
+				Utility::cancel_as_multi(Origin::signed(2), 3, vec![1, 3], hash),
+				"must have approved the call first",
+			);
+			assert_ok!(Utility::cancel_as_multi(Origin::signed(1), 3, vec![2, 3], hash));
+			assert!(deposited(RawEvent::MultisigCancelled(1, multi, hash)));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+			assert_eq!(Utility::multisig((multi, hash)), None);
+		})
+	}
+}
@@ -0,0 +1,311 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! A validator set root can change, feeding `session`.
+//!
+//! Changes are handed to `session` when the current session ends and become the Aura and GRANDPA
+//! authorities when the session after it starts. Validators register their keys with
+//! `session::set_keys` before they can be added; without keys they could not author or vote.
+
+use codec::Encode;
+use frame_support::{decl_module, decl_storage, decl_event, dispatch::DispatchResult, ensure, storage::unhashed};
+use system::ensure_root;
+use sp_std::vec::Vec;
+
+pub trait Trait: session::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as ValidatorSet {
+		/// The validators, including changes `session` has not queued yet.
+		pub Validators get(fn validators) config(): Vec<T::ValidatorId>;
+		/// Whether `Validators` changed since it was last handed to `session`.
+		Changed: bool;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		pub fn add_validator(origin, validator: T::ValidatorId) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(!Self::is_validator(&validator), "must not be a validator to be added");
+			ensure!(Self::has_keys(&validator), "must have set session keys to be added");
+			<Validators<T>>::mutate(|v| v.push(validator.clone()));
+			Changed::put(true);
+
+			Self::deposit_event(RawEvent::ValidatorAdded(validator));
+			Ok(())
+		}
+
+		pub fn remove_validator(origin, validator: T::ValidatorId) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(Self::is_validator(&validator), "must be a validator to be removed");
+			ensure!(Self::validators().len() > 1, "the last validator can not be removed");
+			<Validators<T>>::mutate(|v| v.retain(|i| i != &validator));
+			Changed::put(true);
+
+			Self::deposit_event(RawEvent::ValidatorRemoved(validator));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	pub fn is_validator(who: &T::ValidatorId) -> bool {
+		Self::validators().contains(who)
+	}
+
+	/// Whether `who` set the keys `session` would hand to the authorities.
+	///
+	/// `NextKeys` is private to `pallet-session`, so its key is spelled out here.
+	fn has_keys(who: &T::ValidatorId) -> bool {
+		let mut key1 = b"Session NextKeys".to_vec();
+		b":session:keys".to_vec().encode_to(&mut key1);
+		let mut key = sp_io::hashing::twox_64(&key1).to_vec();
+		key.extend_from_slice(&key1);
+		key.extend_from_slice(&who.using_encoded(sp_io::hashing::blake2_256));
+		unhashed::exists(&key)
+	}
+}
+
+impl<T: Trait> session::OnSessionEnding<T::ValidatorId> for Module<T> {
+	fn on_session_ending(_ending: u32, _start_session: u32) -> Option<Vec<T::ValidatorId>> {
+		if Changed::take() {
+			Some(Self::validators())
+		} else {
+			None
+		}
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		ValidatorId = <T as session::Trait>::ValidatorId,
+	{
+		ValidatorAdded(ValidatorId),
+		ValidatorRemoved(ValidatorId),
+	}
+);
+
+#[cfg(test)]
+mod tests {
+	use super::{Module, Trait, RawEvent};
+
+	use std::cell::RefCell;
+	use sp_core::H256;
+	use frame_support::{impl_outer_origin, impl_outer_event, assert_ok, assert_err, parameter_types, weights::Weight};
+	use sp_runtime::{
+		traits::{BlakeTwo256, ConvertInto, IdentityLookup, OnInitialize, OpaqueKeys}, testing::{Header, UintAuthorityId},
+		KeyTypeId, Perbill, RuntimeAppPublic,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {}
+	}
+
+	thread_local! {
+		static AUTHORITIES: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+	}
+
+	/// Records the authorities, as Aura and GRANDPA would take them over.
+	pub struct TestSessionHandler;
+	impl session::SessionHandler<u64> for TestSessionHandler {
+		const KEY_TYPE_IDS: &'static [KeyTypeId] = &[UintAuthorityId::ID];
+		fn on_genesis_session<K: OpaqueKeys>(validators: &[(u64, K)]) {
+			AUTHORITIES.with(|a| *a.borrow_mut() = validators.iter().map(|(v, _)| *v).collect());
+		}
+		fn on_new_session<K: OpaqueKeys>(_changed: bool, validators: &[(u64, K)], _queued: &[(u64, K)]) {
+			AUTHORITIES.with(|a| *a.borrow_mut() = validators.iter().map(|(v, _)| *v).collect());
+		}
+		fn on_disabled(_validator_index: usize) {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	parameter_types! {
+		pub const Period: u64 = 1;
+		pub const Offset: u64 = 0;
+		pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(33);
+	}
+	impl session::Trait for TestRuntime {
+		type Event = ();
+		type ValidatorId = u64;
+		type ValidatorIdOf = ConvertInto;
+		type ShouldEndSession = session::PeriodicSessions<Period, Offset>;
+		type OnSessionEnding = ValidatorSet;
+		type SessionHandler = TestSessionHandler;
+		type Keys = UintAuthorityId;
+		type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+		type SelectInitialValidators = ();
+	}
+
+	mod validator_set_event {
+		pub use crate::validator_set::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for TestRuntime {
+			validator_set_event<T>,
+		}
+	}
+
+	impl Trait for TestRuntime {
+		type Event = TestEvent;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type Session = session::Module<TestRuntime>;
+	type ValidatorSet = Module<TestRuntime>;
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let mut storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			super::GenesisConfig::<TestRuntime> {
+				validators: vec![1, 2],
+			}.assimilate_storage(&mut storage).unwrap();
+			session::GenesisConfig::<TestRuntime> {
+				keys: vec![(1, UintAuthorityId(1)), (2, UintAuthorityId(2))],
+			}.assimilate_storage(&mut storage).unwrap();
+			sp_io::TestExternalities::from(storage)
+		}
+	}
+
+	/// Start the next block, which with `Period` 1 is also the next session.
+	fn next_session() {
+		let block = System::block_number() + 1;
+		System::set_block_number(block);
+		Session::on_initialize(block);
+	}
+
+	fn authorities() -> Vec<u64> {
+		AUTHORITIES.with(|a| a.borrow().clone())
+	}
+
+	#[test]
+	fn genesis_validators_are_the_authorities() {
+		ExtBuilder::build().execute_with(|| {
+			assert_eq!(authorities(), vec![1, 2]);
+			assert_eq!(Session::validators(), vec![1, 2]);
+		})
+	}
+
+	#[test]
+	fn only_root_changes_validators() {
+		ExtBuilder::build().execute_with(|| {
+			assert!(ValidatorSet::add_validator(Origin::signed(1), 3).is_err());
+			assert!(ValidatorSet::remove_validator(Origin::signed(1), 2).is_err());
+			assert_eq!(ValidatorSet::validators(), vec![1, 2]);
+		})
+	}
+
+	#[test]
+	fn added_validators_rotate_in_after_the_next_session() {
+		ExtBuilder::build().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Session::set_keys(Origin::signed(3), UintAuthorityId(3), vec![]));
+			assert_ok!(ValidatorSet::add_validator(Origin::ROOT, 3));
+
+			let expected_event = TestEvent::validator_set_event(RawEvent::ValidatorAdded(3));
+			assert!(System::events().iter().any(|a| a.event == expected_event));
+			assert_err!(ValidatorSet::add_validator(Origin::ROOT, 3), "must not be a validator to be added");
+
+			// the change is queued when this session ends...
+			next_session();
+			assert_eq!(authorities(), vec![1, 2]);
+			// ...and applied when the next one starts
+			next_session();
+			assert_eq!(authorities(), vec![1, 2, 3]);
+			assert_eq!(Session::validators(), vec![1, 2, 3]);
+		})
+	}
+
+	#[test]
+	fn validators_without_keys_are_not_added() {
+		ExtBuilder::build().execute_with(|| {
+			System::set_block_number(1);
+			assert_err!(ValidatorSet::add_validator(Origin::ROOT, 3), "must have set session keys to be added");
+			assert_eq!(ValidatorSet::validators(), vec![1, 2]);
+
+			next_session();
+			next_session();
+			assert_eq!(authorities(), vec![1, 2]);
+		})
+	}
+
+	#[test]
+	fn removed_validators_rotate_out_after_the_next_session() {
+		ExtBuilder::build().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(ValidatorSet::remove_validator(Origin::ROOT, 1));
+
+			let expected_event = TestEvent::validator_set_event(RawEvent::ValidatorRemoved(1));
+			assert!(System::events().iter().any(|a| a.event == expected_event));
+			assert_err!(ValidatorSet::remove_validator(Origin::ROOT, 1), "must be a validator to be removed");
+			assert_err!(ValidatorSet::remove_validator(Origin::ROOT, 2), "the last validator can not be removed");
+
+			next_session();
+			assert_eq!(authorities(), vec![1, 2]);
+			next_session();
+			assert_eq!(authorities(), vec![2]);
+		})
+	}
+
+	#[test]
+	fn unchanged_validators_stay() {
+		ExtBuilder::build().execute_with(|| {
+			for _ in 0..5 {
+				next_session();
+				assert_eq!(authorities(), vec![1, 2]);
+			}
+			assert_eq!(Session::current_index(), 5);
+		})
+	}
+
+	#[test]
+	fn changes_across_several_sessions() {
+		ExtBuilder::build().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Session::set_keys(Origin::signed(3), UintAuthorityId(3), vec![]));
+			assert_ok!(ValidatorSet::add_validator(Origin::ROOT, 3));
+			next_session();
+			assert_ok!(ValidatorSet::remove_validator(Origin::ROOT, 1));
+			next_session();
+			assert_eq!(authorities(), vec![1, 2, 3]);
+			next_session();
+			assert_eq!(authorities(), vec![2, 3]);
+			next_session();
+			assert_eq!(authorities(), vec![2, 3]);
+		})
+	}
+}
@@ -0,0 +1,333 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Vesting schedules set up at genesis.
+//!
+//! A schedule locks part of an account's balance from its `start` block on. Nothing is released
+//! before the `cliff`; from then on the lock shrinks linearly until it is gone `length` blocks after
+//! `start`. The lock only shrinks when its owner calls `vest`.
+
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, dispatch::DispatchResult,
+	traits::{Currency, LockIdentifier, LockableCurrency, WithdrawReasons},
+};
+use sp_runtime::{Perbill, RuntimeDebug, traits::{Bounded, SaturatedConversion, SimpleArithmetic, Zero}};
+use system::ensure_signed;
+
+const VESTING_ID: LockIdentifier = *b"vesting ";
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// The currency vested balances are locked in.
+	type Currency: LockableCurrency<Self::AccountId, Moment = Self::BlockNumber>;
+}
+
+/// An amount released linearly from `start` to `start + length`, but not before `start + cliff`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct VestingSchedule<Balance, BlockNumber> {
+	/// The amount locked at `start`.
+	pub locked: Balance,
+	/// The block the release is counted from.
+	pub start: BlockNumber,
+	/// Blocks after `start` before anything is released.
+	pub cliff: BlockNumber,
+	/// Blocks after `start` until everything is released.
+	pub length: BlockNumber,
+}
+
+impl<Balance, BlockNumber> VestingSchedule<Balance, BlockNumber> where
+	Balance: SimpleArithmetic + Copy,
+	BlockNumber: SimpleArithmetic + Copy,
+{
+	/// Whether the schedule can be kept: the cliff is within the length, which is not zero.
+	pub fn is_valid(&self) -> bool {
+		!self.length.is_zero() && self.cliff <= self.length
+	}
+
+	/// The amount still locked at block `n`.
+	pub fn locked_at(&self, n: BlockNumber) -> Balance {
+		if n < self.start.saturating_add(self.cliff) {
+			return self.locked;
+		}
+		if n >= self.start.saturating_add(self.length) {
+			return Zero::zero();
+		}
+
+		let elapsed = (n - self.start).saturated_into::<u64>();
+		let released = Perbill::from_rational_approximation(elapsed, self.length.saturated_into::<u64>())
+			* self.locked;
+		self.locked - released
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Vesting {
+		/// The schedule of each account with a vesting balance.
+		pub Vesting get(fn vesting): map T::AccountId => Option<VestingSchedule<BalanceOf<T>, T::BlockNumber>>;
+	}
+	add_extra_genesis {
+		/// Schedules as `(account, locked, start, cliff, length)`. An account can have one, and it
+		/// must not lock more than the account's balance.
+		config(vesting): Vec<(T::AccountId, BalanceOf<T>, T::BlockNumber, T::BlockNumber, T::BlockNumber)>;
+		build(|config: &GenesisConfig<T>| {
+			for &(ref who, locked, start, cliff, length) in config.vesting.iter() {
+				let schedule = VestingSchedule { locked, start, cliff, length };
+				assert!(
+					schedule.is_valid(),
+					"vesting schedules need a length of at least the cliff and one block",
+				);
+				assert!(!<Vesting<T>>::exists(who), "an account can have one vesting schedule");
+				assert!(
+					T::Currency::free_balance(who) >= locked,
+					"vesting schedules can not lock more than the account's balance",
+				);
+
+				<Vesting<T>>::insert(who, schedule);
+				<Module<T>>::update_lock(who);
+			}
+		});
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Release what the signer's schedule allows by now.
+		pub fn vest(origin) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let locked = Self::update_lock(&who).ok_or("must have a vesting schedule")?;
+
+			Self::deposit_event(RawEvent::Vested(who, locked));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The balance of `who` that is still locked by their schedule.
+	pub fn vesting_balance(who: &T::AccountId) -> BalanceOf<T> {
+		Self::vesting(who)
+			.map(|s| s.locked_at(<system::Module<T>>::block_number()))
+			.unwrap_or_else(Zero::zero)
+	}
+
+	/// Shrink the lock of `who` to what is still locked, dropping it and the schedule once all is
+	/// released. Returns the locked amount, if `who` had a schedule.
+	fn update_lock(who: &T::AccountId) -> Option<BalanceOf<T>> {
+		let schedule = Self::vesting(who)?;
+		let locked = schedule.locked_at(<system::Module<T>>::block_number());
+
+		if locked.is_zero() {
+			T::Currency::remove_lock(VESTING_ID, who);
+			<Vesting<T>>::remove(who);
+		} else {
+			let until = T::BlockNumber::max_value();
+			T::Currency::set_lock(VESTING_ID, who, locked, until, WithdrawReasons::all());
+		}
+		Some(locked)
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+	{
+		/// An account vested; what is still locked.
+		Vested(AccountId, Balance),
+	}
+);
+
+#[cfg(test)]
+mod tests {
+	use super::{Module, Trait, RawEvent, VestingSchedule};
+
+	use sp_core::H256;
+	use frame_support::{
+		impl_outer_origin, impl_outer_event, assert_ok, assert_err, parameter_types,
+		traits::{Currency, WithdrawReason}, weights::Weight,
+	};
+	use sp_runtime::{
+		traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for TestRuntime {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct TestRuntime;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	}
+	impl system::Trait for TestRuntime {
+		type Origin = Origin;
+		type Call = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for TestRuntime {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+
+	mod vesting_event {
+		pub use crate::vesting::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for TestRuntime {
+			vesting_event<T>,
+		}
+	}
+
+	impl Trait for TestRuntime {
+		type Event = TestEvent;
+		type Currency = balances::Module<TestRuntime>;
+	}
+
+	type System = system::Module<TestRuntime>;
+	type Balances = balances::Module<TestRuntime>;
+	type Vesting = Module<TestRuntime>;
+
+	/// Locks 1_000 of 1_500 from block 10, none released before block 30, all by block 110.
+	const VESTED: u64 = 1;
+	const OTHER: u64 = 2;
+
+	pub struct ExtBuilder;
+	impl ExtBuilder {
+		pub fn build() -> sp_io::TestExternalities {
+			let mut storage = system::GenesisConfig::default()
+				.build_storage::<TestRuntime>()
+				.unwrap();
+			balances::GenesisConfig::<TestRuntime> {
+				balances: vec![(VESTED, 1_500), (OTHER, 100)],
+				vesting: vec![],
+			}.assimilate_storage(&mut storage).unwrap();
+			super::GenesisConfig::<TestRuntime> {
+				vesting: vec![(VESTED, 1_000, 10, 20, 100)],
+			}.assimilate_storage(&mut storage).unwrap();
+			sp_io::TestExternalities::from(storage)
+		}
+	}
+
+	fn can_withdraw(who: u64, amount: u64) -> bool {
+		let new_balance = Balances::free_balance(&who) - amount;
+		Balances::ensure_can_withdraw(&who, amount, WithdrawReason::Transfer.into(), new_balance).is_ok()
+	}
+
+	#[test]
+	fn schedules_release_linearly_after_the_cliff() {
+		let schedule = VestingSchedule { locked: 1_000u64, start: 10u64, cliff: 20, length: 100 };
+
+		assert_eq!(schedule.locked_at(0), 1_000);
+		assert_eq!(schedule.locked_at(29), 1_000);
+		// the cliff releases what accrued since `start`
+		assert_eq!(schedule.locked_at(30), 800);
+		assert_eq!(schedule.locked_at(60), 500);
+		assert_eq!(schedule.locked_at(109), 10);
+		assert_eq!(schedule.locked_at(110), 0);
+		assert_eq!(schedule.locked_at(1_000), 0);
+	}
+
+	#[test]
+	fn invalid_schedules_are_detected() {
+		assert!(VestingSchedule { locked: 1u64, start: 0u64, cliff: 0, length: 1 }.is_valid());
+		assert!(VestingSchedule { locked: 1u64, start: 0u64, cliff: 5, length: 5 }.is_valid());
+		assert!(!VestingSchedule { locked: 1u64, start: 0u64, cliff: 6, length: 5 }.is_valid());
+		assert!(!VestingSchedule { locked: 1u64, start: 0u64, cliff: 0, length: 0 }.is_valid());
+	}
+
+	#[test]
+	fn genesis_locks_the_vesting_balance() {
+		ExtBuilder::build().execute_with(|| {
+			System::set_block_number(1);
+			assert_eq!(Vesting::vesting_balance(&VESTED), 1_000);
+			assert!(can_withdraw(VESTED, 500));
+			assert!(!can_withdraw(VESTED, 501));
+			assert_eq!(Vesting::vesting_balance(&OTHER), 0);
+		})
+	}
+
+	#[test]
+	fn vest_releases_what_is_due() {
+		ExtBuilder::build().execute_with(|| {
+			System::set_block_number(60);
+			// nothing is released until the owner vests
+			assert!(!can_withdraw(VESTED, 501));
+
+			assert_ok!(Vesting::vest(Origin::signed(VESTED)));
+			let expected_event = TestEvent::vesting_event(RawEvent::Vested(VESTED, 500));
+			assert!(System::events().iter().any(|a| a.event == expected_event));
+			assert!(can_withdraw(VESTED, 1_000));
+			assert!(!can_withdraw(VESTED, 1_001));
+			assert_ok!(Balances::transfer(Origin::signed(VESTED), OTHER, 1_000));
+		})
+	}
+
+	#[test]
+	fn vesting_everything_drops_the_schedule() {
+		ExtBuilder::build().execute_with(|| {
+			System::set_block_number(110);
+			assert_ok!(Vesting::vest(Origin::signed(VESTED)));
+
+			assert_eq!(Vesting::vesting(&VESTED), None);
+			assert!(can_withdraw(VESTED, 1_500));
+			assert_err!(Vesting::vest(Origin::signed(VESTED)), "must have a vesting schedule");
+		})
+	}
+
+	#[test]
+	fn accounts_without_schedules_can_not_vest() {
+		ExtBuilder::build().execute_with(|| {
+			assert_err!(Vesting::vest(Origin::signed(OTHER)), "must have a vesting schedule");
+		})
+	}
+
+	#[test]
+	#[should_panic(expected = "vesting schedules can not lock more than the account's balance")]
+	fn genesis_rejects_schedules_above_the_balance() {
+		let mut storage = system::GenesisConfig::default()
+			.build_storage::<TestRuntime>()
+			.unwrap();
+		balances::GenesisConfig::<TestRuntime> {
+			balances: vec![(OTHER, 100)],
+			vesting: vec![],
+		}.assimilate_storage(&mut storage).unwrap();
+		super::GenesisConfig::<TestRuntime> {
+			vesting: vec![(OTHER, 101, 0, 0, 10)],
+		}.assimilate_storage(&mut storage).unwrap();
+	}
+}
@@ -1,7 +1,8 @@
 use sp_core::{Pair, Public, sr25519};
 use substrate_demo_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, IndicesConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, AuraConfig, Balance, BalancesConfig, BlockNumber, ContractsConfig, ContractsSchedule,
+	CouncilConfig, GenesisConfig, GrandpaConfig, SessionConfig, IndicesConfig, SystemConfig,
+	ValidatorSetConfig, VestingConfig, WASM_BINARY, Signature, DAYS, opaque::SessionKeys,
 };
 use sp_consensus_aura::sr25519::{AuthorityId as AuraId};
 use grandpa_primitives::{AuthorityId as GrandpaId};
@@ -14,6 +15,13 @@ use sp_runtime::traits::{Verify, IdentifyAccount};
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
 pub type ChainSpec = sc_service::ChainSpec<GenesisConfig>;
 
+/// A vesting schedule: the account, the amount locked, and the blocks the release starts at, of
+/// the cliff and of the whole release, see `vesting::VestingSchedule`.
+pub type Vesting = (AccountId, Balance, BlockNumber, BlockNumber, BlockNumber);
+
+/// Balance of the accounts the presets endow.
+pub const ENDOWMENT: Balance = 1 << 60;
+
 /// The chain specification option. This is expected to come in from the CLI and
 /// is little more than one of a number of alternatives which can easily be converted
 /// from a string (`--chain=...`) into a `ChainSpec`.
@@ -41,9 +49,15 @@ pub fn get_account_id_from_seed<TPublic: Public>(seed: &str) -> AccountId where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Helper function to generate an authority key for Aura
-pub fn get_authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
+/// Helper function to endow each of `accounts` with `ENDOWMENT`
+pub fn endow(accounts: Vec<AccountId>) -> Vec<(AccountId, Balance)> {
+	accounts.into_iter().map(|account| (account, ENDOWMENT)).collect()
+}
+
+/// Helper function to generate a validator's account and its Aura and GRANDPA keys
+pub fn get_authority_keys_from_seed(s: &str) -> (AccountId, AuraId, GrandpaId) {
 	(
+		get_account_id_from_seed::<sr25519::Public>(s),
 		get_from_seed::<AuraId>(s),
 		get_from_seed::<GrandpaId>(s),
 	)
@@ -51,7 +65,7 @@ pub fn get_authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
 
 impl Alternative {
 	/// Get an actual chain config from one of the alternatives.
-	pub(crate) fn load(self) -> Result<ChainSpec, String> {
+	pub fn load(self) -> Result<ChainSpec, String> {
 		Ok(match self {
 			Alternative::Development => ChainSpec::from_genesis(
 				"Development",
@@ -59,13 +73,16 @@ impl Alternative {
 				|| testnet_genesis(vec![
 					get_authority_keys_from_seed("Alice"),
 				],
-				get_account_id_from_seed::<sr25519::Public>("Alice"),
 				vec![
 					get_account_id_from_seed::<sr25519::Public>("Alice"),
+				],
+				endow(vec![
+					get_account_id_from_seed::<sr25519::Public>("Alice"),
 					get_account_id_from_seed::<sr25519::Public>("Bob"),
 					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
-				],
+				]),
+				vec![],
 				true),
 				vec![],
 				None,
@@ -80,11 +97,15 @@ impl Alternative {
 					get_authority_keys_from_seed("Alice"),
 					get_authority_keys_from_seed("Bob"),
 				],
-				get_account_id_from_seed::<sr25519::Public>("Alice"),
 				vec![
 					get_account_id_from_seed::<sr25519::Public>("Alice"),
 					get_account_id_from_seed::<sr25519::Public>("Bob"),
 					get_account_id_from_seed::<sr25519::Public>("Charlie"),
+				],
+				endow(vec![
+					get_account_id_from_seed::<sr25519::Public>("Alice"),
+					get_account_id_from_seed::<sr25519::Public>("Bob"),
+					get_account_id_from_seed::<sr25519::Public>("Charlie"),
 					get_account_id_from_seed::<sr25519::Public>("Dave"),
 					get_account_id_from_seed::<sr25519::Public>("Eve"),
 					get_account_id_from_seed::<sr25519::Public>("Ferdie"),
@@ -94,6 +115,12 @@ impl Alternative {
 					get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
+				]),
+				vec![
+					// half released over a month, after a day
+					(get_account_id_from_seed::<sr25519::Public>("Dave//stash"), ENDOWMENT / 2, 0, DAYS, 30 * DAYS),
+					// all released over a year, after a week
+					(get_account_id_from_seed::<sr25519::Public>("Eve//stash"), ENDOWMENT, 0, 7 * DAYS, 365 * DAYS),
 				],
 				true),
 				vec![],
@@ -114,30 +141,150 @@ impl Alternative {
 	}
 }
 
-fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
-	root_key: AccountId,
-	endowed_accounts: Vec<AccountId>,
-	_enable_println: bool) -> GenesisConfig {
+/// The session keys of an authority.
+pub(crate) fn session_keys(aura: AuraId, grandpa: GrandpaId) -> SessionKeys {
+	SessionKeys { aura, grandpa }
+}
+
+pub(crate) fn testnet_genesis(initial_authorities: Vec<(AccountId, AuraId, GrandpaId)>,
+	council: Vec<AccountId>,
+	endowed_accounts: Vec<(AccountId, Balance)>,
+	vesting: Vec<Vesting>,
+	enable_println: bool) -> GenesisConfig {
 	GenesisConfig {
 		system: Some(SystemConfig {
 			code: WASM_BINARY.to_vec(),
 			changes_trie_config: Default::default(),
 		}),
 		indices: Some(IndicesConfig {
-			ids: endowed_accounts.clone(),
+			ids: endowed_accounts.iter().map(|x| x.0.clone()).collect(),
 		}),
 		balances: Some(BalancesConfig {
-			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
+			balances: endowed_accounts,
 			vesting: vec![],
 		}),
-		sudo: Some(SudoConfig {
-			key: root_key,
+		vesting: Some(VestingConfig {
+			vesting,
 		}),
+		// new chains are governed by the council from the start
+		sudo: None,
+		council: Some(CouncilConfig {
+			members: council,
+			phantom: Default::default(),
+		}),
+		// the authorities are set by `session`
 		aura: Some(AuraConfig {
-			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
+			authorities: vec![],
 		}),
 		grandpa: Some(GrandpaConfig {
-			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
+			authorities: vec![],
+		}),
+		validator_set: Some(ValidatorSetConfig {
+			validators: initial_authorities.iter().map(|x| x.0.clone()).collect(),
+		}),
+		session: Some(SessionConfig {
+			keys: initial_authorities.iter()
+				.map(|x| (x.0.clone(), session_keys(x.1.clone(), x.2.clone())))
+				.collect(),
 		}),
+		contracts: Some(ContractsConfig {
+			current_schedule: ContractsSchedule {
+				enable_println,
+				..Default::default()
+			},
+			// the price before the first block; `gas_price` follows the fee multiplier from then on
+			gas_price: 1,
+		}),
+	}
+}
+
+/// Check that the vesting schedules of `genesis` are valid and lock at most the endowment of their
+/// account.
+pub fn check_vesting(genesis: &GenesisConfig) -> Result<(), String> {
+	let balances = genesis.balances.as_ref().map(|config| &config.balances[..]).unwrap_or(&[]);
+	let vesting = genesis.vesting.as_ref().map(|config| &config.vesting[..]).unwrap_or(&[]);
+
+	for (i, (who, locked, _start, cliff, length)) in vesting.iter().enumerate() {
+		if *length == 0 || cliff > length {
+			return Err(format!(
+				"The vesting schedule of {} needs a length of at least its cliff and one block",
+				who,
+			));
+		}
+		if vesting[..i].iter().any(|schedule| &schedule.0 == who) {
+			return Err(format!("{} has more than one vesting schedule", who));
+		}
+		let endowment = balances.iter()
+			.filter(|(account, _)| account == who)
+			.map(|(_, balance)| *balance)
+			.sum::<Balance>();
+		if locked > &endowment {
+			return Err(format!(
+				"The vesting schedule of {} locks {}, more than its endowment of {}",
+				who, locked, endowment,
+			));
+		}
+	}
+
+	Ok(())
+}
+
+/// Check the vesting schedules of `spec` if it has a genesis config. The runtime checks those of
+/// raw specs when it builds the genesis block.
+pub fn check_spec(spec: &ChainSpec) -> Result<(), String> {
+	let json: serde_json::Value = serde_json::from_str(&spec.to_json(false)?)
+		.map_err(|e| format!("Failed to parse the chain spec: {}", e))?;
+	let genesis = match json.pointer("/genesis/runtime") {
+		Some(genesis) => serde_json::from_value::<GenesisConfig>(genesis.clone())
+			.map_err(|e| format!("Invalid genesis config: {}", e))?,
+		None => return Ok(()),
+	};
+
+	check_vesting(&genesis).map_err(|e| format!("Invalid chain spec {}: {}", spec.id(), e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn genesis(vesting: Vec<Vesting>) -> GenesisConfig {
+		testnet_genesis(
+			vec![get_authority_keys_from_seed("Alice")],
+			vec![],
+			vec![(get_account_id_from_seed::<sr25519::Public>("Alice"), 1_000)],
+			vesting,
+			false,
+		)
+	}
+
+	fn alice() -> AccountId {
+		get_account_id_from_seed::<sr25519::Public>("Alice")
+	}
+
+	#[test]
+	fn presets_are_valid() {
+		for preset in &[Alternative::Development, Alternative::LocalTestnet] {
+			assert_eq!(check_spec(&preset.clone().load().unwrap()), Ok(()));
+		}
+	}
+
+	#[test]
+	fn schedules_within_the_endowment_are_accepted() {
+		assert_eq!(check_vesting(&genesis(vec![(alice(), 1_000, 0, 10, 100)])), Ok(()));
+		assert_eq!(check_vesting(&genesis(vec![(alice(), 1, 5, 100, 100)])), Ok(()));
+	}
+
+	#[test]
+	fn schedules_above_the_endowment_are_rejected() {
+		assert!(check_vesting(&genesis(vec![(alice(), 1_001, 0, 10, 100)])).is_err());
+		let bob = get_account_id_from_seed::<sr25519::Public>("Bob");
+		assert!(check_vesting(&genesis(vec![(bob, 1, 0, 10, 100)])).is_err());
+	}
+
+	#[test]
+	fn malformed_schedules_are_rejected() {
+		assert!(check_vesting(&genesis(vec![(alice(), 1, 0, 10, 0)])).is_err());
+		assert!(check_vesting(&genesis(vec![(alice(), 1, 0, 101, 100)])).is_err());
+		assert!(check_vesting(&genesis(vec![(alice(), 1, 0, 0, 100), (alice(), 1, 0, 0, 100)])).is_err());
 	}
 }
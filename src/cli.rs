@@ -1,13 +1,65 @@
 use crate::service;
-use futures::{future::{select, Map}, FutureExt, TryFutureExt, channel::oneshot, compat::Future01CompatExt};
+use crate::shutdown::{self, Tasks};
+use futures::{future::{select, Either, Map}, FutureExt, TryFutureExt, channel::oneshot, compat::Future01CompatExt};
+use futures01::{future as future01, Async, Future as Future01};
+use parking_lot::Mutex;
 use std::cell::RefCell;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::runtime::Runtime;
 pub use sc_cli::{VersionInfo, IntoExit, error};
-use sc_cli::{display_role, informant, parse_and_prepare, ParseAndPrepare, NoCustom};
+use sc_cli::{display_role, informant, parse_and_prepare, GetSharedParams, NoCustom, ParseAndPrepare, SharedParams};
 use sc_service::{AbstractService, Roles as ServiceRoles, Configuration};
 use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
 use crate::chain_spec;
-use log::info;
+use crate::export_state::ExportStateCmd;
+use crate::runtime_upgrade::RuntimeUpgradeCmd;
+use crate::testnet::TestnetCmd;
+use log::{info, warn};
+use structopt::StructOpt;
+
+/// Subcommands in addition to the standard ones.
+#[derive(Debug, StructOpt, Clone)]
+pub enum CustomSubcommands {
+	/// Run a local testnet of validators, each in its own process.
+	#[structopt(name = "testnet")]
+	Testnet(TestnetCmd),
+
+	/// Write a raw chain spec that starts a new chain from the state of a local database.
+	#[structopt(name = "export-state")]
+	ExportState(ExportStateCmd),
+
+	/// Upgrade the runtime of a running chain through a council motion.
+	#[structopt(name = "runtime-upgrade")]
+	RuntimeUpgrade(RuntimeUpgradeCmd),
+}
+
+impl GetSharedParams for CustomSubcommands {
+	fn shared_params(&self) -> Option<&SharedParams> {
+		match self {
+			CustomSubcommands::Testnet(cmd) => cmd.shared_params(),
+			CustomSubcommands::ExportState(cmd) => cmd.shared_params(),
+			CustomSubcommands::RuntimeUpgrade(cmd) => cmd.shared_params(),
+		}
+	}
+}
+
+/// Additional parameters for the `run` command.
+#[derive(Debug, StructOpt, Clone)]
+pub struct NodeParams {
+	/// Expose Prometheus metrics on `127.0.0.1:<PORT>/metrics`.
+	#[structopt(long = "prometheus-port", value_name = "PORT")]
+	pub prometheus_port: Option<u16>,
+
+	/// Index the demo modules' events into the SQLite database at this path.
+	#[structopt(long = "indexer-db", value_name = "PATH", parse(from_os_str))]
+	pub indexer_db: Option<PathBuf>,
+
+	/// Seconds to wait for the node's tasks to stop on shutdown before giving up.
+	#[structopt(long = "shutdown-timeout", value_name = "SECS", default_value = "10")]
+	pub shutdown_timeout: u64,
+}
 
 /// Parse command line arguments into service configuration.
 pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()> where
@@ -15,10 +67,18 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 	T: Into<std::ffi::OsString> + Clone,
 	E: IntoExit,
 {
-	type Config<T> = Configuration<(), T>;
-	match parse_and_prepare::<NoCustom, NoCustom, _>(&version, "substrate-node", args) {
+	type Config<T> = Configuration<service::NodeConfig, T>;
+	match parse_and_prepare::<CustomSubcommands, NodeParams, _>(&version, "substrate-node", args) {
 		ParseAndPrepare::Run(cmd) => cmd.run(load_spec, exit,
-		|exit, _cli_args, _custom_args, config: Config<_>| {
+		|exit, _cli_args, custom_args: NodeParams, mut config: Config<_>| {
+			let tasks = Tasks::default();
+			let shutdown_timeout = Duration::from_secs(custom_args.shutdown_timeout);
+			config.custom = service::NodeConfig {
+				prometheus_port: custom_args.prometheus_port,
+				indexer_db: custom_args.indexer_db,
+				tasks: tasks.clone(),
+			};
+
 			info!("{}", version.name);
 			info!("  version {}", config.full_version());
 			info!("  by {}, 2017, 2018", version.author);
@@ -30,12 +90,16 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 				ServiceRoles::LIGHT => run_until_exit(
 					runtime,
 					service::new_light(config)?,
-					exit
+					exit,
+					tasks,
+					shutdown_timeout,
 				),
 				_ => run_until_exit(
 					runtime,
 					service::new_full(config)?,
-					exit
+					exit,
+					tasks,
+					shutdown_timeout,
 				),
 			}
 		}),
@@ -49,23 +113,32 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 		ParseAndPrepare::PurgeChain(cmd) => cmd.run(load_spec),
 		ParseAndPrepare::RevertChain(cmd) => cmd.run_with_builder(|config: Config<_>|
 			Ok(new_full_start!(config).0), load_spec),
-		ParseAndPrepare::CustomCommand(_) => Ok(())
+		ParseAndPrepare::CustomCommand(CustomSubcommands::Testnet(cmd)) => cmd.run(exit),
+		ParseAndPrepare::CustomCommand(CustomSubcommands::ExportState(cmd)) => cmd.run(load_spec, &version),
+		ParseAndPrepare::CustomCommand(CustomSubcommands::RuntimeUpgrade(cmd)) => cmd.run(),
 	}?;
 
 	Ok(())
 }
 
+/// Load a preset or a chain spec file, rejecting vesting schedules that lock more than their
+/// account is endowed with.
 fn load_spec(id: &str) -> Result<Option<chain_spec::ChainSpec>, String> {
-	Ok(match chain_spec::Alternative::from(id) {
-		Some(spec) => Some(spec.load()?),
-		None => None,
-	})
+	let spec = match chain_spec::Alternative::from(id) {
+		Some(spec) => spec.load()?,
+		None => chain_spec::ChainSpec::from_json_file(PathBuf::from(id))?,
+	};
+	chain_spec::check_spec(&spec)?;
+
+	Ok(Some(spec))
 }
 
 fn run_until_exit<T, E>(
 	mut runtime: Runtime,
 	service: T,
 	e: E,
+	tasks: Tasks,
+	shutdown_timeout: Duration,
 ) -> error::Result<()>
 where
 	T: AbstractService,
@@ -73,9 +146,9 @@ where
 {
 	let (exit_send, exit) = oneshot::channel();
 
-	let informant = informant::build(&service);
+	let informant = tasks.track("informant", informant::build(&service));
 
-	let future = select(exit, informant)
+	let future = select(exit, informant.boxed())
 		.map(|_| Ok(()))
 		.compat();
 
@@ -85,29 +158,59 @@ where
 	// but we need to keep holding a reference to the global telemetry guard
 	let _telemetry = service.telemetry();
 
+	// the database is flushed and closed once the last reference to the backend is gone
+	let backend = Arc::downgrade(service.client().backend());
+
+	// the service is driven on the runtime but owned here, so that it is dropped on this
+	// thread rather than whenever a worker gets around to it
+	let service = Arc::new(Mutex::new(Some(service)));
+
 	let service_res = {
-		let exit = e.into_exit();
-		let service = service
+		// stop authoring before the rest of the service is torn down, so that we
+		// never produce a block we won't be around to announce
+		let authoring = tasks.clone();
+		let exit = e.into_exit().map(move |()| {
+			info!("Shutting down, stopped {} authoring task(s)", authoring.stop_authoring());
+		});
+		let driven = service.clone();
+		let service = future01::poll_fn(move || match driven.lock().as_mut() {
+			Some(service) => service.poll(),
+			None => Ok(Async::Ready(())),
+		})
 			.map_err(|err| error::Error::Service(err))
 			.compat();
 		let select = select(service, exit)
-			.map(|_| Ok(()))
+			.map(|either| match either {
+				Either::Left((res, _)) => res,
+				Either::Right(((), _)) => Ok(()),
+			})
 			.compat();
 		runtime.block_on(select)
 	};
 
+	drop(service.lock().take());
 	let _ = exit_send.send(());
 
-	// TODO [andre]: timeout this future #1318
+	let stopped = shutdown::wait_for_idle(runtime, shutdown_timeout, &tasks);
 
-	use futures01::Future;
+	if backend.upgrade().is_none() {
+		info!("Database flushed and closed");
+	} else if stopped {
+		warn!("Database was not closed, the backend is still referenced outside of the service");
+	} else {
+		warn!("Exiting without flushing the database, the backend is held by a task that failed to stop");
+	}
 
-	let _ = runtime.shutdown_on_idle().wait();
+	if !stopped {
+		std::process::exit(shutdown::EXIT_TIMEOUT);
+	}
 
 	service_res
 }
 
-// handles ctrl-c
+/// Handles Ctrl-C, SIGTERM and SIGHUP.
+///
+/// `ctrlc` is built with its `termination` feature, so the same handler fires for all three.
 pub struct Exit;
 impl IntoExit for Exit {
 	type Exit = Map<oneshot::Receiver<()>, fn(Result<(), oneshot::Canceled>) -> ()>;
@@ -121,7 +224,7 @@ impl IntoExit for Exit {
 			if let Some(exit_send) = exit_send {
 				exit_send.send(()).expect("Error sending exit notification");
 			}
-		}).expect("Error setting Ctrl-C handler");
+		}).expect("Error setting Ctrl-C/SIGTERM handler");
 
 		exit.map(drop)
 	}
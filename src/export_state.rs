@@ -0,0 +1,185 @@
+//! The `export-state` subcommand: fork a chain off the state of a local database.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use log::info;
+use serde_json::{Map, Value};
+use sc_cli::{SharedParams, GetSharedParams, VersionInfo, error};
+use sc_service::Configuration;
+use sp_core::{Pair, ed25519, sr25519, storage::StorageKey};
+use sp_runtime::{BuildStorage, MultiSigner, generic::BlockId, traits::IdentifyAccount};
+use substrate_demo_runtime::{
+	opaque::Block, CouncilConfig, GenesisConfig, Hash, SessionConfig, SudoConfig, SystemConfig,
+	ValidatorSetConfig,
+};
+use structopt::StructOpt;
+use crate::chain_spec::{ChainSpec, session_keys};
+use crate::service::NodeConfig;
+
+/// The `export-state` command.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportStateCmd {
+	/// Hash or number of the block to export the state of; the best block by default.
+	#[structopt(long = "at", value_name = "BLOCK")]
+	pub at: Option<String>,
+
+	/// Write the chain spec to this file instead of stdout.
+	#[structopt(long = "output", value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Make the accounts of these secret URIs, e.g. `//Alice`, the council members.
+	#[structopt(long = "council", value_name = "SURI")]
+	pub council: Vec<String>,
+
+	/// Make the account of this secret URI the sudo key.
+	#[structopt(long = "sudo", value_name = "SURI")]
+	pub sudo: Option<String>,
+
+	/// Make the accounts and keys of these secret URIs the validators.
+	#[structopt(long = "authority", value_name = "SURI")]
+	pub authorities: Vec<String>,
+
+	/// Replace the runtime code with this wasm blob.
+	#[structopt(long = "code", value_name = "PATH", parse(from_os_str))]
+	pub code: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl GetSharedParams for ExportStateCmd {
+	fn shared_params(&self) -> Option<&SharedParams> {
+		Some(&self.shared_params)
+	}
+}
+
+fn pair<P: Pair>(suri: &str) -> Result<P, String> {
+	P::from_string(suri, None).map_err(|e| format!("Invalid secret URI {}: {:?}", suri, e))
+}
+
+fn parse_block(at: &str) -> Result<BlockId<Block>, String> {
+	if at.starts_with("0x") {
+		let hash = hex::decode(&at[2..]).map_err(|e| format!("Invalid block hash {}: {}", at, e))?;
+		if hash.len() != Hash::len_bytes() {
+			return Err(format!("Invalid block hash {}: expected 32 bytes", at));
+		}
+		Ok(BlockId::Hash(Hash::from_slice(&hash)))
+	} else {
+		at.parse().map(BlockId::Number).map_err(|e| format!("Invalid block number {}: {}", at, e))
+	}
+}
+
+fn hex_value(bytes: &[u8]) -> Value {
+	Value::String(format!("0x{}", hex::encode(bytes)))
+}
+
+impl ExportStateCmd {
+	/// The genesis items that replace the exported ones.
+	fn overrides(&self) -> Result<GenesisConfig, String> {
+		let code = self.code.as_ref()
+			.map(|path| fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e)))
+			.transpose()?;
+		let account = |suri: &String| pair::<sr25519::Pair>(suri)
+			.map(|pair| MultiSigner::from(pair.public()).into_account());
+		let sudo = self.sudo.as_ref().map(account).transpose()?;
+		let council = self.council.iter().map(account).collect::<Result<Vec<_>, _>>()?;
+		let authorities = self.authorities.iter()
+			.map(|suri| {
+				let aura = pair::<sr25519::Pair>(suri)?.public();
+				let grandpa = pair::<ed25519::Pair>(suri)?.public();
+				let account = MultiSigner::from(aura.clone()).into_account();
+				Ok((account, session_keys(aura.into(), grandpa.into())))
+			})
+			.collect::<Result<Vec<_>, String>>()?;
+		let has_authorities = !authorities.is_empty();
+
+		// `session` hands the keys to Aura and GRANDPA when its genesis is built
+		Ok(GenesisConfig {
+			system: code.map(|code| SystemConfig { code, changes_trie_config: Default::default() }),
+			indices: None,
+			balances: None,
+			sudo: sudo.map(|key| SudoConfig { key }),
+			aura: None,
+			grandpa: None,
+			council: if council.is_empty() {
+				None
+			} else {
+				Some(CouncilConfig { members: council, phantom: Default::default() })
+			},
+			validator_set: if has_authorities {
+				Some(ValidatorSetConfig { validators: authorities.iter().map(|x| x.0.clone()).collect() })
+			} else {
+				None
+			},
+			session: if has_authorities {
+				Some(SessionConfig { keys: authorities })
+			} else {
+				None
+			},
+			vesting: None,
+			contracts: None,
+		})
+	}
+
+	/// Export the state and write the raw chain spec of the fork.
+	pub fn run<F>(self, spec_factory: F, version: &VersionInfo) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Option<ChainSpec>, String>,
+	{
+		let overrides = self.overrides()?.build_storage()?;
+
+		let config: Configuration<NodeConfig, GenesisConfig> =
+			sc_cli::create_config_with_db_path(spec_factory, &self.shared_params, version)?;
+		let mut spec: Value = serde_json::from_str(&config.chain_spec.to_json(true)?)
+			.map_err(|e| format!("Failed to parse the chain spec: {}", e))?;
+		let name = config.chain_spec.name().to_string();
+		let id = config.chain_spec.id().to_string();
+
+		let client = new_full_start!(config).0.client().clone();
+		let at = match self.at {
+			Some(ref at) => parse_block(at)?,
+			None => BlockId::Hash(client.info().chain.best_hash),
+		};
+		let header = client.header(&at)
+			.map_err(|e| format!("Failed to read block {}: {}", at, e))?
+			.ok_or_else(|| format!("Block {} not found", at))?;
+
+		// child tries are not exported, none of the demo modules use them
+		let pairs = client.storage_pairs(&at, &StorageKey(Vec::new()))
+			.map_err(|e| format!(
+				"Failed to read the state at {}, it may have been pruned (see `--pruning archive`): {}",
+				at, e,
+			))?;
+		let mut top = pairs.into_iter()
+			.map(|(key, value)| (key.0, value.0))
+			.collect::<BTreeMap<_, _>>();
+		top.extend(overrides.top);
+
+		let top = top.iter()
+			.map(|(key, value)| (format!("0x{}", hex::encode(key)), hex_value(value)))
+			.collect::<Map<_, _>>();
+		let raw = spec.pointer_mut("/genesis/raw")
+			.and_then(Value::as_object_mut)
+			.ok_or("The chain spec has no raw genesis")?;
+		raw.insert("top".into(), Value::Object(top));
+		raw.insert("children".into(), Value::Object(Map::new()));
+
+		spec["name"] = Value::String(format!("{} fork", name));
+		spec["id"] = Value::String(format!("{}_fork", id));
+		spec["bootNodes"] = Value::Array(Vec::new());
+		spec["telemetryEndpoints"] = Value::Null;
+
+		let json = serde_json::to_string_pretty(&spec)
+			.map_err(|e| format!("Failed to encode the chain spec: {}", e))?;
+		match self.output {
+			Some(ref path) => {
+				fs::write(path, json)?;
+				info!("Wrote the state at #{} to {}", header.number, path.display());
+			},
+			None => println!("{}", json),
+		}
+
+		Ok(())
+	}
+}
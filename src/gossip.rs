@@ -0,0 +1,374 @@
+//! Gossip of signed off-chain announcements between `vec_set` members.
+//!
+//! Announcements travel over the consensus gossip of `NodeProtocol` under `ENGINE_ID`. A peer's
+//! message is only kept and propagated if it is fresh, correctly signed by an account that is in
+//! `vec_set::Members` at our best block, and the peer stays within its rate limit.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use codec::{Decode, Encode};
+use futures::{channel::mpsc, future::{join, ready}, Future, FutureExt, StreamExt};
+use log::{debug, warn};
+use parking_lot::{Mutex, RwLock};
+use sc_network::PeerId;
+use sc_network_gossip::{GossipEngine, ValidationResult, Validator, ValidatorContext};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	ConsensusEngineId,
+	generic::BlockId,
+	traits::{Block as BlockT, Hash as HashT, Header as HeaderT, Verify},
+};
+use substrate_demo_runtime::{AccountId, DemoApi, Signature};
+
+/// Engine id of the announcement gossip.
+pub const ENGINE_ID: ConsensusEngineId = *b"dmoa";
+
+const SIGNING_CONTEXT: &[u8] = b"substrate-demo-announcement";
+const MAX_MESSAGE_LEN: usize = 1024;
+const MAX_AGE_MS: u64 = 5 * 60 * 1000;
+const MAX_CLOCK_DRIFT_MS: u64 = 30 * 1000;
+const RATE_LIMIT_PERIOD: Duration = Duration::from_secs(10);
+const RATE_LIMIT_MESSAGES: u32 = 10;
+
+/// A status message signed by a `vec_set` member.
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+pub struct Announcement {
+	/// The member making the announcement.
+	pub who: AccountId,
+	/// Milliseconds since the UNIX epoch at which the announcement was made.
+	pub timestamp: u64,
+	/// Application-defined content.
+	pub message: Vec<u8>,
+	/// Signature of `who` over `Announcement::signing_payload`.
+	pub signature: Signature,
+}
+
+impl Announcement {
+	/// The bytes an announcement's signature is made over.
+	pub fn signing_payload(who: &AccountId, timestamp: u64, message: &[u8]) -> Vec<u8> {
+		(SIGNING_CONTEXT, who, timestamp, message).encode()
+	}
+
+	fn verify(&self) -> bool {
+		let payload = Self::signing_payload(&self.who, self.timestamp, &self.message);
+		self.signature.verify(&payload[..], &self.who)
+	}
+
+	fn is_expired(&self, now: u64) -> bool {
+		self.timestamp.saturating_add(MAX_AGE_MS) < now
+	}
+}
+
+fn now_ms() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|d| d.as_secs() * 1000 + d.subsec_millis() as u64)
+		.unwrap_or(0)
+}
+
+/// The topic all announcements are gossiped under.
+pub fn topic<B: BlockT>() -> B::Hash {
+	<<B::Header as HeaderT>::Hashing as HashT>::hash(b"demo-announcements")
+}
+
+/// Checks an announcement before it is accepted.
+pub trait CheckAnnouncement: Send + Sync {
+	/// Ok if the announcement may be delivered and propagated.
+	fn check(&self, announcement: &Announcement) -> Result<(), &'static str>;
+}
+
+/// Where the validator learns the members from.
+pub trait MemberSource<B: BlockT>: Send + Sync {
+	/// Hash of the best block.
+	fn best_hash(&self) -> B::Hash;
+	/// `vec_set::Members` at block `at`.
+	fn members(&self, at: B::Hash) -> Vec<AccountId>;
+}
+
+impl<B, C> MemberSource<B> for C where
+	B: BlockT,
+	C: ProvideRuntimeApi + HeaderBackend<B> + Send + Sync,
+	C::Api: DemoApi<B>,
+{
+	fn best_hash(&self) -> B::Hash {
+		self.info().best_hash
+	}
+
+	fn members(&self, at: B::Hash) -> Vec<AccountId> {
+		self.runtime_api()
+			.members(&BlockId::Hash(at))
+			.unwrap_or_else(|e| {
+				warn!("Failed to read members at {}: {:?}", at, e);
+				Vec::new()
+			})
+	}
+}
+
+/// Validates gossiped announcements against the on-chain `Members` set.
+pub struct GossipValidator<B: BlockT, C> {
+	client: Arc<C>,
+	/// Members at the given best block, refreshed when the best block changes.
+	members: RwLock<(Option<B::Hash>, Vec<AccountId>)>,
+	/// Start of the current rate limit period and messages received in it, per peer.
+	peers: Mutex<HashMap<PeerId, (Instant, u32)>>,
+}
+
+impl<B: BlockT, C: MemberSource<B>> GossipValidator<B, C> {
+	/// Create a validator reading members through `client`.
+	pub fn new(client: Arc<C>) -> Self {
+		GossipValidator {
+			client,
+			members: RwLock::new((None, Vec::new())),
+			peers: Mutex::new(HashMap::new()),
+		}
+	}
+
+	fn is_member(&self, who: &AccountId) -> bool {
+		let best = self.client.best_hash();
+		{
+			let members = self.members.read();
+			if members.0 == Some(best) {
+				return members.1.contains(who);
+			}
+		}
+
+		let members = self.client.members(best);
+		let is_member = members.contains(who);
+		*self.members.write() = (Some(best), members);
+
+		is_member
+	}
+
+	fn within_rate_limit(&self, who: &PeerId) -> bool {
+		let now = Instant::now();
+		let mut peers = self.peers.lock();
+		let (since, count) = peers.entry(who.clone()).or_insert((now, 0));
+		if now.duration_since(*since) >= RATE_LIMIT_PERIOD {
+			*since = now;
+			*count = 0;
+		}
+		*count += 1;
+
+		*count <= RATE_LIMIT_MESSAGES
+	}
+}
+
+impl<B: BlockT, C: MemberSource<B>> CheckAnnouncement for GossipValidator<B, C> {
+	fn check(&self, announcement: &Announcement) -> Result<(), &'static str> {
+		let now = now_ms();
+		if announcement.message.len() > MAX_MESSAGE_LEN {
+			return Err("announcement is too long");
+		}
+		if announcement.is_expired(now) {
+			return Err("announcement has expired");
+		}
+		if announcement.timestamp > now.saturating_add(MAX_CLOCK_DRIFT_MS) {
+			return Err("announcement is timestamped in the future");
+		}
+		if !announcement.verify() {
+			return Err("bad announcement signature");
+		}
+		if !self.is_member(&announcement.who) {
+			return Err("announcer is not a member");
+		}
+
+		Ok(())
+	}
+}
+
+impl<B: BlockT, C: MemberSource<B>> Validator<B> for GossipValidator<B, C> {
+	fn peer_disconnected(&self, _context: &mut dyn ValidatorContext<B>, who: &PeerId) {
+		self.peers.lock().remove(who);
+	}
+
+	fn validate(
+		&self,
+		_context: &mut dyn ValidatorContext<B>,
+		sender: &PeerId,
+		data: &[u8],
+	) -> ValidationResult<B::Hash> {
+		if !self.within_rate_limit(sender) {
+			debug!(target: "gossip", "Peer {} exceeded the announcement rate limit", sender);
+			return ValidationResult::Discard;
+		}
+
+		let checked = Announcement::decode(&mut &data[..])
+			.map_err(|_| "malformed announcement")
+			.and_then(|announcement| self.check(&announcement));
+		match checked {
+			Ok(()) => ValidationResult::ProcessAndKeep(topic::<B>()),
+			Err(e) => {
+				debug!(target: "gossip", "Discarding announcement from {}: {}", sender, e);
+				ValidationResult::Discard
+			},
+		}
+	}
+
+	fn message_expired<'a>(&'a self) -> Box<dyn FnMut(B::Hash, &[u8]) -> bool + 'a> {
+		let now = now_ms();
+		Box::new(move |_topic, data| {
+			Announcement::decode(&mut &data[..]).map(|a| a.is_expired(now)).unwrap_or(true)
+		})
+	}
+}
+
+/// Locally submitted announcements and subscribers to received ones.
+pub struct Announcements {
+	check: Arc<dyn CheckAnnouncement>,
+	outgoing: mpsc::UnboundedSender<Announcement>,
+	subscribers: Mutex<Vec<mpsc::UnboundedSender<Announcement>>>,
+}
+
+impl Announcements {
+	/// Create the hub. The returned receiver yields announcements to gossip, see `run`.
+	pub fn new(check: Arc<dyn CheckAnnouncement>) -> (Arc<Self>, mpsc::UnboundedReceiver<Announcement>) {
+		let (outgoing, outgoing_rx) = mpsc::unbounded();
+		let announcements = Announcements {
+			check,
+			outgoing,
+			subscribers: Mutex::new(Vec::new()),
+		};
+
+		(Arc::new(announcements), outgoing_rx)
+	}
+
+	/// Check a local announcement, deliver it to subscribers and gossip it.
+	pub fn submit(&self, announcement: Announcement) -> Result<(), &'static str> {
+		self.check.check(&announcement)?;
+		self.notify(&announcement);
+		self.outgoing.unbounded_send(announcement).map_err(|_| "announcement gossip is not running")
+	}
+
+	/// Stream of announcements accepted from now on.
+	pub fn subscribe(&self) -> mpsc::UnboundedReceiver<Announcement> {
+		let (sink, stream) = mpsc::unbounded();
+		self.subscribers.lock().push(sink);
+		stream
+	}
+
+	fn notify(&self, announcement: &Announcement) {
+		self.subscribers.lock().retain(|sink| sink.unbounded_send(announcement.clone()).is_ok());
+	}
+}
+
+/// Deliver announcements received from peers and gossip the ones submitted locally.
+pub fn run<B: BlockT>(
+	engine: GossipEngine<B>,
+	announcements: Arc<Announcements>,
+	outgoing: mpsc::UnboundedReceiver<Announcement>,
+) -> impl Future<Output = ()> {
+	let topic = topic::<B>();
+
+	let incoming = engine.messages_for(topic).for_each(move |notification| {
+		if let Ok(announcement) = Announcement::decode(&mut &notification.message[..]) {
+			announcements.notify(&announcement);
+		}
+		ready(())
+	});
+
+	let outgoing = outgoing.for_each(move |announcement| {
+		engine.gossip_message(topic, announcement.encode(), false);
+		ready(())
+	});
+
+	join(incoming, outgoing).map(drop)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{Pair, H256};
+	use sp_keyring::AccountKeyring;
+	use substrate_demo_runtime::opaque::Block;
+
+	/// Members that never change.
+	struct Members(Vec<AccountId>);
+
+	impl MemberSource<Block> for Members {
+		fn best_hash(&self) -> H256 {
+			H256::zero()
+		}
+
+		fn members(&self, _at: H256) -> Vec<AccountId> {
+			self.0.clone()
+		}
+	}
+
+	struct NoContext;
+
+	impl ValidatorContext<Block> for NoContext {
+		fn broadcast_topic(&mut self, _topic: H256, _force: bool) {}
+		fn broadcast_message(&mut self, _topic: H256, _message: Vec<u8>, _force: bool) {}
+		fn send_message(&mut self, _who: &PeerId, _message: Vec<u8>) {}
+		fn send_topic(&mut self, _who: &PeerId, _topic: H256, _force: bool) {}
+	}
+
+	fn validator() -> GossipValidator<Block, Members> {
+		GossipValidator::new(Arc::new(Members(vec![AccountKeyring::Alice.to_account_id()])))
+	}
+
+	fn announcement(signer: AccountKeyring, who: AccountKeyring, message: &[u8]) -> Vec<u8> {
+		let (who, timestamp) = (who.to_account_id(), now_ms());
+		let payload = Announcement::signing_payload(&who, timestamp, message);
+		let signature = signer.pair().sign(&payload).into();
+
+		Announcement { who, timestamp, message: message.to_vec(), signature }.encode()
+	}
+
+	fn kept(validator: &GossipValidator<Block, Members>, sender: &PeerId, data: &[u8]) -> bool {
+		match validator.validate(&mut NoContext, sender, data) {
+			ValidationResult::ProcessAndKeep(topic) => topic == super::topic::<Block>(),
+			_ => false,
+		}
+	}
+
+	#[test]
+	fn members_announcements_are_kept() {
+		let validator = validator();
+		let alice = announcement(AccountKeyring::Alice, AccountKeyring::Alice, b"hello");
+
+		assert!(kept(&validator, &PeerId::random(), &alice));
+	}
+
+	#[test]
+	fn non_members_announcements_are_discarded() {
+		let validator = validator();
+		let bob = announcement(AccountKeyring::Bob, AccountKeyring::Bob, b"hello");
+
+		assert!(!kept(&validator, &PeerId::random(), &bob));
+		assert_eq!(validator.check(&Announcement::decode(&mut &bob[..]).unwrap()), Err("announcer is not a member"));
+	}
+
+	#[test]
+	fn badly_signed_announcements_are_discarded() {
+		let validator = validator();
+		// Bob signs in Alice's name
+		let forged = announcement(AccountKeyring::Bob, AccountKeyring::Alice, b"hello");
+		let signed = announcement(AccountKeyring::Alice, AccountKeyring::Alice, b"hello");
+		let mut tampered = Announcement::decode(&mut &signed[..]).unwrap();
+		tampered.message = b"goodbye".to_vec();
+
+		assert!(!kept(&validator, &PeerId::random(), &forged));
+		assert!(!kept(&validator, &PeerId::random(), &tampered.encode()));
+		assert_eq!(validator.check(&tampered), Err("bad announcement signature"));
+	}
+
+	#[test]
+	fn peers_over_the_rate_limit_are_discarded() {
+		let validator = validator();
+		let alice = announcement(AccountKeyring::Alice, AccountKeyring::Alice, b"hello");
+		let (flooding, other) = (PeerId::random(), PeerId::random());
+
+		for _ in 0..RATE_LIMIT_MESSAGES {
+			assert!(kept(&validator, &flooding, &alice));
+		}
+		assert!(!kept(&validator, &flooding, &alice));
+		assert!(kept(&validator, &other, &alice));
+
+		// the count starts over once the peer reconnects
+		validator.peer_disconnected(&mut NoContext, &flooding);
+		assert!(kept(&validator, &flooding, &alice));
+	}
+}
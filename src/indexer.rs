@@ -0,0 +1,487 @@
+//! Indexer writing the demo modules' events into a local SQLite database.
+//!
+//! Only blocks on the best chain are indexed. When the best chain changes, the rows of the
+//! retracted blocks are deleted; rows become final once their block is finalized.
+
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
+use futures::{future::ready, stream, Future, StreamExt};
+use log::{info, warn};
+use rusqlite::{Connection, OptionalExtension, NO_PARAMS, params};
+use sc_client_api::BlockchainEvents;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::{HeaderBackend, HeaderMetadata, tree_route};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, SaturatedConversion},
+};
+use substrate_demo_runtime::{
+	AccountId, DemoApi, Event, EventRecord, Hash, Phase,
+	linked_map, simple_event, simple_map, single_value, vec_set,
+};
+
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS blocks (
+		block_hash BLOB PRIMARY KEY,
+		block_number INTEGER NOT NULL,
+		finalized INTEGER NOT NULL DEFAULT 0
+	);
+	CREATE TABLE IF NOT EXISTS events (
+		block_hash BLOB NOT NULL REFERENCES blocks (block_hash),
+		block_number INTEGER NOT NULL,
+		event_index INTEGER NOT NULL,
+		extrinsic_index INTEGER,
+		module TEXT NOT NULL,
+		name TEXT NOT NULL,
+		account BLOB,
+		data TEXT NOT NULL,
+		PRIMARY KEY (block_hash, event_index)
+	);
+	CREATE INDEX IF NOT EXISTS events_by_number ON events (block_number);
+	CREATE INDEX IF NOT EXISTS events_by_account ON events (account);
+	CREATE TABLE IF NOT EXISTS progress (
+		id INTEGER PRIMARY KEY CHECK (id = 0),
+		finalized_number INTEGER NOT NULL
+	);
+";
+
+/// A demo module event, flattened into the columns of the `events` table.
+struct Row {
+	module: &'static str,
+	name: &'static str,
+	account: Option<AccountId>,
+	data: String,
+}
+
+impl Row {
+	fn new(module: &'static str, name: &'static str, account: Option<AccountId>, data: String) -> Self {
+		Row { module, name, account, data }
+	}
+}
+
+/// Flatten a runtime event. Events of modules other than the demo ones are not indexed.
+fn flatten(event: Event) -> Option<Row> {
+	use simple_event::RawEvent as SimpleEvent;
+	use single_value::RawEvent as SingleValue;
+	use vec_set::RawEvent as VecSet;
+	use simple_map::RawEvent as SimpleMap;
+	use linked_map::RawEvent as LinkedMap;
+
+	const SIMPLE_EVENT: &str = "SimpleEventModule";
+	const SINGLE_VALUE: &str = "SingleValueModule";
+	const VEC_SET: &str = "VecValueModule";
+	const SIMPLE_MAP: &str = "SimpleMapModule";
+	const LINKED_MAP: &str = "LinkedMapModule";
+
+	Some(match event {
+		Event::simple_event(SimpleEvent::EmitInput(value, who)) =>
+			Row::new(SIMPLE_EVENT, "EmitInput", Some(who), format!(r#"{{"value":{}}}"#, value)),
+		Event::single_value(SingleValue::ValueSet(value, at)) =>
+			Row::new(SINGLE_VALUE, "ValueSet", None, format!(r#"{{"value":{},"at":{}}}"#, value, at)),
+		Event::single_value(SingleValue::ValueGet(value, at)) =>
+			Row::new(SINGLE_VALUE, "ValueGet", None, format!(r#"{{"value":{},"at":{}}}"#, value, at)),
+		Event::single_value(SingleValue::AccountSet(who, at)) =>
+			Row::new(SINGLE_VALUE, "AccountSet", Some(who), format!(r#"{{"at":{}}}"#, at)),
+		Event::single_value(SingleValue::AccountGet(who, at)) =>
+			Row::new(SINGLE_VALUE, "AccountGet", Some(who), format!(r#"{{"at":{}}}"#, at)),
+		Event::vec_set(VecSet::MemberAdded(who)) =>
+			Row::new(VEC_SET, "MemberAdded", Some(who), "{}".into()),
+		Event::vec_set(VecSet::MemberRemoved(who)) =>
+			Row::new(VEC_SET, "MemberRemoved", Some(who), "{}".into()),
+		Event::simple_map(SimpleMap::EntrySet(who, entry, version)) => Row::new(
+			SIMPLE_MAP, "EntrySet", Some(who), format!(r#"{{"entry":{},"version":{}}}"#, entry, version),
+		),
+		Event::simple_map(SimpleMap::EntryGot(who, entry, version)) => Row::new(
+			SIMPLE_MAP, "EntryGot", Some(who), format!(r#"{{"entry":{},"version":{}}}"#, entry, version),
+		),
+		Event::simple_map(SimpleMap::EntryTook(who, entry)) =>
+			Row::new(SIMPLE_MAP, "EntryTook", Some(who), format!(r#"{{"entry":{}}}"#, entry)),
+		Event::simple_map(SimpleMap::IncreaseEntry(old, new, version)) => Row::new(
+			SIMPLE_MAP, "IncreaseEntry", None, format!(r#"{{"old":{},"new":{},"version":{}}}"#, old, new, version),
+		),
+		Event::simple_map(SimpleMap::CAS(old, new, version)) => Row::new(
+			SIMPLE_MAP, "CAS", None, format!(r#"{{"old":{},"new":{},"version":{}}}"#, old, new, version),
+		),
+		Event::simple_map(SimpleMap::EntryExpired(who, entry)) =>
+			Row::new(SIMPLE_MAP, "EntryExpired", Some(who), format!(r#"{{"entry":{}}}"#, entry)),
+		Event::simple_map(SimpleMap::EntryRenewed(who, expires_at)) => Row::new(
+			SIMPLE_MAP,
+			"EntryRenewed",
+			Some(who),
+			format!(r#"{{"expiresAt":{}}}"#, expires_at.map_or("null".into(), |at| at.to_string())),
+		),
+		Event::simple_map(SimpleMap::Approved(owner, delegate, permissions)) => Row::new(
+			SIMPLE_MAP,
+			"Approved",
+			Some(owner),
+			format!(
+				r#"{{"delegate":"{}","increase":{},"compareAndSwap":{}}}"#,
+				delegate, permissions.increase, permissions.compare_and_swap,
+			),
+		),
+		Event::simple_map(SimpleMap::Revoked(owner, delegate)) =>
+			Row::new(SIMPLE_MAP, "Revoked", Some(owner), format!(r#"{{"delegate":"{}"}}"#, delegate)),
+		Event::linked_map(LinkedMap::MemberAdded(who, index)) =>
+			Row::new(LINKED_MAP, "MemberAdded", Some(who), format!(r#"{{"index":{}}}"#, index)),
+		Event::linked_map(LinkedMap::MemberRemoved(who, index)) =>
+			Row::new(LINKED_MAP, "MemberRemoved", Some(who), format!(r#"{{"index":{}}}"#, index)),
+		_ => return None,
+	})
+}
+
+/// The indexer's SQLite database.
+struct Database {
+	conn: Connection,
+}
+
+impl Database {
+	fn open(path: &Path) -> Result<Self, String> {
+		let conn = Connection::open(path)
+			.map_err(|e| format!("Failed to open indexer database {}: {}", path.display(), e))?;
+		Self::with_schema(conn)
+	}
+
+	#[cfg(test)]
+	fn open_in_memory() -> Result<Self, String> {
+		Self::with_schema(Connection::open_in_memory().map_err(|e| e.to_string())?)
+	}
+
+	fn with_schema(conn: Connection) -> Result<Self, String> {
+		conn.execute_batch(SCHEMA).map_err(|e| format!("Failed to create indexer schema: {}", e))?;
+		Ok(Database { conn })
+	}
+
+	/// The number of the last finalized block indexed, if any.
+	fn progress(&self) -> Result<Option<u64>, String> {
+		self.conn
+			.query_row("SELECT finalized_number FROM progress WHERE id = 0", NO_PARAMS, |row| row.get::<_, i64>(0))
+			.optional()
+			.map(|number| number.map(|n| n as u64))
+			.map_err(|e| e.to_string())
+	}
+
+	fn set_progress(&self, finalized_number: u64) -> Result<(), String> {
+		self.conn
+			.execute(
+				"INSERT OR REPLACE INTO progress (id, finalized_number) VALUES (0, ?1)",
+				params![finalized_number as i64],
+			)
+			.map(drop)
+			.map_err(|e| e.to_string())
+	}
+
+	/// Write the block and the rows of the demo events among its `records`.
+	fn insert_block(
+		&mut self,
+		hash: Hash,
+		number: u64,
+		finalized: bool,
+		records: Vec<EventRecord<Event, Hash>>,
+	) -> Result<(), String> {
+		let number = number as i64;
+		let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+		tx.execute(
+			"INSERT OR REPLACE INTO blocks (block_hash, block_number, finalized) VALUES (?1, ?2, ?3)",
+			params![hash.as_bytes(), number, finalized],
+		).map_err(|e| e.to_string())?;
+
+		for (event_index, record) in records.into_iter().enumerate() {
+			let extrinsic_index = match record.phase {
+				Phase::ApplyExtrinsic(index) => Some(index),
+				_ => None,
+			};
+			let row = match flatten(record.event) {
+				Some(row) => row,
+				None => continue,
+			};
+			tx.execute(
+				"INSERT OR REPLACE INTO events
+					(block_hash, block_number, event_index, extrinsic_index, module, name, account, data)
+					VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+				params![
+					hash.as_bytes(),
+					number,
+					event_index as i64,
+					extrinsic_index,
+					row.module,
+					row.name,
+					row.account.as_ref().map(|a| AsRef::<[u8]>::as_ref(a)),
+					row.data,
+				],
+			).map_err(|e| e.to_string())?;
+		}
+
+		tx.commit().map_err(|e| e.to_string())
+	}
+
+	/// Delete the block and its rows, unless it is finalized.
+	fn retract_block(&mut self, hash: Hash) -> Result<(), String> {
+		let tx = self.conn.transaction().map_err(|e| e.to_string())?;
+		let retracted = tx
+			.execute("DELETE FROM blocks WHERE block_hash = ?1 AND finalized = 0", params![hash.as_bytes()])
+			.map_err(|e| e.to_string())?;
+		if retracted > 0 {
+			tx.execute("DELETE FROM events WHERE block_hash = ?1", params![hash.as_bytes()])
+				.map_err(|e| e.to_string())?;
+		}
+
+		tx.commit().map_err(|e| e.to_string())
+	}
+
+	/// Mark the pending blocks up to `number` that `canonical` returns the hash of as finalized,
+	/// and retract the others.
+	fn finalize(
+		&mut self,
+		number: u64,
+		canonical: impl Fn(u64) -> Result<Option<Hash>, String>,
+	) -> Result<(), String> {
+		let pending = {
+			let mut stmt = self.conn
+				.prepare("SELECT block_hash, block_number FROM blocks WHERE finalized = 0 AND block_number <= ?1")
+				.map_err(|e| e.to_string())?;
+			let rows = stmt
+				.query_map(params![number as i64], |row| {
+					Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, i64>(1)?))
+				})
+				.map_err(|e| e.to_string())?;
+			rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
+		};
+
+		for (hash, pending_number) in pending {
+			let hash = Hash::from_slice(&hash);
+			if canonical(pending_number as u64)? == Some(hash) {
+				self.conn
+					.execute("UPDATE blocks SET finalized = 1 WHERE block_hash = ?1", params![hash.as_bytes()])
+					.map_err(|e| e.to_string())?;
+			} else {
+				self.retract_block(hash)?;
+			}
+		}
+
+		self.set_progress(number)
+	}
+}
+
+enum Notification<B: BlockT> {
+	Imported { hash: B::Hash, is_new_best: bool },
+	Finalized(B::Header),
+}
+
+struct Indexer<B, C> {
+	client: Arc<C>,
+	db: Database,
+	best: Option<Hash>,
+	_phantom: PhantomData<B>,
+}
+
+impl<B, C> Indexer<B, C> where
+	B: BlockT<Hash = Hash>,
+	C: ProvideRuntimeApi + HeaderBackend<B> + HeaderMetadata<B, Error = sp_blockchain::Error>,
+	C::Api: DemoApi<B>,
+{
+	fn open(client: Arc<C>, path: &Path) -> Result<Self, String> {
+		Ok(Indexer { client, db: Database::open(path)?, best: None, _phantom: PhantomData })
+	}
+
+	/// Index the canonical blocks imported since the last finalized block we indexed.
+	fn catch_up(&mut self) -> Result<(), String> {
+		let info = self.client.info();
+		let from = self.db.progress()?.map(|n| n + 1).unwrap_or(1);
+		let best = info.best_number.saturated_into::<u64>();
+		let finalized = info.finalized_number.saturated_into::<u64>();
+
+		if from <= best {
+			info!("Indexing demo events of blocks #{} to #{}", from, best);
+		}
+		for number in from..=best {
+			let number: NumberFor<B> = number.saturated_into();
+			let hash = self.client.hash(number)
+				.map_err(|e| e.to_string())?
+				.ok_or_else(|| format!("Canonical block #{} not found", number))?;
+			self.index_block(hash, number, number <= info.finalized_number)?;
+		}
+		self.db.set_progress(finalized)?;
+		self.best = Some(info.best_hash);
+
+		Ok(())
+	}
+
+	fn handle(&mut self, notification: Notification<B>) -> Result<(), String> {
+		match notification {
+			Notification::Imported { hash, is_new_best: true } => self.on_new_best(hash),
+			Notification::Imported { .. } => Ok(()),
+			Notification::Finalized(header) => self.on_finalized(*header.number()),
+		}
+	}
+
+	fn on_new_best(&mut self, hash: Hash) -> Result<(), String> {
+		let best = match self.best.replace(hash) {
+			Some(best) => best,
+			None => {
+				let header = self.client.header(BlockId::Hash(hash))
+					.map_err(|e| e.to_string())?
+					.ok_or_else(|| format!("Header of {} not found", hash))?;
+				return self.index_block(hash, *header.number(), false);
+			},
+		};
+
+		let route = tree_route(&*self.client, best, hash).map_err(|e| e.to_string())?;
+		for block in route.retracted() {
+			self.db.retract_block(block.hash)?;
+		}
+		for block in route.enacted() {
+			self.index_block(block.hash, block.number, false)?;
+		}
+
+		Ok(())
+	}
+
+	fn on_finalized(&mut self, number: NumberFor<B>) -> Result<(), String> {
+		let client = &self.client;
+		self.db.finalize(number.saturated_into(), |number| {
+			client.hash(number.saturated_into()).map_err(|e| e.to_string())
+		})
+	}
+
+	fn index_block(&mut self, hash: Hash, number: NumberFor<B>, finalized: bool) -> Result<(), String> {
+		let records = self.client.runtime_api()
+			.block_events(&BlockId::Hash(hash))
+			.map_err(|e| format!("Failed to read events of {}: {:?}", hash, e))?;
+		self.db.insert_block(hash, number.saturated_into(), finalized, records)
+	}
+}
+
+/// Open the database at `path`, index the blocks missed since the last run and return a future
+/// that keeps indexing imported and finalized blocks.
+pub fn run<B, C>(client: Arc<C>, path: &Path) -> Result<impl Future<Output = ()>, String> where
+	B: BlockT<Hash = Hash>,
+	C: ProvideRuntimeApi + HeaderBackend<B> + HeaderMetadata<B, Error = sp_blockchain::Error>
+		+ BlockchainEvents<B> + Send + Sync + 'static,
+	C::Api: DemoApi<B>,
+{
+	let mut indexer = Indexer::open(client.clone(), path)?;
+	indexer.catch_up()?;
+
+	let imported = client.import_notification_stream()
+		.map(|n| Notification::Imported { hash: n.hash, is_new_best: n.is_new_best });
+	let finalized = client.finality_notification_stream()
+		.map(|n| Notification::Finalized(n.header));
+
+	Ok(stream::select(imported, finalized).for_each(move |notification| {
+		if let Err(e) = indexer.handle(notification) {
+			warn!("Event indexer: {}", e);
+		}
+		ready(())
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::sr25519;
+	use crate::chain_spec::get_account_id_from_seed;
+
+	fn alice() -> AccountId {
+		get_account_id_from_seed::<sr25519::Public>("Alice")
+	}
+
+	fn record(extrinsic: u32, event: Event) -> EventRecord<Event, Hash> {
+		EventRecord { phase: Phase::ApplyExtrinsic(extrinsic), event, topics: vec![] }
+	}
+
+	fn member_added() -> EventRecord<Event, Hash> {
+		record(0, Event::vec_set(vec_set::RawEvent::MemberAdded(alice())))
+	}
+
+	/// The indexed rows as `(block number, event index, extrinsic index, name, data)`.
+	fn rows(db: &Database) -> Vec<(i64, i64, Option<u32>, String, String)> {
+		let mut stmt = db.conn
+			.prepare(
+				"SELECT block_number, event_index, extrinsic_index, name, data FROM events
+					ORDER BY block_number, event_index",
+			)
+			.unwrap();
+		let rows = stmt
+			.query_map(NO_PARAMS, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+			.unwrap();
+		rows.collect::<Result<_, _>>().unwrap()
+	}
+
+	/// The indexed blocks as `(hash, finalized)`, by number.
+	fn blocks(db: &Database) -> Vec<(Hash, bool)> {
+		let mut stmt = db.conn.prepare("SELECT block_hash, finalized FROM blocks ORDER BY block_number").unwrap();
+		let rows = stmt
+			.query_map(NO_PARAMS, |row| Ok((Hash::from_slice(&row.get::<_, Vec<u8>>(0)?), row.get(1)?)))
+			.unwrap();
+		rows.collect::<Result<_, _>>().unwrap()
+	}
+
+	#[test]
+	fn demo_events_are_indexed() {
+		let mut db = Database::open_in_memory().unwrap();
+		let records = vec![
+			member_added(),
+			record(0, Event::system(system::Event::ExtrinsicSuccess(Default::default()))),
+			record(1, Event::simple_map(simple_map::RawEvent::EntrySet(alice(), 5, 1))),
+		];
+
+		db.insert_block(Hash::repeat_byte(1), 1, false, records).unwrap();
+
+		assert_eq!(rows(&db), vec![
+			(1, 0, Some(0), "MemberAdded".into(), "{}".into()),
+			(1, 2, Some(1), "EntrySet".into(), r#"{"entry":5,"version":1}"#.into()),
+		]);
+		let account: Vec<u8> = db.conn
+			.query_row("SELECT account FROM events WHERE event_index = 0", NO_PARAMS, |row| row.get(0))
+			.unwrap();
+		assert_eq!(&account[..], AsRef::<[u8]>::as_ref(&alice()));
+	}
+
+	#[test]
+	fn retracted_blocks_lose_their_rows() {
+		let mut db = Database::open_in_memory().unwrap();
+		let (a, b, c) = (Hash::repeat_byte(1), Hash::repeat_byte(2), Hash::repeat_byte(3));
+		db.insert_block(a, 1, false, vec![member_added()]).unwrap();
+		db.insert_block(b, 2, false, vec![member_added()]).unwrap();
+
+		// a reorg replaces `b` with `c`
+		db.retract_block(b).unwrap();
+		db.insert_block(c, 2, false, vec![record(3, Event::single_value(single_value::RawEvent::ValueSet(7, 2)))])
+			.unwrap();
+
+		assert_eq!(blocks(&db), vec![(a, false), (c, false)]);
+		assert_eq!(rows(&db), vec![
+			(1, 0, Some(0), "MemberAdded".into(), "{}".into()),
+			(2, 0, Some(3), "ValueSet".into(), r#"{"value":7,"at":2}"#.into()),
+		]);
+	}
+
+	#[test]
+	fn finality_keeps_canonical_blocks_and_retracts_the_rest() {
+		let mut db = Database::open_in_memory().unwrap();
+		let (a, b, c, d) = (Hash::repeat_byte(1), Hash::repeat_byte(2), Hash::repeat_byte(3), Hash::repeat_byte(4));
+		db.insert_block(a, 1, false, vec![member_added()]).unwrap();
+		db.insert_block(b, 2, false, vec![member_added()]).unwrap();
+		db.insert_block(c, 2, false, vec![member_added()]).unwrap();
+		db.insert_block(d, 3, false, vec![member_added()]).unwrap();
+		assert_eq!(db.progress().unwrap(), None);
+
+		// `c` was finalized, while `b` lost the fork before it was retracted
+		db.finalize(2, |number| Ok(match number {
+			1 => Some(a),
+			2 => Some(c),
+			_ => None,
+		})).unwrap();
+
+		assert_eq!(blocks(&db), vec![(a, true), (c, true), (d, false)]);
+		assert_eq!(rows(&db).len(), 3);
+		assert_eq!(db.progress().unwrap(), Some(2));
+
+		// finalized blocks are never retracted
+		db.retract_block(c).unwrap();
+		db.retract_block(d).unwrap();
+		assert_eq!(blocks(&db), vec![(a, true), (c, true)]);
+		assert_eq!(rows(&db).len(), 2);
+	}
+}
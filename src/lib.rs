@@ -0,0 +1,20 @@
+//! Substrate Node Template CLI library.
+
+#![warn(missing_docs)]
+#![warn(unused_extern_crates)]
+
+pub mod chain_spec;
+#[macro_use]
+pub mod service;
+pub mod cli;
+mod export_state;
+mod gossip;
+mod indexer;
+mod metrics;
+mod proof;
+mod rpc;
+mod runtime_upgrade;
+pub mod shutdown;
+mod testnet;
+
+pub use sc_cli::{VersionInfo, IntoExit, error};
@@ -1,16 +1,11 @@
-//! Substrate Node Template CLI library.
+//! Substrate Node Template CLI.
 
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
 
-mod chain_spec;
-#[macro_use]
-mod service;
-mod cli;
+use substrate_demo::{cli, shutdown, VersionInfo};
 
-pub use sc_cli::{VersionInfo, IntoExit, error};
-
-fn main() -> Result<(), cli::error::Error> {
+fn main() {
 	let version = VersionInfo {
 		name: "Substrate Node",
 		commit: env!("VERGEN_SHA_SHORT"),
@@ -21,5 +16,13 @@ fn main() -> Result<(), cli::error::Error> {
 		support_url: "support.anonymous.an",
 	};
 
-	cli::run(std::env::args(), cli::Exit, version)
+	let code = match cli::run(std::env::args(), cli::Exit, version) {
+		Ok(()) => shutdown::EXIT_CLEAN,
+		Err(e) => {
+			eprintln!("Error: {:?}", e);
+			shutdown::EXIT_ERROR
+		},
+	};
+
+	std::process::exit(code);
 }
@@ -0,0 +1,163 @@
+//! Prometheus metrics for the node.
+//!
+//! Metrics live in the default `prometheus` registry and are served as plain text on
+//! `/metrics` when the node is started with `--prometheus-port`.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use futures::{future::ready, compat::Future01CompatExt, Future, FutureExt, Stream, StreamExt};
+use futures01::Future as Future01;
+use hyper::{Body, Request, Response, StatusCode, header::CONTENT_TYPE, service::service_fn_ok};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use prometheus::{
+	Encoder, Histogram, IntGauge, TextEncoder, register_histogram, register_int_gauge,
+};
+use sc_client_api::BlockchainEvents;
+use sc_network::{NetworkStatus, network_state::NetworkState};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::{BlockCheckParams, BlockImport, BlockImportParams, ImportResult};
+use sp_consensus::import_queue::CacheKeyId;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, SaturatedConversion}};
+use sp_transaction_pool::TransactionPool;
+use substrate_demo_runtime::DemoApi;
+
+lazy_static! {
+	static ref BEST_BLOCK: IntGauge = register_int_gauge!(
+		"substrate_demo_block_height", "Height of the best block"
+	).expect("metric names are unique; qed");
+	static ref FINALIZED_BLOCK: IntGauge = register_int_gauge!(
+		"substrate_demo_finalized_height", "Height of the last finalized block"
+	).expect("metric names are unique; qed");
+	static ref TX_POOL_READY: IntGauge = register_int_gauge!(
+		"substrate_demo_tx_pool_ready", "Number of ready transactions in the pool"
+	).expect("metric names are unique; qed");
+	static ref TX_POOL_FUTURE: IntGauge = register_int_gauge!(
+		"substrate_demo_tx_pool_future", "Number of future transactions in the pool"
+	).expect("metric names are unique; qed");
+	static ref PEERS: IntGauge = register_int_gauge!(
+		"substrate_demo_peers", "Number of connected peers"
+	).expect("metric names are unique; qed");
+	static ref BLOCK_IMPORT_TIME: Histogram = register_histogram!(
+		"substrate_demo_block_import_seconds", "Time spent importing a block"
+	).expect("metric names are unique; qed");
+	static ref MEMBERS: IntGauge = register_int_gauge!(
+		"substrate_demo_vec_set_members", "Number of accounts in vec_set::Members"
+	).expect("metric names are unique; qed");
+	static ref THE_COUNTER: IntGauge = register_int_gauge!(
+		"substrate_demo_linked_map_counter", "Value of linked_map::TheCounter"
+	).expect("metric names are unique; qed");
+	static ref SIMPLE_MAP_ENTRIES: IntGauge = register_int_gauge!(
+		"substrate_demo_simple_map_entries", "Number of entries in simple_map::SimpleMap"
+	).expect("metric names are unique; qed");
+}
+
+/// Serve the default registry on `addr`.
+pub fn serve(addr: SocketAddr) -> Result<impl Future<Output = ()>, hyper::Error> {
+	let server = hyper::Server::try_bind(&addr)?
+		.serve(|| service_fn_ok(handle_request))
+		.map_err(|e| warn!("Prometheus endpoint failed: {}", e));
+
+	info!("Prometheus metrics served at http://{}/metrics", addr);
+
+	Ok(server.compat().map(drop))
+}
+
+fn handle_request(req: Request<Body>) -> Response<Body> {
+	if req.uri().path() != "/metrics" {
+		return Response::builder()
+			.status(StatusCode::NOT_FOUND)
+			.body(Body::empty())
+			.expect("static response is valid; qed");
+	}
+
+	let encoder = TextEncoder::new();
+	let mut buffer = Vec::new();
+	if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+		warn!("Failed to encode metrics: {}", e);
+	}
+
+	Response::builder()
+		.header(CONTENT_TYPE, encoder.format_type())
+		.body(Body::from(buffer))
+		.expect("static response is valid; qed")
+}
+
+/// Refresh chain, pool and demo module gauges on every imported block.
+pub fn refresh_chain_gauges<B, C, P>(client: Arc<C>, pool: Arc<P>) -> impl Future<Output = ()>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi + HeaderBackend<B> + BlockchainEvents<B> + Send + Sync + 'static,
+	C::Api: DemoApi<B>,
+	P: TransactionPool + 'static,
+{
+	client.import_notification_stream().for_each(move |notification| {
+		let info = client.info();
+		BEST_BLOCK.set(info.best_number.saturated_into::<u64>() as i64);
+		FINALIZED_BLOCK.set(info.finalized_number.saturated_into::<u64>() as i64);
+
+		let status = pool.status();
+		TX_POOL_READY.set(status.ready as i64);
+		TX_POOL_FUTURE.set(status.future as i64);
+
+		let at = BlockId::hash(notification.hash);
+		let api = client.runtime_api();
+		match (api.member_count(&at), api.the_counter(&at), api.simple_map_entries(&at)) {
+			(Ok(members), Ok(counter), Ok(entries)) => {
+				MEMBERS.set(members as i64);
+				THE_COUNTER.set(counter as i64);
+				SIMPLE_MAP_ENTRIES.set(entries as i64);
+			},
+			_ => warn!("Failed to query demo module state at {}", notification.hash),
+		}
+
+		ready(())
+	})
+}
+
+/// Refresh the peer count from the network status stream.
+pub fn refresh_peer_count<B, S>(status: S) -> impl Future<Output = ()>
+where
+	B: BlockT,
+	S: Stream<Item = (NetworkStatus<B>, NetworkState)>,
+{
+	status.for_each(|(status, _)| {
+		PEERS.set(status.num_connected_peers as i64);
+		ready(())
+	})
+}
+
+/// A `BlockImport` wrapper that records how long each import takes.
+pub struct MeteredBlockImport<I> {
+	inner: I,
+}
+
+impl<I> MeteredBlockImport<I> {
+	/// Wrap `inner`.
+	pub fn new(inner: I) -> Self {
+		MeteredBlockImport { inner }
+	}
+}
+
+impl<B: BlockT, I: BlockImport<B>> BlockImport<B> for MeteredBlockImport<I> {
+	type Error = I::Error;
+
+	fn check_block(&mut self, block: BlockCheckParams<B>) -> Result<ImportResult, Self::Error> {
+		self.inner.check_block(block)
+	}
+
+	fn import_block(
+		&mut self,
+		block: BlockImportParams<B>,
+		cache: HashMap<CacheKeyId, Vec<u8>>,
+	) -> Result<ImportResult, Self::Error> {
+		let start = Instant::now();
+		let result = self.inner.import_block(block, cache);
+		let elapsed = start.elapsed();
+		BLOCK_IMPORT_TIME.observe(elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9);
+		result
+	}
+}
@@ -0,0 +1,168 @@
+//! Storage read proofs of the demo modules' items.
+
+use std::collections::HashMap;
+use codec::Decode;
+use serde::{Deserialize, Serialize};
+use sp_core::{Blake2Hasher, Bytes};
+use sp_state_machine::{StorageProof, read_proof_check};
+use substrate_demo_runtime::{AccountId, Hash, storage_keys};
+
+/// A demo module storage item that can be proven.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DemoKey {
+	/// An account's entry in `simple_map::SimpleMap`.
+	SimpleMap(AccountId),
+	/// `vec_set::Members`.
+	Members,
+	/// `single_value::MyValue`.
+	MyValue,
+}
+
+impl DemoKey {
+	/// The storage key of this item.
+	pub fn storage_key(&self) -> Vec<u8> {
+		match self {
+			DemoKey::SimpleMap(account) => storage_keys::simple_map(account),
+			DemoKey::Members => storage_keys::members(),
+			DemoKey::MyValue => storage_keys::my_value(),
+		}
+	}
+
+	/// Decode the raw value of this item, `None` if it is not in storage.
+	pub fn decode_value(&self, raw: Option<&[u8]>) -> Result<DemoValue, String> {
+		fn decode<T: Decode>(raw: Option<&[u8]>) -> Result<Option<T>, String> {
+			raw.map(|mut raw| T::decode(&mut raw).map_err(|e| e.what().to_string())).transpose()
+		}
+
+		Ok(match self {
+			DemoKey::SimpleMap(_) => DemoValue::SimpleMap(decode(raw)?),
+			DemoKey::Members => DemoValue::Members(decode(raw)?.unwrap_or_default()),
+			DemoKey::MyValue => DemoValue::MyValue(decode(raw)?),
+		})
+	}
+}
+
+/// The decoded value of a `DemoKey`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DemoValue {
+	/// The entry, if the account has one.
+	SimpleMap(Option<u32>),
+	/// The members.
+	Members(Vec<AccountId>),
+	/// The value, if it was ever set.
+	MyValue(Option<u32>),
+}
+
+/// A read proof of demo storage items at a block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemoProof {
+	/// Hash of the block the proof was made at.
+	pub at: Hash,
+	/// Trie nodes proving the items.
+	pub proof: Vec<Bytes>,
+}
+
+/// Decode the values of `items` from the raw values read for their storage keys.
+pub fn decode_values(
+	items: &[DemoKey],
+	values: &HashMap<Vec<u8>, Option<Vec<u8>>>,
+) -> Result<Vec<DemoValue>, String> {
+	items.iter()
+		.map(|item| {
+			let raw = values.get(&item.storage_key())
+				.ok_or_else(|| format!("{:?} is not covered by the proof", item))?;
+			item.decode_value(raw.as_ref().map(|v| &v[..]))
+		})
+		.collect()
+}
+
+/// Check `proof` against `state_root` and decode the values of `items`.
+pub fn verify(state_root: Hash, proof: Vec<Bytes>, items: &[DemoKey]) -> Result<Vec<DemoValue>, String> {
+	let proof = StorageProof::new(proof.into_iter().map(|node| node.0).collect());
+	let keys = items.iter().map(DemoKey::storage_key).collect::<Vec<_>>();
+	let values = read_proof_check::<Blake2Hasher, _>(state_root, proof, &keys)
+		.map_err(|e| format!("Invalid storage proof: {}", e))?;
+
+	decode_values(items, &values)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use sp_core::sr25519;
+	use sp_state_machine::{Backend, InMemoryBackend, prove_read};
+	use crate::chain_spec::get_account_id_from_seed;
+
+	fn alice() -> AccountId {
+		get_account_id_from_seed::<sr25519::Public>("Alice")
+	}
+
+	fn bob() -> AccountId {
+		get_account_id_from_seed::<sr25519::Public>("Bob")
+	}
+
+	/// The state of a full node holding Alice's entry and membership, and `MyValue`.
+	fn full_state() -> InMemoryBackend<Blake2Hasher> {
+		let mut storage = HashMap::new();
+		storage.insert(storage_keys::simple_map(&alice()), 7u32.encode());
+		storage.insert(storage_keys::members(), vec![alice()].encode());
+		storage.insert(storage_keys::my_value(), 42u32.encode());
+		storage.into()
+	}
+
+	/// What the full node's `demo_storageProof` returns for `items`.
+	fn full_proof(state: &InMemoryBackend<Blake2Hasher>, items: &[DemoKey]) -> Vec<Bytes> {
+		let keys = items.iter().map(DemoKey::storage_key).collect::<Vec<_>>();
+		prove_read(state.clone(), &keys)
+			.expect("keys can be proven")
+			.iter_nodes()
+			.map(Bytes)
+			.collect()
+	}
+
+	#[test]
+	fn light_verifies_full_proof() {
+		let state = full_state();
+		let root = state.storage_root(std::iter::empty::<(Vec<u8>, Option<Vec<u8>>)>()).0;
+		let items = vec![
+			DemoKey::SimpleMap(alice()),
+			DemoKey::SimpleMap(bob()),
+			DemoKey::Members,
+			DemoKey::MyValue,
+		];
+
+		let proof = full_proof(&state, &items);
+
+		assert_eq!(
+			verify(root, proof, &items),
+			Ok(vec![
+				DemoValue::SimpleMap(Some(7)),
+				DemoValue::SimpleMap(None),
+				DemoValue::Members(vec![alice()]),
+				DemoValue::MyValue(Some(42)),
+			]),
+		);
+	}
+
+	#[test]
+	fn proof_against_other_root_is_rejected() {
+		let state = full_state();
+		let items = vec![DemoKey::MyValue];
+		let proof = full_proof(&state, &items);
+
+		assert!(verify(Hash::repeat_byte(1), proof, &items).is_err());
+	}
+
+	#[test]
+	fn items_missing_from_proof_are_rejected() {
+		let state = full_state();
+		let root = state.storage_root(std::iter::empty::<(Vec<u8>, Option<Vec<u8>>)>()).0;
+		let proof = full_proof(&state, &[DemoKey::MyValue]);
+
+		assert!(verify(root, proof, &[DemoKey::MyValue, DemoKey::Members]).is_err());
+	}
+}
@@ -0,0 +1,227 @@
+//! Node-specific RPC extensions.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use codec::Decode;
+use futures::{FutureExt, StreamExt, TryFutureExt, TryStreamExt};
+use futures01::{future, Future, Sink};
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode, Result as RpcResult};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use log::warn;
+use sc_client::{CallExecutor, Client};
+use sc_client_api::{backend::Backend, light::{Fetcher, RemoteReadRequest}};
+use sc_rpc_api::Subscriptions;
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Blake2Hasher, Bytes};
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT}};
+use substrate_demo_runtime::{AccountId, Hash};
+use crate::gossip::{Announcement, Announcements};
+use crate::proof::{DemoKey, DemoProof, DemoValue, decode_values, verify};
+
+/// The RPC extensions of a node.
+pub type IoHandler = jsonrpc_core::IoHandler<sc_rpc_api::Metadata>;
+
+fn error(code: i64, message: impl Into<String>) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(code),
+		message: message.into(),
+		data: None,
+	}
+}
+
+/// An announcement as returned to subscribers.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementInfo {
+	/// The announcing member.
+	pub who: AccountId,
+	/// Milliseconds since the UNIX epoch.
+	pub timestamp: u64,
+	/// The announced message.
+	pub message: Bytes,
+}
+
+impl From<Announcement> for AnnouncementInfo {
+	fn from(announcement: Announcement) -> Self {
+		AnnouncementInfo {
+			who: announcement.who,
+			timestamp: announcement.timestamp,
+			message: announcement.message.into(),
+		}
+	}
+}
+
+/// Submit and follow off-chain announcements of `vec_set` members.
+#[rpc]
+pub trait AnnounceApi {
+	/// RPC metadata.
+	type Metadata;
+
+	/// Submit a SCALE-encoded, signed `Announcement` to be gossiped.
+	#[rpc(name = "announce_submit")]
+	fn submit(&self, announcement: Bytes) -> RpcResult<()>;
+
+	/// Subscribe to announcements received from now on.
+	#[pubsub(subscription = "announce_messages", subscribe, name = "announce_subscribe")]
+	fn subscribe(&self, metadata: Self::Metadata, subscriber: Subscriber<AnnouncementInfo>);
+
+	/// Cancel a subscription to announcements.
+	#[pubsub(subscription = "announce_messages", unsubscribe, name = "announce_unsubscribe")]
+	fn unsubscribe(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool>;
+}
+
+/// Implementation of `AnnounceApi`.
+pub struct Announce {
+	announcements: Arc<Announcements>,
+	subscriptions: Subscriptions,
+}
+
+impl Announce {
+	/// Create the RPC handler.
+	pub fn new(announcements: Arc<Announcements>) -> Self {
+		Announce {
+			announcements,
+			// RPC calls are handled on the RPC server's event loop, so the forwarding
+			// tasks of subscriptions are spawned there.
+			subscriptions: Subscriptions::new(Arc::new(tokio::executor::DefaultExecutor::current())),
+		}
+	}
+}
+
+impl AnnounceApi for Announce {
+	type Metadata = sc_rpc_api::Metadata;
+
+	fn submit(&self, announcement: Bytes) -> RpcResult<()> {
+		let announcement = Announcement::decode(&mut &announcement[..])
+			.map_err(|e| error(1, format!("Malformed announcement: {}", e.what())))?;
+		self.announcements.submit(announcement).map_err(|e| error(2, e))
+	}
+
+	fn subscribe(&self, _metadata: Self::Metadata, subscriber: Subscriber<AnnouncementInfo>) {
+		let stream = self.announcements.subscribe()
+			.map(|announcement| Ok::<_, ()>(Ok(AnnouncementInfo::from(announcement))))
+			.compat();
+
+		self.subscriptions.add(subscriber, |sink| {
+			sink
+				.sink_map_err(|e| warn!("Error sending announcements: {:?}", e))
+				.send_all(stream)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
+
+/// Storage read proofs of the demo modules' items, served by full nodes.
+#[rpc]
+pub trait DemoProofApi {
+	/// Prove the values of `items` at block `at`, or at the best block.
+	#[rpc(name = "demo_storageProof")]
+	fn storage_proof(&self, items: Vec<DemoKey>, at: Option<Hash>) -> RpcResult<DemoProof>;
+}
+
+/// Implementation of `DemoProofApi`.
+pub struct FullDemoProof<B, E, Block: BlockT, RA> {
+	client: Arc<Client<B, E, Block, RA>>,
+}
+
+impl<B, E, Block: BlockT, RA> FullDemoProof<B, E, Block, RA> {
+	/// Create the RPC handler.
+	pub fn new(client: Arc<Client<B, E, Block, RA>>) -> Self {
+		FullDemoProof { client }
+	}
+}
+
+impl<B, E, Block, RA> DemoProofApi for FullDemoProof<B, E, Block, RA> where
+	Block: BlockT<Hash = Hash>,
+	B: Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+{
+	fn storage_proof(&self, items: Vec<DemoKey>, at: Option<Hash>) -> RpcResult<DemoProof> {
+		let at = at.unwrap_or_else(|| self.client.info().chain.best_hash);
+		let keys = items.iter().map(DemoKey::storage_key).collect::<Vec<_>>();
+		let proof = self.client.read_proof(&BlockId::Hash(at), &keys)
+			.map_err(|e| error(3, format!("Failed to prove storage at {}: {}", at, e)))?;
+
+		Ok(DemoProof {
+			at,
+			proof: proof.iter_nodes().map(Bytes).collect(),
+		})
+	}
+}
+
+/// Verified reads of the demo modules' items, served by light nodes.
+#[rpc]
+pub trait DemoLightApi {
+	/// Fetch `items` at the last finalized block from full nodes, verify them against the
+	/// finalized header and return the decoded values.
+	#[rpc(name = "demo_verifiedRead")]
+	fn verified_read(&self, items: Vec<DemoKey>) -> BoxFuture<Vec<DemoValue>>;
+
+	/// Verify a proof returned by a full node's `demo_storageProof` against the header of the
+	/// finalized block it was made at, and return the decoded values of `items`.
+	#[rpc(name = "demo_checkProof")]
+	fn check_proof(&self, items: Vec<DemoKey>, proof: DemoProof) -> RpcResult<Vec<DemoValue>>;
+}
+
+/// Implementation of `DemoLightApi`.
+pub struct LightDemoRead<Block, C, F> {
+	client: Arc<C>,
+	fetcher: Arc<F>,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block, C, F> LightDemoRead<Block, C, F> {
+	/// Create the RPC handler.
+	pub fn new(client: Arc<C>, fetcher: Arc<F>) -> Self {
+		LightDemoRead { client, fetcher, _phantom: PhantomData }
+	}
+}
+
+impl<Block, C, F> DemoLightApi for LightDemoRead<Block, C, F> where
+	Block: BlockT<Hash = Hash>,
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + Send + Sync + 'static,
+	F::RemoteReadResult: Send,
+{
+	fn verified_read(&self, items: Vec<DemoKey>) -> BoxFuture<Vec<DemoValue>> {
+		let finalized = self.client.info().finalized_hash;
+		let header = match self.client.header(BlockId::Hash(finalized)) {
+			Ok(Some(header)) => header,
+			Ok(None) => return Box::new(future::err(error(4, format!("Header of {} not found", finalized)))),
+			Err(e) => return Box::new(future::err(error(4, format!("Failed to read header: {}", e)))),
+		};
+
+		// the fetch checker verifies the proof against `header.state_root`
+		let request = RemoteReadRequest {
+			block: finalized,
+			header,
+			keys: items.iter().map(DemoKey::storage_key).collect(),
+			retry_count: None,
+		};
+		let read = self.fetcher.remote_read(request)
+			.map_err(|e| error(5, format!("Failed to fetch storage proof: {}", e)))
+			.map(move |values| values.and_then(|values| decode_values(&items, &values).map_err(|e| error(6, e))));
+
+		Box::new(read.boxed().compat())
+	}
+
+	fn check_proof(&self, items: Vec<DemoKey>, proof: DemoProof) -> RpcResult<Vec<DemoValue>> {
+		let header = self.client.header(BlockId::Hash(proof.at))
+			.map_err(|e| error(4, format!("Failed to read header: {}", e)))?
+			.ok_or_else(|| error(4, format!("Header of {} not found", proof.at)))?;
+		let canonical = self.client.hash(*header.number())
+			.map_err(|e| error(4, format!("Failed to read header: {}", e)))?;
+		if *header.number() > self.client.info().finalized_number || canonical != Some(proof.at) {
+			return Err(error(7, format!("{} is not a finalized block", proof.at)));
+		}
+
+		verify(*header.state_root(), proof.proof, &items).map_err(|e| error(6, e))
+	}
+}
@@ -0,0 +1,262 @@
+//! The `runtime-upgrade` subcommand: propose a `set_code` to the council and submit it to a node.
+
+use std::fs;
+use std::path::PathBuf;
+use codec::Decode;
+use futures01::{Future, Stream};
+use hyper::{Body, Client, Request, client::HttpConnector, header::CONTENT_TYPE};
+use log::info;
+use sc_cli::{SharedParams, GetSharedParams, error};
+use sc_executor::WasmExecutionMethod;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sp_core::{Bytes, Pair, sr25519};
+use sp_state_machine::BasicExternalities;
+use sp_version::RuntimeVersion;
+use structopt::StructOpt;
+use substrate_demo_client::{self as client, SigningParams};
+use substrate_demo_runtime::{Hash, VERSION};
+
+/// Heap pages the wasm blob is given to report its version.
+const HEAP_PAGES: u64 = 1024;
+
+/// The `runtime-upgrade` command.
+#[derive(Debug, StructOpt, Clone)]
+pub struct RuntimeUpgradeCmd {
+	/// The compiled runtime to upgrade to.
+	#[structopt(value_name = "WASM", parse(from_os_str))]
+	pub wasm: PathBuf,
+
+	/// Secret URI of a council member, or of the sudo key with `--sudo`, e.g. `//Alice`.
+	#[structopt(long = "suri", value_name = "SURI")]
+	pub suri: String,
+
+	/// Votes the motion needs, the proposer's included; two thirds of the council by default.
+	#[structopt(long = "threshold", value_name = "VOTES")]
+	pub threshold: Option<u32>,
+
+	/// Dispatch the upgrade with the sudo key instead, on chains that still have one.
+	#[structopt(long = "sudo")]
+	pub sudo: bool,
+
+	/// HTTP RPC endpoint of the node to read the chain's state from and submit to.
+	#[structopt(long = "rpc-url", value_name = "URL", default_value = "http://127.0.0.1:9933")]
+	pub rpc_url: String,
+
+	/// Write the hex-encoded extrinsic to this file instead of submitting it.
+	#[structopt(long = "output", value_name = "PATH", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl GetSharedParams for RuntimeUpgradeCmd {
+	fn shared_params(&self) -> Option<&SharedParams> {
+		Some(&self.shared_params)
+	}
+}
+
+/// The version a wasm runtime reports through `Core_version`.
+fn wasm_version(code: &[u8]) -> Result<RuntimeVersion, String> {
+	let mut ext = BasicExternalities::default();
+	let version = sc_executor::call_in_wasm::<_, sp_io::SubstrateHostFunctions>(
+		"Core_version",
+		&[],
+		WasmExecutionMethod::Interpreted,
+		&mut ext,
+		code,
+		HEAP_PAGES,
+	).map_err(|e| format!("The wasm blob is not a runtime: {}", e))?;
+
+	RuntimeVersion::decode(&mut &version[..])
+		.map_err(|e| format!("The wasm blob returned an invalid version: {}", e.what()))
+}
+
+/// Check that `new` can replace the runtime `current` of the chain.
+fn check_upgrade(current: &RuntimeVersion, new: &RuntimeVersion) -> Result<(), String> {
+	if new.spec_name != VERSION.spec_name {
+		return Err(format!("The wasm blob is a {} runtime, not {}", new.spec_name, VERSION.spec_name));
+	}
+	if current.spec_name != new.spec_name {
+		return Err(format!("The chain runs {}, not {}", current.spec_name, new.spec_name));
+	}
+	if new.spec_version <= current.spec_version {
+		return Err(format!(
+			"The wasm blob's spec_version {} must be greater than the chain's {}",
+			new.spec_version, current.spec_version,
+		));
+	}
+
+	Ok(())
+}
+
+/// The fewest votes of `members` that may dispatch as root.
+fn two_thirds(members: u32) -> u32 {
+	(2 * members + 2) / 3
+}
+
+/// Check that a motion passed with `threshold` votes of `members` may dispatch as root.
+fn check_threshold(threshold: u32, members: u32) -> Result<(), String> {
+	if threshold > members {
+		return Err(format!("The threshold {} is more than the {} council members", threshold, members));
+	}
+	if threshold < two_thirds(members) {
+		return Err(format!(
+			"A motion passed by {} of {} council members can not dispatch as root, it needs {}",
+			threshold, members, two_thirds(members),
+		));
+	}
+
+	Ok(())
+}
+
+/// A minimal JSON-RPC client over HTTP.
+struct Rpc {
+	url: String,
+	client: Client<HttpConnector>,
+	runtime: tokio::runtime::Runtime,
+	next_id: u64,
+}
+
+impl Rpc {
+	fn new(url: String) -> Result<Self, String> {
+		Ok(Rpc {
+			url,
+			client: Client::new(),
+			runtime: tokio::runtime::Runtime::new().map_err(|e| format!("{:?}", e))?,
+			next_id: 0,
+		})
+	}
+
+	fn call<T: DeserializeOwned>(&mut self, method: &str, params: Value) -> Result<T, String> {
+		self.next_id += 1;
+		let body = json!({ "jsonrpc": "2.0", "id": self.next_id, "method": method, "params": params });
+		let request = Request::post(self.url.as_str())
+			.header(CONTENT_TYPE, "application/json")
+			.body(Body::from(body.to_string()))
+			.map_err(|e| format!("Invalid RPC URL {}: {}", self.url, e))?;
+
+		let response = self.runtime
+			.block_on(self.client.request(request).and_then(|res| res.into_body().concat2()))
+			.map_err(|e| format!("{} to {} failed: {}", method, self.url, e))?;
+		let mut response: Value = serde_json::from_slice(&response)
+			.map_err(|e| format!("{} returned invalid JSON: {}", method, e))?;
+		if let Some(error) = response.get("error") {
+			return Err(format!("{} failed: {}", method, error));
+		}
+
+		serde_json::from_value(response["result"].take())
+			.map_err(|e| format!("{} returned an unexpected result: {}", method, e))
+	}
+}
+
+impl RuntimeUpgradeCmd {
+	/// Check the wasm blob against the chain, then sign and submit or write the upgrade.
+	pub fn run(self) -> error::Result<()> {
+		let code = fs::read(&self.wasm)?;
+		let version = wasm_version(&code)?;
+
+		let mut rpc = Rpc::new(self.rpc_url.clone())?;
+		let current: RuntimeVersion = rpc.call("state_getRuntimeVersion", json!([]))?;
+		check_upgrade(&current, &version)?;
+		info!(
+			"Upgrading {} from spec_version {} to {}",
+			current.spec_name, current.spec_version, version.spec_version,
+		);
+
+		let pair = sr25519::Pair::from_string(&self.suri, None)
+			.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+		let account = client::account_id(&pair);
+		let genesis_hash: Hash = rpc.call("chain_getBlockHash", json!([0]))?;
+		let nonce: Option<Bytes> = rpc.call("state_getStorage", json!([Bytes(client::nonce_key(&account))]))?;
+		let nonce = client::decode_nonce(nonce.as_ref().map(|n| &n[..]))
+			.map_err(|e| format!("Invalid nonce of {}: {}", account, e.what()))?;
+
+		// `CheckVersion` is checked against the runtime that is replaced, not the new one
+		let params = SigningParams {
+			spec_version: current.spec_version,
+			..SigningParams::immortal(genesis_hash, nonce)
+		};
+		let set_code = client::system::set_code(code);
+		let (call, motion) = if self.sudo {
+			(client::sudo::sudo(set_code), None)
+		} else {
+			let members: Option<Bytes> = rpc.call("state_getStorage", json!([Bytes(client::council_members_key())]))?;
+			let members = client::decode_council_members(members.as_ref().map(|m| &m[..]))
+				.map_err(|e| format!("Invalid council members: {}", e.what()))?;
+			if !members.contains(&account) {
+				return Err(format!("{} is not a council member", account).into());
+			}
+			let threshold = self.threshold.unwrap_or_else(|| two_thirds(members.len() as u32));
+			check_threshold(threshold, members.len() as u32)?;
+
+			let proposal = client::governance::dispatch_as_root(set_code);
+			let hash = client::council::proposal_hash(&proposal);
+			(client::council::propose(threshold, proposal), Some((hash, threshold)))
+		};
+		let extrinsic = Bytes(client::sign_and_encode(&pair, call, &params));
+
+		match self.output {
+			Some(ref path) => {
+				fs::write(path, serde_json::to_string(&extrinsic).expect("bytes serialize; qed"))?;
+				info!("Wrote the signed upgrade to {}", path.display());
+			},
+			None => {
+				let hash: Hash = rpc.call("author_submitExtrinsic", json!([extrinsic]))?;
+				info!("Submitted the upgrade as {:?}", hash);
+			},
+		}
+		if let Some((hash, threshold)) = motion {
+			info!(
+				"The upgrade is motion {:?}; it is dispatched once {} council members voted for it",
+				hash, threshold,
+			);
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::borrow::Cow;
+
+	fn version(spec_name: &'static str, spec_version: u32) -> RuntimeVersion {
+		RuntimeVersion {
+			spec_name: Cow::Borrowed(spec_name),
+			spec_version,
+			..VERSION
+		}
+	}
+
+	#[test]
+	fn newer_spec_version_is_accepted() {
+		assert_eq!(check_upgrade(&version("substrate-demo", 1), &version("substrate-demo", 2)), Ok(()));
+	}
+
+	#[test]
+	fn same_or_older_spec_version_is_rejected() {
+		assert!(check_upgrade(&version("substrate-demo", 2), &version("substrate-demo", 2)).is_err());
+		assert!(check_upgrade(&version("substrate-demo", 2), &version("substrate-demo", 1)).is_err());
+	}
+
+	#[test]
+	fn motions_need_two_thirds_of_the_council() {
+		assert_eq!(two_thirds(1), 1);
+		assert_eq!(two_thirds(3), 2);
+		assert_eq!(two_thirds(4), 3);
+		assert_eq!(check_threshold(2, 3), Ok(()));
+		assert_eq!(check_threshold(3, 3), Ok(()));
+		assert!(check_threshold(1, 3).is_err());
+		assert!(check_threshold(4, 3).is_err());
+	}
+
+	#[test]
+	fn other_runtimes_are_rejected() {
+		assert!(check_upgrade(&version("substrate-demo", 1), &version("node-template", 2)).is_err());
+		assert!(check_upgrade(&version("node-template", 1), &version("substrate-demo", 2)).is_err());
+	}
+}
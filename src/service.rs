@@ -1,5 +1,7 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use sc_client::LongestChain;
@@ -7,11 +9,15 @@ use substrate_demo_runtime::{self, GenesisConfig, opaque::Block, RuntimeApi};
 use sc_service::{error::{Error as ServiceError}, AbstractService, Configuration, ServiceBuilder};
 use sp_inherents::InherentDataProviders;
 use sc_network::{construct_simple_protocol};
+use sc_network_gossip::GossipEngine;
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
 use grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider};
 use sc_basic_authority;
+use pallet_contracts_rpc::{Contracts, ContractsApi};
+use crate::{gossip, indexer, metrics, rpc};
+use crate::shutdown::Tasks;
 
 // Our native executor instance.
 native_executor_instance!(
@@ -20,8 +26,21 @@ native_executor_instance!(
 	substrate_demo_runtime::native_version,
 );
 
+/// Node-specific configuration, carried in `Configuration::custom`.
+#[derive(Default)]
+pub struct NodeConfig {
+	/// Port of the Prometheus metrics endpoint, if enabled.
+	pub prometheus_port: Option<u16>,
+	/// SQLite database the demo modules' events are indexed into, if enabled.
+	pub indexer_db: Option<PathBuf>,
+	/// Named node tasks, stopped and reported on during shutdown.
+	pub tasks: Tasks,
+}
+
 construct_simple_protocol! {
 	/// Demo protocol attachment for substrate.
+	///
+	/// Application messages are gossiped through its consensus gossip, see `gossip::ENGINE_ID`.
 	pub struct NodeProtocol where Block = Block { }
 }
 
@@ -62,7 +81,7 @@ macro_rules! new_full_start {
 
 				let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _>(
 					sc_consensus_aura::SlotDuration::get_or_compute(&*client)?,
-					aura_block_import,
+					crate::metrics::MeteredBlockImport::new(aura_block_import),
 					Some(Box::new(grandpa_block_import.clone())),
 					None,
 					client,
@@ -80,10 +99,13 @@ macro_rules! new_full_start {
 }
 
 /// Builds a new service for a full client.
-pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisConfig>)
-	-> Result<impl AbstractService, ServiceError>
+pub fn new_full(config: Configuration<NodeConfig, GenesisConfig>)
+	-> Result<impl AbstractService<Block = Block>, ServiceError>
 {
 	let is_authority = config.roles.is_authority();
+	let prometheus_port = config.custom.prometheus_port;
+	let indexer_db = config.custom.indexer_db.clone();
+	let tasks = config.custom.tasks.clone();
 	let force_authoring = config.force_authoring;
 	let name = config.name.clone();
 	let disable_grandpa = config.disable_grandpa;
@@ -99,12 +121,62 @@ pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisCon
 		import_setup.take()
 			.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
 
-	let service = builder.with_network_protocol(|_| Ok(NodeProtocol::new()))?
+	let mut gossip_setup = None;
+
+	let service = builder
+		.with_rpc_extensions(|client, _pool, _backend, _fetcher, _remote_blockchain|
+			-> Result<rpc::IoHandler, ServiceError>
+		{
+			let validator = Arc::new(gossip::GossipValidator::new(client.clone()));
+			let (announcements, outgoing) = gossip::Announcements::new(validator.clone());
+
+			let mut io = rpc::IoHandler::default();
+			io.extend_with(rpc::AnnounceApi::to_delegate(rpc::Announce::new(announcements.clone())));
+			io.extend_with(ContractsApi::to_delegate(Contracts::new(client.clone())));
+			io.extend_with(rpc::DemoProofApi::to_delegate(rpc::FullDemoProof::new(client)));
+
+			gossip_setup = Some((validator, announcements, outgoing));
+
+			Ok(io)
+		})?
+		.with_network_protocol(|_| Ok(NodeProtocol::new()))?
 		.with_finality_proof_provider(|client, backend|
 			Ok(Arc::new(GrandpaFinalityProofProvider::new(backend, client)) as _)
 		)?
 		.build()?;
 
+	let (gossip_validator, announcements, outgoing) = gossip_setup.take()
+		.expect("RPC extensions are set up when the service is built. qed");
+	let gossip_engine = GossipEngine::new(
+		service.network(),
+		&service.spawn_task_handle(),
+		gossip::ENGINE_ID,
+		gossip_validator,
+	);
+	service.spawn_task(tasks.track(
+		"announcement-gossip",
+		gossip::run(gossip_engine, announcements, outgoing),
+	));
+
+	if let Some(port) = prometheus_port {
+		let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port);
+		let server = metrics::serve(addr)
+			.map_err(|e| format!("Failed to start Prometheus endpoint on {}: {}", addr, e))?;
+		service.spawn_task(tasks.track("prometheus", server));
+		service.spawn_task(tasks.track("metrics-chain", metrics::refresh_chain_gauges(
+			service.client(),
+			service.transaction_pool(),
+		)));
+		service.spawn_task(tasks.track("metrics-network", metrics::refresh_peer_count(
+			service.network_status(Duration::from_millis(5000)),
+		)));
+	}
+
+	if let Some(path) = indexer_db {
+		let indexer = indexer::run(service.client(), &path)?;
+		service.spawn_task(tasks.track("indexer", indexer));
+	}
+
 	if participates_in_consensus {
 		let proposer = sc_basic_authority::ProposerFactory {
 			client: service.client(),
@@ -133,7 +205,7 @@ pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisCon
 
 		// the AURA authoring task is considered essential, i.e. if it
 		// fails we take down the service with it.
-		service.spawn_essential_task(aura);
+		service.spawn_essential_task(tasks.track_authoring("aura", aura));
 	}
 
 	// if the node isn't actively participating in consensus then it doesn't
@@ -157,13 +229,13 @@ pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisCon
 	match (is_authority, disable_grandpa) {
 		(false, false) => {
 			// start the lightweight GRANDPA observer
-			service.spawn_task(grandpa::run_grandpa_observer(
+			service.spawn_task(tasks.track("grandpa-observer", grandpa::run_grandpa_observer(
 				grandpa_config,
 				grandpa_link,
 				service.network(),
 				service.on_exit(),
 				service.spawn_task_handle(),
-			)?);
+			)?));
 		},
 		(true, false) => {
 			// start the full GRANDPA voter
@@ -180,7 +252,9 @@ pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisCon
 
 			// the GRANDPA voter task is considered infallible, i.e.
 			// if it fails we take down the service with it.
-			service.spawn_essential_task(grandpa::run_grandpa_voter(voter_config)?);
+			service.spawn_essential_task(
+				tasks.track("grandpa-voter", grandpa::run_grandpa_voter(voter_config)?)
+			);
 		},
 		(_, true) => {
 			grandpa::setup_disabled_grandpa(
@@ -196,7 +270,7 @@ pub fn new_full<C: Send + Default + 'static>(config: Configuration<C, GenesisCon
 
 /// Builds a new service for a light client.
 pub fn new_light<C: Send + Default + 'static>(config: Configuration<C, GenesisConfig>)
-	-> Result<impl AbstractService, ServiceError>
+	-> Result<impl AbstractService<Block = Block>, ServiceError>
 {
 	let inherent_data_providers = InherentDataProviders::new();
 
@@ -236,6 +310,17 @@ pub fn new_light<C: Send + Default + 'static>(config: Configuration<C, GenesisCo
 
 			Ok((import_queue, finality_proof_request_builder))
 		})?
+		.with_rpc_extensions(|client, _pool, _backend, fetcher, _remote_blockchain|
+			-> Result<rpc::IoHandler, ServiceError>
+		{
+			let fetcher = fetcher
+				.ok_or_else(|| "Trying to start node RPC without active fetcher")?;
+
+			let mut io = rpc::IoHandler::default();
+			io.extend_with(rpc::DemoLightApi::to_delegate(rpc::LightDemoRead::new(client, fetcher)));
+
+			Ok(io)
+		})?
 		.with_network_protocol(|_| Ok(NodeProtocol::new()))?
 		.with_finality_proof_provider(|client, backend|
 			Ok(Arc::new(GrandpaFinalityProofProvider::new(backend, client)) as _)
@@ -0,0 +1,117 @@
+//! Bounded shutdown of the node.
+//!
+//! The process exits with `EXIT_CLEAN` when every task stopped in time, `EXIT_ERROR` when the
+//! service failed, and `EXIT_TIMEOUT` when tasks were still running after the shutdown timeout.
+
+use std::collections::HashMap;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+use futures::{channel::oneshot, future::select, Future, FutureExt};
+use futures01::Future as Future01;
+use log::warn;
+use parking_lot::Mutex;
+use tokio::runtime::Runtime;
+
+/// Exit code of a clean shutdown.
+pub const EXIT_CLEAN: i32 = 0;
+/// Exit code when the service stopped with an error.
+pub const EXIT_ERROR: i32 = 1;
+/// Exit code when tasks did not stop within the shutdown timeout.
+pub const EXIT_TIMEOUT: i32 = 2;
+
+#[derive(Default)]
+struct Inner {
+	next_id: u64,
+	running: HashMap<u64, &'static str>,
+	authoring: Vec<oneshot::Sender<()>>,
+}
+
+/// Named tasks spawned by the node, so that shutdown can stop block authoring first and
+/// report the tasks that failed to stop.
+#[derive(Clone, Default)]
+pub struct Tasks {
+	inner: Arc<Mutex<Inner>>,
+}
+
+impl Tasks {
+	/// Track `task` under `name` until it completes or is dropped.
+	pub fn track<F>(&self, name: &'static str, task: F) -> impl Future<Output = ()> + Send
+	where
+		F: Future<Output = ()> + Send,
+	{
+		let guard = self.register(name);
+		task.map(move |()| drop(guard))
+	}
+
+	/// Track an authoring task. It is stopped by `stop_authoring`, before the rest of the service.
+	pub fn track_authoring<F>(&self, name: &'static str, task: F) -> impl Future<Output = ()> + Send
+	where
+		F: Future<Output = ()> + Send + 'static,
+	{
+		let (stop, stopped) = oneshot::channel();
+		self.inner.lock().authoring.push(stop);
+
+		let guard = self.register(name);
+		select(Box::pin(task), stopped).map(move |_| drop(guard))
+	}
+
+	/// Signal all authoring tasks to stop. Returns the number of tasks signalled.
+	pub fn stop_authoring(&self) -> usize {
+		let authoring = std::mem::replace(&mut self.inner.lock().authoring, Vec::new());
+		authoring.into_iter().filter(|stop| !stop.is_canceled()).map(|stop| stop.send(())).count()
+	}
+
+	/// Names of the tracked tasks that are still running.
+	pub fn running(&self) -> Vec<&'static str> {
+		let mut running: Vec<_> = self.inner.lock().running.values().cloned().collect();
+		running.sort();
+		running
+	}
+
+	fn register(&self, name: &'static str) -> TaskGuard {
+		let mut inner = self.inner.lock();
+		let id = inner.next_id;
+		inner.next_id += 1;
+		inner.running.insert(id, name);
+
+		TaskGuard { id, inner: self.inner.clone() }
+	}
+}
+
+struct TaskGuard {
+	id: u64,
+	inner: Arc<Mutex<Inner>>,
+}
+
+impl Drop for TaskGuard {
+	fn drop(&mut self) {
+		self.inner.lock().running.remove(&self.id);
+	}
+}
+
+/// Wait up to `timeout` for all tasks on `runtime` to finish.
+///
+/// Returns `false` and logs the tracked tasks still running if the timeout elapsed.
+pub fn wait_for_idle(runtime: Runtime, timeout: Duration, tasks: &Tasks) -> bool {
+	let (done_send, done) = mpsc::channel();
+	thread::spawn(move || {
+		let _ = runtime.shutdown_on_idle().wait();
+		let _ = done_send.send(());
+	});
+
+	if done.recv_timeout(timeout).is_ok() {
+		return true;
+	}
+
+	warn!("Shutdown did not complete within {}s", timeout.as_secs());
+	let running = tasks.running();
+	if running.is_empty() {
+		warn!("All node tasks stopped; a task internal to the service is stuck");
+	}
+	for name in running {
+		warn!("Task `{}` failed to stop", name);
+	}
+
+	false
+}
@@ -0,0 +1,307 @@
+//! The `testnet` subcommand: a local multi-validator network of child processes.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use futures::FutureExt;
+use log::{info, warn};
+use sc_cli::{IntoExit, SharedParams, GetSharedParams, error};
+use sc_keystore::Store as Keystore;
+use sp_core::{Pair, crypto::key_types, ed25519, sr25519};
+use sp_runtime::{MultiSigner, traits::IdentifyAccount};
+use substrate_demo_runtime::AccountId;
+use sp_consensus_aura::sr25519::{AuthorityId as AuraId};
+use grandpa_primitives::{AuthorityId as GrandpaId};
+use structopt::StructOpt;
+use crate::chain_spec::{self, ChainSpec, get_account_id_from_seed};
+
+const CHAIN_ID: &str = "testnet";
+
+/// The `testnet` command.
+#[derive(Debug, StructOpt, Clone)]
+pub struct TestnetCmd {
+	/// Number of validators to run.
+	#[structopt(long = "validators", value_name = "COUNT", default_value = "2")]
+	pub validators: u16,
+
+	/// P2P port of the first node; node `i` listens on `base-port + i`.
+	#[structopt(long = "base-port", value_name = "PORT", default_value = "30333")]
+	pub base_port: u16,
+
+	/// RPC port of the first node; node `i` listens on `rpc-port + i`.
+	#[structopt(long = "rpc-port", value_name = "PORT", default_value = "9933")]
+	pub rpc_port: u16,
+
+	/// WebSockets RPC port of the first node; node `i` listens on `ws-port + i`.
+	#[structopt(long = "ws-port", value_name = "PORT", default_value = "9944")]
+	pub ws_port: u16,
+
+	/// Only write the keys and the raw chain spec, do not start the nodes.
+	#[structopt(long = "spec-only")]
+	pub spec_only: bool,
+
+	/// Seconds to wait for the nodes to stop before killing them.
+	#[structopt(long = "shutdown-timeout", value_name = "SECS", default_value = "10")]
+	pub shutdown_timeout: u64,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl GetSharedParams for TestnetCmd {
+	fn shared_params(&self) -> Option<&SharedParams> {
+		Some(&self.shared_params)
+	}
+}
+
+/// A validator of the test network.
+struct Validator {
+	/// Secret phrase its session keys are derived from.
+	phrase: String,
+	/// Secret of its libp2p identity, hex encoded as `--node-key` expects it.
+	node_key: String,
+	/// Its libp2p peer id.
+	peer_id: String,
+	base_path: PathBuf,
+	port: u16,
+	rpc_port: u16,
+	ws_port: u16,
+}
+
+impl Validator {
+	fn generate(index: u16, cmd: &TestnetCmd, base_path: &Path) -> error::Result<Self> {
+		let port = |flag: &str, base: u16| base.checked_add(index)
+			.ok_or_else(|| format!("{} {} leaves no port for node {}", flag, base, index));
+		let (port, rpc_port, ws_port) = (
+			port("--base-port", cmd.base_port)?,
+			port("--rpc-port", cmd.rpc_port)?,
+			port("--ws-port", cmd.ws_port)?,
+		);
+
+		let (_, phrase, _) = sr25519::Pair::generate_with_phrase(None);
+		let (_, mut node_secret) = ed25519::Pair::generate();
+		let node_key = hex::encode(&node_secret);
+		let secret = libp2p::identity::ed25519::SecretKey::from_bytes(&mut node_secret)
+			.expect("a 32 byte seed is a valid ed25519 secret; qed");
+		let public = libp2p::identity::ed25519::Keypair::from(secret).public();
+		let peer_id = libp2p::identity::PublicKey::Ed25519(public).into_peer_id().to_base58();
+
+		Ok(Validator {
+			phrase,
+			node_key,
+			peer_id,
+			base_path: base_path.join(format!("node-{}", index)),
+			port,
+			rpc_port,
+			ws_port,
+		})
+	}
+
+	fn authority_keys(&self) -> (AuraId, GrandpaId) {
+		let aura = sr25519::Pair::from_phrase(&self.phrase, None)
+			.expect("generated phrases are valid; qed").0.public();
+		let grandpa = ed25519::Pair::from_phrase(&self.phrase, None)
+			.expect("generated phrases are valid; qed").0.public();
+
+		(aura.into(), grandpa.into())
+	}
+
+	fn account(&self) -> AccountId {
+		let public = sr25519::Pair::from_phrase(&self.phrase, None)
+			.expect("generated phrases are valid; qed").0.public();
+
+		MultiSigner::from(public).into_account()
+	}
+
+	fn boot_node(&self) -> String {
+		format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", self.port, self.peer_id)
+	}
+
+	/// Put the session keys into the keystore the node will open.
+	fn insert_keys(&self) -> error::Result<()> {
+		let path = self.base_path.join("chains").join(CHAIN_ID).join("keystore");
+		let keystore = Keystore::open(path, None)
+			.map_err(|e| format!("Failed to open keystore: {:?}", e))?;
+		let (aura, grandpa) = self.authority_keys();
+		let mut keystore = keystore.write();
+		keystore.insert_unknown(key_types::AURA, &self.phrase, aura.as_ref())
+			.map_err(|e| format!("Failed to insert Aura key: {:?}", e))?;
+		keystore.insert_unknown(key_types::GRANDPA, &self.phrase, grandpa.as_ref())
+			.map_err(|e| format!("Failed to insert GRANDPA key: {:?}", e))?;
+
+		Ok(())
+	}
+}
+
+fn testnet_spec(validators: &[Validator]) -> ChainSpec {
+	let authorities = validators.iter()
+		.map(|validator| {
+			let (aura, grandpa) = validator.authority_keys();
+			(validator.account(), aura, grandpa)
+		})
+		.collect::<Vec<_>>();
+	let accounts = validators.iter().map(Validator::account).collect::<Vec<_>>();
+	let boot_nodes = validators.iter().map(Validator::boot_node).collect();
+
+	ChainSpec::from_genesis(
+		"Testnet",
+		CHAIN_ID,
+		move || chain_spec::testnet_genesis(
+			authorities.clone(),
+			vec![get_account_id_from_seed::<sr25519::Public>("Alice")],
+			chain_spec::endow(["Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie"].iter()
+				.map(|seed| get_account_id_from_seed::<sr25519::Public>(seed))
+				.chain(accounts.iter().cloned())
+				.collect()),
+			vec![],
+			true,
+		),
+		boot_nodes,
+		None,
+		None,
+		None,
+		None,
+	)
+}
+
+impl TestnetCmd {
+	/// Generate the keys and chain spec, then run the nodes until `exit` fires or one of them stops.
+	pub fn run<E: IntoExit>(self, exit: E) -> error::Result<()> {
+		if self.validators == 0 {
+			return Err("A testnet needs at least one validator".into());
+		}
+
+		// without `--base-path` each run gets a directory of its own, removed when it stops
+		let (base_path, temporary) = match self.shared_params.base_path.clone() {
+			Some(path) => (path, false),
+			None => (std::env::temp_dir().join(format!("substrate-demo-testnet-{}", std::process::id())), true),
+		};
+		let validators = (0..self.validators)
+			.map(|i| Validator::generate(i, &self, &base_path))
+			.collect::<error::Result<Vec<_>>>()?;
+
+		fs::create_dir_all(&base_path)?;
+		let spec_path = base_path.join("testnet-raw.json");
+		fs::write(&spec_path, testnet_spec(&validators).to_json(true)?)?;
+		info!("Wrote raw chain spec to {}", spec_path.display());
+
+		for (i, validator) in validators.iter().enumerate() {
+			// a database or keystore left from an earlier run belongs to another chain
+			if validator.base_path.exists() {
+				fs::remove_dir_all(&validator.base_path)?;
+			}
+			validator.insert_keys()?;
+			info!("Validator {}: {} ({})", i, validator.peer_id, validator.base_path.display());
+		}
+
+		if self.spec_only {
+			return Ok(());
+		}
+
+		let result = self.run_nodes(exit, &validators, &spec_path);
+		if temporary {
+			if let Err(e) = fs::remove_dir_all(&base_path) {
+				warn!("Failed to remove {}: {}", base_path.display(), e);
+			}
+		}
+		result
+	}
+
+	/// Run a node for each of `validators` until `exit` fires or one of them stops.
+	fn run_nodes<E: IntoExit>(&self, exit: E, validators: &[Validator], spec_path: &Path) -> error::Result<()> {
+		let mut nodes = Vec::new();
+		for (i, validator) in validators.iter().enumerate() {
+			match self.spawn(i as u16, validator, spec_path) {
+				Ok(child) => nodes.push(child),
+				Err(e) => {
+					stop(nodes, Duration::from_secs(self.shutdown_timeout));
+					return Err(e);
+				},
+			}
+		}
+
+		let mut exit = exit.into_exit();
+		let stopped = loop {
+			if (&mut exit).now_or_never().is_some() {
+				break None;
+			}
+			if let Some(i) = nodes.iter_mut().position(|node| !is_running(node)) {
+				break Some(i);
+			}
+			thread::sleep(Duration::from_millis(500));
+		};
+
+		if let Some(i) = stopped {
+			warn!("Node {} stopped, tearing down the testnet", i);
+		}
+		stop(nodes, Duration::from_secs(self.shutdown_timeout));
+
+		match stopped {
+			Some(i) => Err(format!("Node {} stopped unexpectedly", i).into()),
+			None => Ok(()),
+		}
+	}
+
+	fn spawn(&self, index: u16, validator: &Validator, spec_path: &Path) -> error::Result<Child> {
+		let log_path = validator.base_path.join("node.log");
+		let log = File::create(&log_path)?;
+		let child = Command::new(std::env::current_exe()?)
+			.arg("--chain").arg(spec_path)
+			.arg("--base-path").arg(&validator.base_path)
+			.arg("--name").arg(format!("testnet-{}", index))
+			.arg("--node-key").arg(&validator.node_key)
+			.arg("--port").arg(validator.port.to_string())
+			.arg("--rpc-port").arg(validator.rpc_port.to_string())
+			.arg("--ws-port").arg(validator.ws_port.to_string())
+			.arg("--validator")
+			.stdout(Stdio::null())
+			.stderr(log)
+			.spawn()?;
+
+		info!(
+			"Started node {} (pid {}), RPC on port {}, logs in {}",
+			index, child.id(), validator.rpc_port, log_path.display(),
+		);
+
+		Ok(child)
+	}
+}
+
+/// Ask every node to stop and kill the ones still running after `timeout`.
+fn stop(mut nodes: Vec<Child>, timeout: Duration) {
+	for node in &nodes {
+		terminate(node);
+	}
+
+	let deadline = Instant::now() + timeout;
+	while Instant::now() < deadline && nodes.iter_mut().any(is_running) {
+		thread::sleep(Duration::from_millis(100));
+	}
+
+	for node in &mut nodes {
+		if is_running(node) {
+			warn!("Node (pid {}) did not stop in time, killing it", node.id());
+			let _ = node.kill();
+			let _ = node.wait();
+		}
+	}
+}
+
+fn is_running(node: &mut Child) -> bool {
+	match node.try_wait() {
+		Ok(None) => true,
+		_ => false,
+	}
+}
+
+#[cfg(unix)]
+fn terminate(node: &Child) {
+	// SIGTERM gives the node a chance to shut down cleanly
+	unsafe { libc::kill(node.id() as libc::pid_t, libc::SIGTERM); }
+}
+
+#[cfg(not(unix))]
+fn terminate(_node: &Child) {}
@@ -0,0 +1,575 @@
+//! End-to-end tests of the demo modules against an in-process `--dev` node.
+//!
+//! Each test starts a full node on the development chain, with Alice as its only authority. It
+//! signs extrinsics with `substrate-demo-client`, which uses the runtime's real `SignedExtra`, and
+//! waits for them to be included and finalized before checking the events they deposited and the
+//! resulting storage. Blocks are executed both natively and in wasm, so the two diverging fails
+//! the test too.
+//!
+//! `light_node_*` also starts a light node connected to the full node, and reads through the RPC
+//! servers of both over HTTP.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use codec::{Decode, Encode};
+use futures::{
+	channel::oneshot, compat::Future01CompatExt, executor::{block_on, block_on_stream}, future::select,
+	FutureExt, TryFutureExt,
+};
+use sc_client::{CallExecutor, Client};
+use sc_client_api::{BlockchainEvents, execution_extensions::ExecutionStrategies};
+use sc_network::PeerId;
+use sc_service::{AbstractService, Configuration, DatabaseConfig, Roles};
+use serde_json::{json, Value};
+use sp_core::storage::StorageKey;
+use sp_keyring::AccountKeyring;
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+use sp_state_machine::ExecutionStrategy;
+use sp_transaction_pool::TransactionPool;
+use substrate_demo::{chain_spec::Alternative, service::{self, NodeConfig}};
+use substrate_demo_client::{self as client, Call, SigningParams};
+use substrate_demo_runtime::{
+	opaque::{Block, UncheckedExtrinsic as OpaqueExtrinsic}, AccountId, BlockNumber, Event, EventRecord,
+	GenesisConfig, Hash, Phase, linked_map, simple_event, simple_map, single_value, storage_keys, vec_set,
+};
+
+/// How long an extrinsic may take to be included and finalized.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+type NodeClient<S> = Client<
+	<S as AbstractService>::Backend,
+	<S as AbstractService>::CallExecutor,
+	Block,
+	<S as AbstractService>::RuntimeApi,
+>;
+
+/// The database and keystore directory of a node, removed once the node is gone.
+struct BasePath(PathBuf);
+
+impl BasePath {
+	fn new(test: &str) -> Self {
+		let path = std::env::temp_dir()
+			.join(format!("substrate-demo-{}-{}", test, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		BasePath(path)
+	}
+}
+
+impl Drop for BasePath {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.0);
+	}
+}
+
+/// An extrinsic that made it into a finalized block.
+struct Included {
+	/// Number of the block it was included in.
+	number: BlockNumber,
+	/// Hash of that block.
+	hash: Hash,
+	/// Events deposited while applying it, the last being `ExtrinsicSuccess` or `ExtrinsicFailed`.
+	events: Vec<Event>,
+}
+
+impl Included {
+	fn succeeded(&self) -> bool {
+		match self.events.last() {
+			Some(Event::system(system::Event::ExtrinsicSuccess(_))) => true,
+			_ => false,
+		}
+	}
+}
+
+/// A running development node.
+struct DevNode<S: AbstractService<Block = Block>> {
+	client: Arc<NodeClient<S>>,
+	pool: Arc<S::TransactionPool>,
+	peer_id: PeerId,
+	stop: Option<oneshot::Sender<()>>,
+	_runtime: tokio::runtime::Runtime,
+	// removed only after the runtime has dropped the service and closed the database
+	_base_path: BasePath,
+}
+
+/// The configuration of a node of `test` on the dev chain, without networking.
+fn config<C: Default>(test: &str, base_path: &BasePath) -> Configuration<C, GenesisConfig> {
+	let spec = Alternative::Development.load().expect("the dev chain spec is valid");
+
+	let mut config = Configuration::<C, GenesisConfig>::default_with_spec_and_base_path(
+		spec,
+		Some(base_path.0.clone()),
+	);
+	config.name = format!("test-{}", test);
+	config.keystore_path = Some(base_path.0.join("keystore"));
+	config.database = DatabaseConfig::Path {
+		path: base_path.0.join("db"),
+		cache_size: None,
+	};
+	config.network.listen_addresses = Vec::new();
+	config.network.enable_mdns = false;
+	config.execution_strategies = ExecutionStrategies {
+		importing: ExecutionStrategy::Both,
+		block_construction: ExecutionStrategy::Both,
+		other: ExecutionStrategy::Both,
+		..Default::default()
+	};
+	config
+}
+
+/// Run `service` on a runtime of its own until the returned sender is used or dropped.
+fn spawn(service: impl AbstractService) -> (tokio::runtime::Runtime, oneshot::Sender<()>) {
+	let (stop, stopped) = oneshot::channel();
+	let runtime = tokio::runtime::Runtime::new().expect("a tokio runtime can be created");
+	let service = service.compat().map(|res| if let Err(e) = res {
+		panic!("Node failed: {:?}", e);
+	});
+	runtime.executor().spawn(select(service, stopped).map(|_| Ok::<(), ()>(())).compat());
+
+	(runtime, stop)
+}
+
+fn start(test: &str) -> DevNode<impl AbstractService<Block = Block>> {
+	start_with(test, |_| ())
+}
+
+/// Start a dev node with the changes `configure` makes to its configuration.
+fn start_with(
+	test: &str,
+	configure: impl FnOnce(&mut Configuration<NodeConfig, GenesisConfig>),
+) -> DevNode<impl AbstractService<Block = Block>> {
+	let base_path = BasePath::new(test);
+	let mut config = config(test, &base_path);
+	config.roles = Roles::AUTHORITY;
+	config.dev_key_seed = Some("//Alice".into());
+	config.force_authoring = true;
+	configure(&mut config);
+
+	let service = service::new_full(config).expect("the dev node starts");
+	let client = service.client();
+	let pool = service.transaction_pool();
+	let peer_id = service.network().local_peer_id().clone();
+	let (runtime, stop) = spawn(service);
+
+	DevNode {
+		client,
+		pool,
+		peer_id,
+		stop: Some(stop),
+		_runtime: runtime,
+		_base_path: base_path,
+	}
+}
+
+impl<S: AbstractService<Block = Block>> DevNode<S> {
+	fn sign(&self, signer: AccountKeyring, call: Call) -> Vec<u8> {
+		let info = self.client.info().chain;
+		let nonce = self.raw_storage(info.best_hash, client::nonce_key(&signer.to_account_id()));
+		let nonce = client::decode_nonce(nonce.as_ref().map(|n| &n[..])).expect("nonces decode");
+
+		client::sign_and_encode(&signer.pair(), call, &SigningParams::immortal(info.genesis_hash, nonce))
+	}
+
+	/// Submit `call` signed by `signer` and wait for the block including it to be finalized.
+	fn submit(&self, signer: AccountKeyring, call: Call) -> Included {
+		let description = format!("{:?}", call);
+		let xt = OpaqueExtrinsic(self.sign(signer, call));
+		// a block is authored every slot, so the deadline is checked at least that often
+		let deadline = Instant::now() + TIMEOUT;
+
+		let imports = block_on_stream(self.client.import_notification_stream());
+		let finality = block_on_stream(self.client.finality_notification_stream());
+
+		let best = self.client.info().chain.best_hash;
+		block_on(self.pool.submit_one(&BlockId::Hash(best), xt.clone()))
+			.unwrap_or_else(|e| panic!("{} was rejected by the pool: {:?}", description, e));
+
+		let client = &self.client;
+		let (hash, number, index) = imports
+			.take_while(|_| Instant::now() < deadline)
+			.filter(|notification| notification.is_new_best)
+			.find_map(|notification| {
+				let body = client.block_body(&BlockId::Hash(notification.hash)).ok()??;
+				let index = body.iter().position(|ext| *ext == xt)?;
+				Some((notification.hash, *notification.header.number(), index as u32))
+			})
+			.unwrap_or_else(|| panic!("{} was not included within {:?}", description, TIMEOUT));
+
+		finality
+			.take_while(|_| Instant::now() < deadline)
+			.find(|notification| *notification.header.number() >= number)
+			.unwrap_or_else(|| panic!("{} was not finalized within {:?}", description, TIMEOUT));
+		assert_eq!(
+			self.client.header(&BlockId::Number(number)).ok().and_then(|h| h.map(|h| h.hash())),
+			Some(hash),
+			"the block including {} was retracted",
+			description,
+		);
+
+		let events = self.call::<Vec<EventRecord<Event, Hash>>>(hash, "block_events")
+			.into_iter()
+			.filter(|record| record.phase == Phase::ApplyExtrinsic(index))
+			.map(|record| record.event)
+			.collect();
+
+		Included { number, hash, events }
+	}
+
+	/// Call a `DemoApi` function without arguments at block `at`.
+	fn call<R: Decode>(&self, at: Hash, function: &str) -> R {
+		self.call_with(at, function, &[])
+	}
+
+	/// Call a `DemoApi` function with the encoded arguments `args` at block `at`.
+	fn call_with<R: Decode>(&self, at: Hash, function: &str, args: &[u8]) -> R {
+		let result = self.client.executor().call(
+			&BlockId::Hash(at),
+			&format!("DemoApi_{}", function),
+			args,
+			ExecutionStrategy::Both,
+			None,
+		).unwrap_or_else(|e| panic!("DemoApi_{} failed: {}", function, e));
+
+		R::decode(&mut &result[..]).expect("runtime API results decode")
+	}
+
+	/// Read and decode the storage item at `key` at block `at`.
+	fn storage<R: Decode>(&self, at: Hash, key: Vec<u8>) -> Option<R> {
+		self.raw_storage(at, key).map(|data| R::decode(&mut &data[..]).expect("storage values decode"))
+	}
+
+	fn raw_storage(&self, at: Hash, key: Vec<u8>) -> Option<Vec<u8>> {
+		self.client.storage(&BlockId::Hash(at), &StorageKey(key))
+			.expect("storage is readable")
+			.map(|data| data.0)
+	}
+}
+
+impl<S: AbstractService<Block = Block>> Drop for DevNode<S> {
+	fn drop(&mut self) {
+		if let Some(stop) = self.stop.take() {
+			let _ = stop.send(());
+		}
+	}
+}
+
+/// A running light node, following a dev node.
+struct LightNode<S: AbstractService<Block = Block>> {
+	client: Arc<NodeClient<S>>,
+	/// Port of its HTTP RPC server.
+	rpc_port: u16,
+	stop: Option<oneshot::Sender<()>>,
+	_runtime: tokio::runtime::Runtime,
+	_base_path: BasePath,
+}
+
+/// Start a light node connected to `full`, which listens on `full_port`.
+fn start_light(
+	test: &str,
+	full: &DevNode<impl AbstractService<Block = Block>>,
+	full_port: u16,
+	rpc_port: u16,
+) -> LightNode<impl AbstractService<Block = Block>> {
+	let base_path = BasePath::new(test);
+	let mut config = config::<()>(test, &base_path);
+	config.roles = Roles::LIGHT;
+	config.network.boot_nodes = vec![format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", full_port, full.peer_id)];
+	config.rpc_http = Some(([127, 0, 0, 1], rpc_port).into());
+
+	let service = service::new_light(config).expect("the light node starts");
+	let client = service.client();
+	let (runtime, stop) = spawn(service);
+
+	LightNode {
+		client,
+		rpc_port,
+		stop: Some(stop),
+		_runtime: runtime,
+		_base_path: base_path,
+	}
+}
+
+impl<S: AbstractService<Block = Block>> Drop for LightNode<S> {
+	fn drop(&mut self) {
+		if let Some(stop) = self.stop.take() {
+			let _ = stop.send(());
+		}
+	}
+}
+
+/// Call `method` with `params` on the HTTP RPC server at `port`, returning its result or error.
+fn rpc(port: u16, method: &str, params: Value) -> Result<Value, Value> {
+	let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+	let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("the RPC server accepts connections");
+	write!(
+		stream,
+		"POST / HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nContent-Type: application/json\r\n\
+			Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+		port,
+		request.len(),
+		request,
+	).expect("RPC requests can be sent");
+
+	let mut response = String::new();
+	stream.read_to_string(&mut response).expect("RPC responses can be read");
+	let body = response.splitn(2, "\r\n\r\n").nth(1).expect("RPC responses have a body");
+	let mut response: Value = serde_json::from_str(body).expect("RPC responses are JSON");
+	match response.get("error") {
+		Some(error) => Err(error.clone()),
+		None => Ok(response["result"].take()),
+	}
+}
+
+/// Wait until `condition` holds, failing the test after `TIMEOUT`.
+fn wait_until(description: &str, condition: impl Fn() -> bool) {
+	let deadline = Instant::now() + TIMEOUT;
+	while !condition() {
+		assert!(Instant::now() < deadline, "did not {} within {:?}", description, TIMEOUT);
+		std::thread::sleep(Duration::from_millis(100));
+	}
+}
+
+fn account(keyring: AccountKeyring) -> AccountId {
+	keyring.to_account_id()
+}
+
+/// Gas each contract call may use.
+const GAS_LIMIT: u64 = 500_000;
+
+/// The wasm of the example contract `contracts/<name>.wat`.
+fn contract_code(name: &str) -> Vec<u8> {
+	let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("contracts").join(format!("{}.wat", name));
+	let wat = std::fs::read_to_string(&path).expect("the example contracts are readable");
+	wabt::wat2wasm(wat).expect("the example contracts compile")
+}
+
+impl<S: AbstractService<Block = Block>> DevNode<S> {
+	/// Store and instantiate the example contract `name` as Alice, returning the contract's account.
+	fn deploy(&self, name: &str) -> AccountId {
+		let stored = self.submit(AccountKeyring::Alice, client::contracts::put_code(GAS_LIMIT, contract_code(name)));
+		assert!(stored.succeeded());
+		let code_hash = stored.events.iter()
+			.find_map(|event| match event {
+				Event::contracts(contracts::RawEvent::CodeStored(hash)) => Some(*hash),
+				_ => None,
+			})
+			.expect("storing code emits its hash");
+
+		let instantiated = self.submit(
+			AccountKeyring::Alice,
+			client::contracts::instantiate(1_000_000_000, GAS_LIMIT, code_hash, vec![]),
+		);
+		assert!(instantiated.succeeded());
+		instantiated.events.iter()
+			.find_map(|event| match event {
+				Event::contracts(contracts::RawEvent::Instantiated(_, address)) => Some(address.clone()),
+				_ => None,
+			})
+			.expect("instantiating emits the contract's account")
+	}
+
+	/// Call the contract `address` with `data` as Alice, returning the data of the events it emitted.
+	fn call_contract(&self, address: &AccountId, data: Vec<u8>) -> Vec<Vec<u8>> {
+		let called = self.submit(AccountKeyring::Alice, client::contracts::call(address.clone(), 0, GAS_LIMIT, data));
+		assert!(called.succeeded());
+		called.events.into_iter()
+			.filter_map(|event| match event {
+				Event::contracts(contracts::RawEvent::Contract(ref from, ref data)) if from == address =>
+					Some(data.clone()),
+				_ => None,
+			})
+			.collect()
+	}
+}
+
+#[test]
+fn simple_event_emits_input() {
+	let node = start("simple-event");
+
+	let included = node.submit(AccountKeyring::Alice, client::simple_event::set_value(42));
+
+	assert!(included.succeeded());
+	assert_eq!(
+		included.events[0],
+		Event::simple_event(simple_event::RawEvent::EmitInput(42, account(AccountKeyring::Alice))),
+	);
+}
+
+#[test]
+fn single_value_is_set_and_read() {
+	let node = start("single-value");
+
+	let set = node.submit(AccountKeyring::Alice, client::single_value::set_value(7));
+	assert!(set.succeeded());
+	assert_eq!(set.events[0], Event::single_value(single_value::RawEvent::ValueSet(7, set.number)));
+	assert_eq!(node.storage::<u32>(set.hash, storage_keys::my_value()), Some(7));
+
+	let get = node.submit(AccountKeyring::Bob, client::single_value::get_value());
+	assert!(get.succeeded());
+	assert_eq!(get.events[0], Event::single_value(single_value::RawEvent::ValueGet(7, get.number)));
+}
+
+#[test]
+fn vec_set_tracks_members() {
+	let node = start("vec-set");
+	let (alice, bob) = (account(AccountKeyring::Alice), account(AccountKeyring::Bob));
+
+	let added = node.submit(AccountKeyring::Alice, client::vec_set::add_member());
+	assert!(added.succeeded());
+	assert_eq!(added.events[0], Event::vec_set(vec_set::RawEvent::MemberAdded(alice.clone())));
+
+	let added = node.submit(AccountKeyring::Bob, client::vec_set::add_member());
+	assert!(added.succeeded());
+	assert_eq!(node.call::<u32>(added.hash, "member_count"), 2);
+
+	let removed = node.submit(AccountKeyring::Alice, client::vec_set::remove_member());
+	assert!(removed.succeeded());
+	assert_eq!(removed.events[0], Event::vec_set(vec_set::RawEvent::MemberRemoved(alice.clone())));
+	assert_eq!(node.call::<Vec<AccountId>>(removed.hash, "members"), vec![bob.clone()]);
+	assert_eq!(node.storage::<Vec<AccountId>>(removed.hash, storage_keys::members()), Some(vec![bob]));
+}
+
+#[test]
+fn vec_set_rejects_non_member_leaving() {
+	let node = start("vec-set-failure");
+
+	let removed = node.submit(AccountKeyring::Charlie, client::vec_set::remove_member());
+
+	// the fee is still paid and the nonce bumped, but the dispatch fails
+	assert!(!removed.succeeded());
+	assert_eq!(removed.events.len(), 1);
+	assert_eq!(node.call::<u32>(removed.hash, "member_count"), 0);
+}
+
+#[test]
+fn simple_map_entries_change() {
+	let node = start("simple-map");
+	let alice = account(AccountKeyring::Alice);
+
+	let set = node.submit(AccountKeyring::Alice, client::simple_map::set_single_entry(5));
+	assert!(set.succeeded());
+	assert_eq!(set.events[0], Event::simple_map(simple_map::RawEvent::EntrySet(alice.clone(), 5, 1)));
+
+	let increased = node.submit(AccountKeyring::Alice, client::simple_map::increase_single_entry(3));
+	assert!(increased.succeeded());
+	assert_eq!(increased.events[0], Event::simple_map(simple_map::RawEvent::IncreaseEntry(5, 8, 2)));
+
+	let swapped = node.submit(
+		AccountKeyring::Alice,
+		client::simple_map::compare_and_swap_single_entry(8, 13),
+	);
+	assert!(swapped.succeeded());
+	assert_eq!(swapped.events[0], Event::simple_map(simple_map::RawEvent::CAS(8, 13, 3)));
+	assert_eq!(node.storage::<u32>(swapped.hash, storage_keys::simple_map(&alice)), Some(13));
+	assert_eq!(node.call::<u32>(swapped.hash, "simple_map_entries"), 1);
+	assert_eq!(node.call_with::<Option<(u32, u64)>>(swapped.hash, "simple_map_entry", &alice.encode()), Some((13, 3)));
+
+	let stale = node.submit(AccountKeyring::Alice, client::simple_map::compare_version_and_swap(2, 21));
+	assert!(!stale.succeeded());
+	let swapped = node.submit(AccountKeyring::Alice, client::simple_map::compare_version_and_swap(3, 21));
+	assert!(swapped.succeeded());
+	assert_eq!(swapped.events[0], Event::simple_map(simple_map::RawEvent::CAS(13, 21, 4)));
+
+	let taken = node.submit(AccountKeyring::Alice, client::simple_map::take_single_entry());
+	assert!(taken.succeeded());
+	assert_eq!(taken.events[0], Event::simple_map(simple_map::RawEvent::EntryTook(alice.clone(), 21)));
+	assert_eq!(node.storage::<u32>(taken.hash, storage_keys::simple_map(&alice)), None);
+	assert_eq!(node.call::<u32>(taken.hash, "simple_map_entries"), 0);
+}
+
+#[test]
+fn linked_map_counts_members() {
+	let node = start("linked-map");
+	let bob = account(AccountKeyring::Bob);
+
+	let added = node.submit(AccountKeyring::Alice, client::linked_map::add_member(bob.clone()));
+	assert!(added.succeeded());
+	assert_eq!(added.events[0], Event::linked_map(linked_map::RawEvent::MemberAdded(bob.clone(), 1)));
+	assert_eq!(node.call::<u32>(added.hash, "the_counter"), 1);
+
+	let removed = node.submit(AccountKeyring::Alice, client::linked_map::remove_member_bunded(1));
+	assert!(removed.succeeded());
+	assert_eq!(removed.events[0], Event::linked_map(linked_map::RawEvent::MemberRemoved(bob, 1)));
+	assert_eq!(node.call::<u32>(removed.hash, "the_counter"), 0);
+}
+
+#[test]
+fn contracts_keep_state() {
+	let node = start("contracts");
+	let counter = node.deploy("counter");
+
+	assert_eq!(node.call_contract(&counter, vec![]), vec![1u32.encode()]);
+	assert_eq!(node.call_contract(&counter, vec![]), vec![2u32.encode()]);
+}
+
+#[test]
+fn contracts_read_demo_modules() {
+	let node = start("contracts-runtime-storage");
+	let alice = account(AccountKeyring::Alice);
+	let reader = node.deploy("runtime_storage");
+
+	assert!(node.submit(AccountKeyring::Alice, client::vec_set::add_member()).succeeded());
+	assert!(node.submit(AccountKeyring::Alice, client::simple_map::set_single_entry(5)).succeeded());
+
+	assert_eq!(node.call_contract(&reader, storage_keys::members()), vec![vec![alice.clone()].encode()]);
+	assert_eq!(node.call_contract(&reader, storage_keys::simple_map(&alice)), vec![5u32.encode()]);
+}
+
+/// Ports of `light_node_verifies_reads_of_finalized_blocks`, whose nodes talk over TCP.
+const FULL_P2P_PORT: u16 = 30_633;
+const FULL_RPC_PORT: u16 = 9_963;
+const LIGHT_RPC_PORT: u16 = 9_964;
+
+#[test]
+fn light_node_verifies_reads_of_finalized_blocks() {
+	let full = start_with("light-full", |config| {
+		config.network.listen_addresses = vec![
+			format!("/ip4/127.0.0.1/tcp/{}", FULL_P2P_PORT).parse().expect("the address is valid"),
+		];
+		config.rpc_http = Some(([127, 0, 0, 1], FULL_RPC_PORT).into());
+	});
+	let light = start_light("light", &full, FULL_P2P_PORT, LIGHT_RPC_PORT);
+	let items = json!(["myValue"]);
+
+	let set = full.submit(AccountKeyring::Alice, client::single_value::set_value(7));
+	assert!(set.succeeded());
+	wait_until("import the block setting the value", || light.client.info().chain.best_number >= set.number);
+
+	// proofs at the light node's finalized block check out, and reads return the same values
+	loop {
+		let finalized = light.client.info().chain.finalized_hash;
+		let expected = json!([{ "myValue": full.storage::<u32>(finalized, storage_keys::my_value()) }]);
+
+		let proof = rpc(FULL_RPC_PORT, "demo_storageProof", json!([items, finalized]))
+			.expect("full nodes prove storage");
+		assert_eq!(rpc(light.rpc_port, "demo_checkProof", json!([items, proof])), Ok(expected.clone()));
+
+		let read = rpc(light.rpc_port, "demo_verifiedRead", json!([items])).expect("light nodes read storage");
+		// a read racing with finality may be of the next finalized block
+		if light.client.info().chain.finalized_hash == finalized {
+			assert_eq!(read, expected);
+			break;
+		}
+	}
+
+	// proofs at blocks the light node imported but has not finalized are rejected
+	let rejected = || {
+		let info = light.client.info().chain;
+		if info.best_number <= info.finalized_number {
+			return false;
+		}
+
+		let proof = rpc(FULL_RPC_PORT, "demo_storageProof", json!([items, info.best_hash]))
+			.expect("full nodes prove storage");
+		let checked = rpc(light.rpc_port, "demo_checkProof", json!([items, proof]));
+		// nothing is learned if the block was finalized in the meantime
+		if light.client.info().chain.finalized_number >= info.best_number {
+			return false;
+		}
+		assert_eq!(checked.map_err(|e| e["code"].clone()), Err(json!(7)));
+		true
+	};
+	wait_until("check a proof at an unfinalized block", rejected);
+}